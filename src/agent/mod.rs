@@ -0,0 +1,336 @@
+//! Background agent that keeps `LocalStorage`'s encryption key decoded in
+//! memory, modeled on rbw's agent/socket design: a small daemon holds the
+//! cipher behind a Unix-domain socket so `clean`/`smudge` don't each have to
+//! re-derive it (re-reading `.whiteout/key`, or unwrapping the repo DEK from
+//! the keyring) per file.
+//!
+//! This intentionally builds the cipher the same way [`crate::Whiteout::new`]
+//! does, via [`Crypto::for_project`] -- every other command (`status`,
+//! `scan`, `clean-all`, ...) reaches storage directly through `Whiteout::new`,
+//! so the agent caching a different key would make entries it writes
+//! unreadable to those commands.
+
+pub mod prompt;
+
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::storage::crypto::Crypto;
+
+/// How long an unlocked cipher is kept in memory before it's wiped.
+pub fn default_idle_timeout() -> Duration {
+    std::env::var("WHITEOUT_AGENT_TIMEOUT")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(Duration::from_secs(15 * 60))
+}
+
+pub fn socket_path(project_root: &Path) -> PathBuf {
+    project_root.join(".whiteout").join("agent.sock")
+}
+
+enum Op {
+    Clean,
+    Smudge,
+    Lock,
+    Ping,
+}
+
+impl Op {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Op::Clean => "CLEAN",
+            Op::Smudge => "SMUDGE",
+            Op::Lock => "LOCK",
+            Op::Ping => "PING",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Op> {
+        match s {
+            "CLEAN" => Some(Op::Clean),
+            "SMUDGE" => Some(Op::Smudge),
+            "LOCK" => Some(Op::Lock),
+            "PING" => Some(Op::Ping),
+            _ => None,
+        }
+    }
+}
+
+/// Ensures a loose-permissioned socket is never trusted: the file must be
+/// owned by us and mode 0600, otherwise another local user could be running
+/// a malicious listener in its place.
+#[cfg(unix)]
+fn check_socket_permissions(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let metadata = fs::symlink_metadata(path)?;
+    let mode = metadata.permissions().mode() & 0o777;
+    if mode != 0o600 {
+        bail!(
+            "Refusing to use agent socket {:?} with loose permissions {:o} (expected 0600)",
+            path,
+            mode
+        );
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn check_socket_permissions(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+fn ping(socket: &Path) -> Result<()> {
+    check_socket_permissions(socket)?;
+    let stream = UnixStream::connect(socket)?;
+    write_frame(&stream, Op::Ping.as_str(), "", &[])?;
+    let (status, _) = read_frame(&stream)?;
+    if status == "PONG" {
+        Ok(())
+    } else {
+        bail!("Unexpected response from agent: {}", status)
+    }
+}
+
+/// Makes sure an agent for this project is listening, spawning one in the
+/// background if it isn't (or if a stale socket file is left over from a
+/// crashed instance).
+pub fn ensure_running(project_root: &Path) -> Result<()> {
+    let socket = socket_path(project_root);
+
+    if ping(&socket).is_ok() {
+        return Ok(());
+    }
+
+    if socket.exists() {
+        fs::remove_file(&socket).ok();
+    }
+
+    let exe = std::env::current_exe().context("Failed to locate whiteout binary")?;
+    std::process::Command::new(exe)
+        .arg("agent")
+        .arg("--foreground")
+        .current_dir(project_root)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .context("Failed to spawn whiteout agent")?;
+
+    for _ in 0..100 {
+        if ping(&socket).is_ok() {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+
+    bail!("Agent did not become ready in time")
+}
+
+fn request(project_root: &Path, op: Op, file_path: &Path, content: &str) -> Result<String> {
+    ensure_running(project_root)?;
+    let socket = socket_path(project_root);
+    check_socket_permissions(&socket)?;
+
+    let stream = UnixStream::connect(&socket).context("Failed to connect to whiteout agent")?;
+    write_frame(&stream, op.as_str(), &file_path.to_string_lossy(), content.as_bytes())?;
+
+    let (status, body) = read_frame(&stream)?;
+    match status.as_str() {
+        "OK" => Ok(String::from_utf8(body).context("Agent returned non-UTF-8 content")?),
+        "ERR" => bail!("Agent error: {}", String::from_utf8_lossy(&body)),
+        other => bail!("Unexpected agent response: {}", other),
+    }
+}
+
+pub fn clean_via_agent(project_root: &Path, content: &str, file_path: &Path) -> Result<String> {
+    request(project_root, Op::Clean, file_path, content)
+}
+
+pub fn smudge_via_agent(project_root: &Path, content: &str, file_path: &Path) -> Result<String> {
+    request(project_root, Op::Smudge, file_path, content)
+}
+
+/// Sends `--lock` to a running agent to wipe its in-memory key. A no-op
+/// (not an error) if no agent is currently running.
+pub fn lock(project_root: &Path) -> Result<()> {
+    let socket = socket_path(project_root);
+    if ping(&socket).is_err() {
+        return Ok(());
+    }
+    let stream = UnixStream::connect(&socket)?;
+    write_frame(&stream, Op::Lock.as_str(), "", &[])?;
+    let (status, _) = read_frame(&stream)?;
+    if status != "OK" {
+        bail!("Agent failed to lock: {}", status);
+    }
+    Ok(())
+}
+
+// Wire format: "<OP> <path-len> <body-len>\n<path bytes><body bytes>"
+fn write_frame(mut stream: &UnixStream, op: &str, path: &str, body: &[u8]) -> Result<()> {
+    write!(stream, "{} {} {}\n", op, path.len(), body.len())?;
+    stream.write_all(path.as_bytes())?;
+    stream.write_all(body)?;
+    stream.flush()?;
+    Ok(())
+}
+
+fn read_frame(stream: &UnixStream) -> Result<(String, Vec<u8>)> {
+    let mut reader = BufReader::new(stream);
+    let mut header = String::new();
+    reader.read_line(&mut header)?;
+    let mut parts = header.trim_end().splitn(2, ' ');
+    let status = parts.next().unwrap_or_default().to_string();
+    let len: usize = parts.next().unwrap_or("0").parse().unwrap_or(0);
+    let mut body = vec![0u8; len];
+    reader.read_exact(&mut body)?;
+    Ok((status, body))
+}
+
+struct Unlocked {
+    crypto: Crypto,
+    last_used: Instant,
+}
+
+/// The long-running half of the agent: owns the cached cipher and serves
+/// clean/smudge requests over the project's Unix socket.
+pub struct AgentServer {
+    project_root: PathBuf,
+    idle_timeout: Duration,
+}
+
+impl AgentServer {
+    pub fn new(project_root: PathBuf, idle_timeout: Duration) -> Self {
+        Self {
+            project_root,
+            idle_timeout,
+        }
+    }
+
+    pub fn run(self) -> Result<()> {
+        let socket = socket_path(&self.project_root);
+        if let Some(parent) = socket.parent() {
+            fs::create_dir_all(parent).context("Failed to create .whiteout directory")?;
+        }
+        if socket.exists() {
+            fs::remove_file(&socket).context("Failed to remove stale agent socket")?;
+        }
+
+        let listener = UnixListener::bind(&socket).context("Failed to bind agent socket")?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&socket, fs::Permissions::from_mode(0o600))?;
+        }
+
+        let state: Arc<Mutex<Option<Unlocked>>> = Arc::new(Mutex::new(None));
+
+        {
+            let state = Arc::clone(&state);
+            let idle_timeout = self.idle_timeout;
+            thread::spawn(move || loop {
+                thread::sleep(Duration::from_secs(5));
+                let mut guard = state.lock().unwrap();
+                if let Some(unlocked) = guard.as_ref() {
+                    if unlocked.last_used.elapsed() >= idle_timeout {
+                        *guard = None; // wipe the cached key
+                        tracing::info!("whiteout agent: idle timeout reached, key wiped");
+                    }
+                }
+            });
+        }
+
+        for incoming in listener.incoming() {
+            let stream = match incoming {
+                Ok(stream) => stream,
+                Err(e) => {
+                    tracing::warn!("whiteout agent: accept failed: {}", e);
+                    continue;
+                }
+            };
+
+            if let Err(e) = self.handle_connection(stream, &state) {
+                tracing::warn!("whiteout agent: connection error: {}", e);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle_connection(
+        &self,
+        stream: UnixStream,
+        state: &Arc<Mutex<Option<Unlocked>>>,
+    ) -> Result<()> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut header = String::new();
+        reader.read_line(&mut header)?;
+
+        let mut parts = header.trim_end().split(' ');
+        let op = parts.next().and_then(Op::parse);
+        let path_len: usize = parts.next().unwrap_or("0").parse().unwrap_or(0);
+        let body_len: usize = parts.next().unwrap_or("0").parse().unwrap_or(0);
+
+        let mut path_buf = vec![0u8; path_len];
+        reader.read_exact(&mut path_buf)?;
+        let file_path = PathBuf::from(String::from_utf8_lossy(&path_buf).into_owned());
+
+        let mut body = vec![0u8; body_len];
+        reader.read_exact(&mut body)?;
+
+        match op {
+            Some(Op::Ping) => write_frame(&stream, "PONG", "", &[]),
+            Some(Op::Lock) => {
+                *state.lock().unwrap() = None;
+                write_frame(&stream, "OK", "", &[])
+            }
+            Some(op @ Op::Clean) | Some(op @ Op::Smudge) => {
+                let crypto = self.ensure_unlocked(state)?;
+                let whiteout = crate::Whiteout::with_crypto(&self.project_root, crypto)?;
+
+                let content = String::from_utf8_lossy(&body).into_owned();
+                let result = match op {
+                    Op::Clean => whiteout.clean(&content, &file_path),
+                    _ => whiteout.smudge(&content, &file_path),
+                };
+
+                match result {
+                    Ok(transformed) => write_frame(&stream, "OK", "", transformed.as_bytes()),
+                    Err(e) => write_frame(&stream, "ERR", "", format!("{:#}", e).as_bytes()),
+                }
+            }
+            None => write_frame(&stream, "ERR", "", b"unknown operation"),
+        }
+    }
+
+    /// Derives the encryption key exactly once per agent lifetime (or idle
+    /// cycle): subsequent requests reuse the already-constructed cipher
+    /// instead of re-reading the key file or re-unwrapping the repo DEK.
+    /// Returns the cipher directly (rather than leaving the caller to read it
+    /// back out of `state`) so a concurrent wipe by the idle-timeout sweeper
+    /// can't race this into returning a now-empty cache.
+    fn ensure_unlocked(&self, state: &Arc<Mutex<Option<Unlocked>>>) -> Result<Crypto> {
+        let mut guard = state.lock().unwrap();
+
+        if let Some(unlocked) = guard.as_mut() {
+            unlocked.last_used = Instant::now();
+            return Ok(unlocked.crypto.clone());
+        }
+
+        let crypto = Crypto::for_project(&self.project_root)?;
+        *guard = Some(Unlocked {
+            crypto: crypto.clone(),
+            last_used: Instant::now(),
+        });
+
+        Ok(crypto)
+    }
+}
@@ -0,0 +1,69 @@
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Obtains the vault passphrase without it ever passing through argv or an
+/// env var that a sibling process could read.
+pub trait PassphrasePrompt: Send + Sync {
+    fn prompt(&self, message: &str) -> Result<String>;
+}
+
+/// Reads the passphrase directly from the controlling terminal with echo
+/// disabled. This is the default when no pinentry program is configured.
+pub struct TerminalPrompt;
+
+impl PassphrasePrompt for TerminalPrompt {
+    fn prompt(&self, message: &str) -> Result<String> {
+        rpassword::prompt_password(format!("{}: ", message))
+            .context("Failed to read passphrase from terminal")
+    }
+}
+
+/// Delegates to an external pinentry-compatible program over the minimal
+/// Assuan subset (`SETDESC` / `GETPIN` / `D <pin>`), the same flow rbw uses.
+pub struct PinentryPrompt {
+    pub command: String,
+}
+
+impl PassphrasePrompt for PinentryPrompt {
+    fn prompt(&self, message: &str) -> Result<String> {
+        let mut child = Command::new(&self.command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .with_context(|| format!("Failed to launch pinentry command: {}", self.command))?;
+
+        {
+            let stdin = child
+                .stdin
+                .as_mut()
+                .context("Failed to open pinentry stdin")?;
+            writeln!(stdin, "SETDESC {}", message.replace('\n', " "))?;
+            writeln!(stdin, "GETPIN")?;
+            writeln!(stdin, "BYE")?;
+        }
+
+        let output = child
+            .wait_with_output()
+            .context("pinentry process did not exit cleanly")?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        for line in stdout.lines() {
+            if let Some(pin) = line.strip_prefix("D ") {
+                return Ok(pin.to_string());
+            }
+        }
+
+        anyhow::bail!("pinentry did not return a passphrase")
+    }
+}
+
+/// Picks the configured prompt: an external pinentry command if set via
+/// `WHITEOUT_PINENTRY`, otherwise the terminal reader.
+pub fn default_prompt() -> Box<dyn PassphrasePrompt> {
+    match std::env::var("WHITEOUT_PINENTRY") {
+        Ok(command) if !command.is_empty() => Box::new(PinentryPrompt { command }),
+        _ => Box::new(TerminalPrompt),
+    }
+}
@@ -0,0 +1,288 @@
+//! A stable C ABI over `Whiteout::clean`/`smudge` and `parser::Parser::parse`,
+//! so editor plugins, pre-commit hooks, and CI shims written in other
+//! languages can call whiteout directly instead of shelling out to the
+//! binary.
+//!
+//! Every function here is `extern "C"` and trades in raw pointers; callers
+//! own whatever comes back and must release it through the matching `_free`
+//! function (`whiteout_free` for a `whiteout_init` handle, `whiteout_string_free`
+//! for everything else). Nothing here panics across the FFI boundary: on
+//! failure the function returns `null` and, when `error_out` is non-null,
+//! writes a newline-joined `anyhow::Error` chain there for the caller to
+//! inspect and free.
+
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::path::Path;
+
+use crate::parser::Parser;
+use crate::Whiteout;
+
+/// Joins an `anyhow::Error`'s cause chain with newlines — the message
+/// format written to every `error_out` out-param below.
+fn chain_message(err: &anyhow::Error) -> String {
+    err.chain().map(ToString::to_string).collect::<Vec<_>>().join("\n")
+}
+
+/// Writes `message` into `*error_out`, if `error_out` is non-null.
+/// Embedded NUL bytes (which can't occur in a message produced by this
+/// crate, but are possible in principle) are stripped rather than causing
+/// the write to silently fail.
+///
+/// # Safety
+/// `error_out` must either be null or point to writable memory.
+unsafe fn write_error(error_out: *mut *mut c_char, message: &str) {
+    if error_out.is_null() {
+        return;
+    }
+    let sanitized = message.replace('\0', "");
+    *error_out = CString::new(sanitized).unwrap_or_default().into_raw();
+}
+
+/// Reads a non-null, UTF-8 `CStr` argument, reporting through `error_out`
+/// (labelled `what`) and returning `None` on a null pointer or invalid
+/// UTF-8.
+///
+/// # Safety
+/// `ptr` must either be null or a valid, NUL-terminated C string.
+unsafe fn read_str<'a>(ptr: *const c_char, what: &str, error_out: *mut *mut c_char) -> Option<&'a str> {
+    if ptr.is_null() {
+        write_error(error_out, &format!("{} must not be null", what));
+        return None;
+    }
+    match CStr::from_ptr(ptr).to_str() {
+        Ok(s) => Some(s),
+        Err(e) => {
+            write_error(error_out, &format!("{} is not valid UTF-8: {}", what, e));
+            None
+        }
+    }
+}
+
+/// Wraps a `Result<String, _>` as the `*mut c_char` / `error_out` contract
+/// every function below shares.
+unsafe fn string_result(result: anyhow::Result<String>, error_out: *mut *mut c_char) -> *mut c_char {
+    match result {
+        Ok(value) => match CString::new(value) {
+            Ok(c_string) => c_string.into_raw(),
+            Err(e) => {
+                write_error(error_out, &format!("result contains an embedded NUL byte: {}", e));
+                std::ptr::null_mut()
+            }
+        },
+        Err(e) => {
+            write_error(error_out, &chain_message(&e));
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Opens the whiteout project rooted at `repo_path`, returning a boxed
+/// handle for use with `whiteout_clean`/`whiteout_smudge`. Returns `null`
+/// and writes to `error_out` on failure.
+///
+/// # Safety
+/// `repo_path` must be a valid, NUL-terminated UTF-8 C string. `error_out`
+/// may be null; if non-null it must point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn whiteout_init(repo_path: *const c_char, error_out: *mut *mut c_char) -> *mut Whiteout {
+    let Some(repo_path) = read_str(repo_path, "repo_path", error_out) else {
+        return std::ptr::null_mut();
+    };
+
+    match Whiteout::new(Path::new(repo_path)) {
+        Ok(whiteout) => Box::into_raw(Box::new(whiteout)),
+        Err(e) => {
+            write_error(error_out, &chain_message(&e));
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Reclaims a handle returned by `whiteout_init`. Passing `null` is a no-op.
+///
+/// # Safety
+/// `handle` must either be null or a pointer previously returned by
+/// `whiteout_init` that hasn't already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn whiteout_free(handle: *mut Whiteout) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+/// Runs the clean filter (local value -> committed value) over `content`.
+/// Returns a heap string the caller must free with `whiteout_string_free`,
+/// or `null` with `error_out` set on failure.
+///
+/// # Safety
+/// `handle` must be a live pointer from `whiteout_init`. `content` and
+/// `file_path` must be valid, NUL-terminated UTF-8 C strings. `error_out`
+/// may be null.
+#[no_mangle]
+pub unsafe extern "C" fn whiteout_clean(
+    handle: *mut Whiteout,
+    content: *const c_char,
+    file_path: *const c_char,
+    error_out: *mut *mut c_char,
+) -> *mut c_char {
+    if handle.is_null() {
+        write_error(error_out, "handle must not be null");
+        return std::ptr::null_mut();
+    }
+    let Some(content) = read_str(content, "content", error_out) else {
+        return std::ptr::null_mut();
+    };
+    let Some(file_path) = read_str(file_path, "file_path", error_out) else {
+        return std::ptr::null_mut();
+    };
+
+    let result = (*handle).clean(content, Path::new(file_path));
+    string_result(result, error_out)
+}
+
+/// Runs the smudge filter (committed value -> local value) over `content`.
+/// See `whiteout_clean` for the return/ownership/error contract.
+///
+/// # Safety
+/// Same as `whiteout_clean`.
+#[no_mangle]
+pub unsafe extern "C" fn whiteout_smudge(
+    handle: *mut Whiteout,
+    content: *const c_char,
+    file_path: *const c_char,
+    error_out: *mut *mut c_char,
+) -> *mut c_char {
+    if handle.is_null() {
+        write_error(error_out, "handle must not be null");
+        return std::ptr::null_mut();
+    }
+    let Some(content) = read_str(content, "content", error_out) else {
+        return std::ptr::null_mut();
+    };
+    let Some(file_path) = read_str(file_path, "file_path", error_out) else {
+        return std::ptr::null_mut();
+    };
+
+    let result = (*handle).smudge(content, Path::new(file_path));
+    string_result(result, error_out)
+}
+
+/// Parses every decoration form in `content` (inline, block, partial) and
+/// returns them as a JSON array, for callers that want to inspect
+/// decorations without running the clean/smudge filters. Doesn't require a
+/// `whiteout_init` handle, since parsing needs no project state.
+///
+/// # Safety
+/// `content` and `file_path` must be valid, NUL-terminated UTF-8 C strings.
+/// `error_out` may be null.
+#[no_mangle]
+pub unsafe extern "C" fn whiteout_parse(
+    content: *const c_char,
+    file_path: *const c_char,
+    error_out: *mut *mut c_char,
+) -> *mut c_char {
+    let Some(content) = read_str(content, "content", error_out) else {
+        return std::ptr::null_mut();
+    };
+    let Some(file_path) = read_str(file_path, "file_path", error_out) else {
+        return std::ptr::null_mut();
+    };
+
+    let result = Parser::new()
+        .parse(content, Some(Path::new(file_path)))
+        .and_then(|decorations| Ok(serde_json::to_string(&decorations)?));
+    string_result(result, error_out)
+}
+
+/// Frees a string returned by `whiteout_clean`, `whiteout_smudge`,
+/// `whiteout_parse`, or an error string written to an `error_out`
+/// out-param. Passing `null` is a no-op.
+///
+/// # Safety
+/// `s` must either be null or a pointer previously returned by one of
+/// those functions that hasn't already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn whiteout_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_init_free_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = CString::new(temp_dir.path().to_str().unwrap()).unwrap();
+        let mut error: *mut c_char = std::ptr::null_mut();
+
+        let handle = unsafe { whiteout_init(repo_path.as_ptr(), &mut error) };
+        assert!(!handle.is_null());
+        assert!(error.is_null());
+
+        unsafe { whiteout_free(handle) };
+    }
+
+    #[test]
+    fn test_init_null_path_reports_error() {
+        let mut error: *mut c_char = std::ptr::null_mut();
+
+        let handle = unsafe { whiteout_init(std::ptr::null(), &mut error) };
+        assert!(handle.is_null());
+        assert!(!error.is_null());
+
+        let message = unsafe { CStr::from_ptr(error) }.to_str().unwrap().to_string();
+        assert!(message.contains("repo_path"));
+        unsafe { whiteout_string_free(error) };
+    }
+
+    #[test]
+    fn test_clean_and_smudge_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_path = CString::new(temp_dir.path().to_str().unwrap()).unwrap();
+        let mut error: *mut c_char = std::ptr::null_mut();
+        let handle = unsafe { whiteout_init(repo_path.as_ptr(), &mut error) };
+        assert!(!handle.is_null());
+
+        let content = CString::new(r#"let api_key = "sk-12345"; // @whiteout: "ENV_VAR""#).unwrap();
+        let file_path = CString::new("test.rs").unwrap();
+
+        let cleaned_ptr = unsafe { whiteout_clean(handle, content.as_ptr(), file_path.as_ptr(), &mut error) };
+        assert!(!cleaned_ptr.is_null());
+        let cleaned = unsafe { CStr::from_ptr(cleaned_ptr) }.to_str().unwrap().to_string();
+        assert!(cleaned.contains("ENV_VAR"));
+        assert!(!cleaned.contains("sk-12345"));
+        unsafe { whiteout_string_free(cleaned_ptr) };
+
+        unsafe { whiteout_free(handle) };
+    }
+
+    #[test]
+    fn test_clean_null_handle_reports_error() {
+        let content = CString::new("x").unwrap();
+        let file_path = CString::new("test.rs").unwrap();
+        let mut error: *mut c_char = std::ptr::null_mut();
+
+        let result = unsafe { whiteout_clean(std::ptr::null_mut(), content.as_ptr(), file_path.as_ptr(), &mut error) };
+        assert!(result.is_null());
+        assert!(!error.is_null());
+        unsafe { whiteout_string_free(error) };
+    }
+
+    #[test]
+    fn test_parse_returns_json_decorations() {
+        let content = CString::new(r#"let api_key = "sk-12345"; // @whiteout: "ENV_VAR""#).unwrap();
+        let file_path = CString::new("test.rs").unwrap();
+        let mut error: *mut c_char = std::ptr::null_mut();
+
+        let json_ptr = unsafe { whiteout_parse(content.as_ptr(), file_path.as_ptr(), &mut error) };
+        assert!(!json_ptr.is_null());
+        let json = unsafe { CStr::from_ptr(json_ptr) }.to_str().unwrap().to_string();
+        assert!(json.contains("ENV_VAR"));
+        unsafe { whiteout_string_free(json_ptr) };
+    }
+}
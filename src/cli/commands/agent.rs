@@ -0,0 +1,29 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::path::Path;
+use whiteout::agent::{self, AgentServer};
+
+pub fn handle(foreground: bool, lock: bool) -> Result<()> {
+    let project_root = Path::new(".").canonicalize()
+        .context("Failed to resolve project root")?;
+
+    if lock {
+        agent::lock(&project_root).context("Failed to lock whiteout agent")?;
+        println!("{} Agent locked, in-memory key wiped", "✓".bright_green());
+        return Ok(());
+    }
+
+    if foreground {
+        println!("{} whiteout agent listening at {}",
+            "→".bright_cyan(),
+            agent::socket_path(&project_root).display());
+        let server = AgentServer::new(project_root, agent::default_idle_timeout());
+        server.run().context("Agent failed")?;
+        return Ok(());
+    }
+
+    agent::ensure_running(&project_root).context("Failed to start whiteout agent")?;
+    println!("{} Agent is running", "✓".bright_green());
+
+    Ok(())
+}
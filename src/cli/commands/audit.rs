@@ -0,0 +1,71 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::path::Path;
+use whiteout::parser::audit::{audit_decorations, AuditKind};
+use whiteout::parser::Parser;
+
+/// Reports exactly what the clean filter would strip from `file`, without
+/// writing anything -- the dry-run counterpart to `preview`, broken down
+/// decoration by decoration instead of as one before/after blob.
+pub fn handle(file: &Path) -> Result<()> {
+    let content = std::fs::read_to_string(file)
+        .with_context(|| format!("Failed to read file: {}", file.display()))?;
+
+    let parser = Parser::new();
+    let decorations = parser.parse(&content, Some(file))?;
+    let report = audit_decorations(&content, &decorations, Some(file));
+
+    println!("{}", "Whiteout Audit".bright_blue().bold());
+    println!("{}", "==============".bright_blue());
+    println!("File: {}\n", file.display());
+
+    if report.findings.is_empty() {
+        println!("{} No decorations found", "ℹ".bright_blue());
+        return Ok(());
+    }
+
+    for finding in &report.findings {
+        let range = match finding.end_line {
+            Some(end) => format!("{}-{}", finding.line, end),
+            None => finding.line.to_string(),
+        };
+        let kind = match finding.kind {
+            AuditKind::Block => "block",
+            AuditKind::Inline => "inline",
+            AuditKind::Partial => "partial",
+        };
+
+        if finding.leaks_secret {
+            println!(
+                "{} line {} ({}) -- committed value still looks like a {}",
+                "✗".bright_red(),
+                range,
+                kind,
+                finding.secret_kind.unwrap_or("secret").bright_yellow()
+            );
+        } else {
+            println!("{} line {} ({})", "✓".bright_green(), range, kind);
+        }
+        println!("  {} {}", "local:".bright_black(), finding.before.bright_green());
+        println!("  {} {}", "committed:".bright_black(), finding.after.bright_yellow());
+    }
+
+    let leaked = report.findings.iter().filter(|f| f.leaks_secret).count();
+    println!();
+    if leaked > 0 {
+        println!(
+            "{} {} of {} decoration(s) would still leak a secret when committed",
+            "✗".bright_red(),
+            leaked,
+            report.findings.len()
+        );
+        anyhow::bail!("audit found decorations that still leak a secret");
+    }
+
+    println!(
+        "{} All {} decoration(s) look safe to commit",
+        "✓".bright_green(),
+        report.findings.len()
+    );
+    Ok(())
+}
@@ -1,72 +1,164 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use colored::Colorize;
-use regex::Regex;
-use std::path::PathBuf;
-use std::process::Command;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use whiteout::config::Config;
+use whiteout::ignore::IgnoreSet;
+use whiteout::matcher;
+use whiteout::storage::{self, atomic::AtomicFile};
+use whiteout::validation::{find_secrets, InputValidator};
+
+/// A safe placeholder committed in place of a detected secret literal,
+/// matching the example value used throughout the docs and `mark`.
+const PLACEHOLDER: &str = "REDACTED";
+
+/// Picks the line-comment token (and, for the rare file types that only
+/// have block comments, the closing token) to write a `@whiteout:`
+/// decoration in, based on file extension. Distinct from
+/// `parser::comment_syntax::patterns_for`, which returns regexes for
+/// *recognizing* existing decorations rather than a token pair for
+/// *writing* new ones.
+fn comment_tokens(path: &Path) -> (&'static str, &'static str) {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("py") | Some("rb") | Some("sh") | Some("yaml") | Some("yml") => ("#", ""),
+        Some("sql") | Some("lua") => ("--", ""),
+        Some("html") | Some("xml") => ("<!--", "-->"),
+        _ => ("//", ""),
+    }
+}
+
+/// Renders a fixed-up line in the same form `parser::apply_decorations`
+/// produces for an already-clean inline decoration, so a subsequent
+/// `clean`/`smudge` round-trips it without special-casing.
+fn render_inline_decoration(placeholder: &str, open: &str, close: &str) -> String {
+    if close.is_empty() {
+        format!("{} {} @whiteout: {}", placeholder, open, placeholder)
+    } else {
+        format!("{} {} @whiteout: {} {}", placeholder, open, placeholder, close)
+    }
+}
+
+/// Walks `root`, honoring `.gitignore`/`.git/info/exclude` (via
+/// [`IgnoreSet`]) and `.whiteoutignore`, the same exclusion semantics
+/// `scan` uses, so `check` doesn't flag generated/vendored files under
+/// ignored directories and does pick up untracked files `git ls-files`
+/// would miss.
+fn discover_files(root: &Path) -> Result<Vec<PathBuf>> {
+    let ignore = IgnoreSet::load(root).context("Failed to load ignore files")?;
+    let whiteoutignore = matcher::load(root).context("Failed to load .whiteoutignore")?;
+
+    let mut files = Vec::new();
+    for entry in WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|e| e.file_name() != ".git")
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+    {
+        let path = entry.path();
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        let relative_str = relative.to_string_lossy();
+
+        if ignore.is_ignored(&relative_str) {
+            continue;
+        }
+        if !whiteoutignore.matches(relative) {
+            continue;
+        }
+
+        files.push(relative.to_path_buf());
+    }
+
+    Ok(files)
+}
 
 pub fn handle(files: Vec<PathBuf>, fix: bool) -> Result<()> {
     println!("{}", "Checking for potential secrets...".bright_blue());
-    
-    // Simple pattern matching for potential secrets
-    let patterns = vec![
-        (r"(?i)(api[_-]?key|apikey)", "API Key"),
-        (r"(?i)(secret|password|passwd|pwd)", "Secret/Password"),
-        (r"(?i)(token|bearer)", "Token"),
-        (r"(?i)sk-[a-zA-Z0-9]{32,}", "OpenAI API Key"),
-        (r"(?i)ghp_[a-zA-Z0-9]{36}", "GitHub Token"),
-        (r"https?://[^/]*:[^@]*@", "URL with credentials"),
-    ];
-    
+
     let files_to_check = if files.is_empty() {
-        // Get all tracked files
-        let output = Command::new("git")
-            .args(&["ls-files"])
-            .output()?;
-        String::from_utf8_lossy(&output.stdout)
-            .lines()
-            .map(PathBuf::from)
-            .collect()
+        discover_files(Path::new("."))?
     } else {
         files
     };
-    
+
+    let project_root = Path::new(".");
+    let backend = if fix {
+        let config = Config::load_or_default(project_root)?;
+        Some(storage::open_backend(&config, project_root)?)
+    } else {
+        None
+    };
+
     let mut found_issues = false;
     for file_path in files_to_check {
         if let Ok(content) = std::fs::read_to_string(&file_path) {
-            for (pattern_str, name) in &patterns {
-                let regex = Regex::new(pattern_str)?;
-                for (line_num, line) in content.lines().enumerate() {
-                    // Skip if already decorated
-                    if line.contains("@whiteout") {
-                        continue;
-                    }
-                    
-                    if regex.is_match(line) {
-                        found_issues = true;
-                        println!(
-                            "{} {} in {}:{} - {}",
-                            "⚠".bright_yellow(),
-                            name,
-                            file_path.display(),
-                            line_num + 1,
-                            line.trim().bright_red()
-                        );
-                        
-                        if fix {
-                            // TODO: Implement auto-fix logic
-                            println!("  {} Auto-fix not yet implemented", "→".bright_cyan());
-                        }
-                    }
+            let findings = find_secrets(&content)?;
+            if findings.is_empty() {
+                continue;
+            }
+
+            let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+            let mut fixed_count = 0usize;
+
+            for finding in &findings {
+                found_issues = true;
+                println!(
+                    "{} {} in {}:{} - {}",
+                    "⚠".bright_yellow(),
+                    finding.name,
+                    file_path.display(),
+                    finding.line,
+                    finding.text.bright_red()
+                );
+
+                if let (true, Some(backend)) = (fix, &backend) {
+                    let replacement = InputValidator::validate_replacement(PLACEHOLDER)
+                        .context("Generated replacement failed validation")?;
+
+                    let (open, close) = comment_tokens(&file_path);
+                    let decorated = render_inline_decoration(&replacement, open, close);
+                    InputValidator::validate_decoration(&decorated)
+                        .context("Generated decoration failed validation")?;
+
+                    let original_line = lines[finding.line - 1].clone();
+                    let blob = backend.blob_ref(&file_path, &format!("inline_{}", finding.line));
+                    backend
+                        .blob_put(&blob, original_line.as_bytes())
+                        .context("Failed to store original value")?;
+
+                    lines[finding.line - 1] = decorated;
+                    fixed_count += 1;
+                    println!("  {} Added decoration, moved value to local storage", "→".bright_cyan());
+                }
+            }
+
+            if fixed_count > 0 {
+                let mut updated = lines.join("\n");
+                if content.ends_with('\n') {
+                    updated.push('\n');
                 }
+
+                let atomic = AtomicFile::new(&file_path)
+                    .with_context(|| format!("Failed to stage write for {}", file_path.display()))?;
+                atomic
+                    .write(updated.as_bytes())
+                    .with_context(|| format!("Failed to write {}", file_path.display()))?;
+
+                println!(
+                    "  {} Fixed {} line(s) in {}",
+                    "✓".bright_green(),
+                    fixed_count,
+                    file_path.display()
+                );
             }
         }
     }
-    
+
     if !found_issues {
         println!("{} No potential secrets found!", "✓".bright_green());
     } else if !fix {
         println!("\n{}", "Tip: Use --fix to automatically add decorations".bright_cyan());
     }
-    
+
     Ok(())
 }
\ No newline at end of file
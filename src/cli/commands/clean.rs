@@ -0,0 +1,51 @@
+use anyhow::{Context, Result};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use whiteout::config::Config;
+use whiteout::Whiteout;
+
+pub fn handle(file: Option<PathBuf>) -> Result<()> {
+    let project_root = Path::new(".");
+    let whiteout = Whiteout::new(project_root)
+        .context("Failed to load Whiteout configuration")?;
+
+    let (raw, file_path) = if let Some(file_path) = file {
+        let raw = std::fs::read(&file_path)
+            .with_context(|| format!("Failed to read file: {}", file_path.display()))?;
+        (raw, file_path)
+    } else {
+        let mut buffer = Vec::new();
+        std::io::stdin().read_to_end(&mut buffer)?;
+        (buffer, PathBuf::from("stdin"))
+    };
+
+    // Git invokes the clean filter on every path matched by `.gitattributes`,
+    // including binary files if it's ever registered on `*`. Whiteout's
+    // decoration markers can only live in UTF-8 text, so a file that isn't
+    // valid UTF-8 is passed through untouched instead of failing the filter.
+    let content = match String::from_utf8(raw) {
+        Ok(content) => content,
+        Err(e) => {
+            std::io::stdout().write_all(e.as_bytes())?;
+            return Ok(());
+        }
+    };
+
+    let cleaned = if encryption_enabled(project_root)? {
+        whiteout::agent::clean_via_agent(project_root, &content, &file_path)
+            .context("Failed to clean via whiteout agent")?
+    } else {
+        whiteout.clean(&content, &file_path)
+            .context("Failed to apply clean filter")?
+    };
+    print!("{}", cleaned);
+
+    Ok(())
+}
+
+/// When encryption is on we route through the agent so the passphrase is
+/// only ever asked for once per session instead of once per invocation.
+fn encryption_enabled(project_root: &Path) -> Result<bool> {
+    let config = Config::load_or_default(project_root)?;
+    Ok(config.data.encryption.enabled)
+}
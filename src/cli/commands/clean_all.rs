@@ -0,0 +1,84 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use rayon::prelude::*;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+use whiteout::ignore::IgnoreSet;
+use whiteout::matcher;
+use whiteout::Whiteout;
+
+/// Runs `clean` over every in-scope file under `path` in parallel with
+/// rayon, the same way `scan` walks the tree but without reading the
+/// result back -- the point isn't the cleaned output (Git already derives
+/// that lazily per file) but the content-addressed cache entry `clean`
+/// populates as a side effect, which `smudge` can then use to restore a
+/// checkout without re-deriving every decoration from storage.
+pub fn handle(path: &Path) -> Result<()> {
+    println!("{}", "Cleaning all in-scope files in parallel...".bright_blue());
+
+    let whiteout = Whiteout::new(path).context(
+        "Whiteout is not initialized in this project (run `whiteout init` first)",
+    )?;
+    let ignore = IgnoreSet::load(path).context("Failed to load ignore files")?;
+    let whiteoutignore = matcher::load(path).context("Failed to load .whiteoutignore")?;
+
+    let files: Vec<PathBuf> = WalkDir::new(path)
+        .into_iter()
+        .filter_entry(|e| e.file_name() != ".git")
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|entry| {
+            let file_path = entry.path();
+            let relative = file_path.strip_prefix(path).unwrap_or(file_path);
+
+            if ignore.is_ignored(&relative.to_string_lossy()) {
+                return None;
+            }
+            if !whiteoutignore.matches(relative) {
+                return None;
+            }
+            match whiteout.config().matches(relative) {
+                Ok(true) => Some(relative.to_path_buf()),
+                _ => None,
+            }
+        })
+        .collect();
+
+    let results: Vec<Result<()>> = files
+        .par_iter()
+        .map(|relative| {
+            let full_path = path.join(relative);
+            let content = std::fs::read_to_string(&full_path)
+                .with_context(|| format!("Failed to read {}", full_path.display()))?;
+            whiteout
+                .clean(&content, relative)
+                .with_context(|| format!("Failed to clean {}", relative.display()))?;
+            Ok(())
+        })
+        .collect();
+
+    let mut cleaned = 0;
+    let mut failed = 0;
+    for result in results {
+        match result {
+            Ok(()) => cleaned += 1,
+            Err(err) => {
+                failed += 1;
+                eprintln!("{} {}", "✗".bright_red(), err);
+            }
+        }
+    }
+
+    println!(
+        "\n{} {} file(s) cleaned, {} failed",
+        "✓".bright_green(),
+        cleaned,
+        failed
+    );
+
+    if failed > 0 {
+        anyhow::bail!("clean-all failed for {} file(s)", failed);
+    }
+
+    Ok(())
+}
@@ -24,14 +24,31 @@ pub fn handle(action: ConfigAction) -> Result<()> {
             
             // Set the value
             if let Some(table) = config.as_table_mut() {
-                table.insert(key.clone(), toml::Value::String(value));
-                
+                // Capture the pre-change storage, so a `storage.backend`
+                // switch can still read the old backend before we overwrite
+                // the config that selects it.
+                let storage_before_change = if key == "storage.backend" {
+                    whiteout::storage::LocalStorage::new(".").ok()
+                } else {
+                    None
+                };
+
+                table.insert(key.clone(), toml::Value::String(value.clone()));
+
                 // Write back
                 std::fs::create_dir_all(config_path.parent().unwrap())?;
                 std::fs::write(config_path, toml::to_string_pretty(&config)?)
                     .context("Failed to write config file")?;
-                
+
                 println!("{} Configuration updated", "✓".bright_green());
+
+                if key == "encryption.enabled" && value == "true" {
+                    migrate_local_storage_to_encrypted()?;
+                }
+
+                if let Some(old_storage) = storage_before_change {
+                    migrate_local_storage_to_backend(&old_storage, &value)?;
+                }
             } else {
                 bail!("Invalid config structure");
             }
@@ -75,14 +92,102 @@ pub fn handle(action: ConfigAction) -> Result<()> {
                     println!("  {} No configuration values set", "ℹ".bright_blue());
                 } else {
                     for (key, value) in table {
-                        println!("  {} = {}", 
-                            key.bright_cyan(), 
+                        println!("  {} = {}",
+                            key.bright_cyan(),
                             value.to_string().bright_yellow());
                     }
                 }
             }
         }
+
+        ConfigAction::AddPattern { pattern } => {
+            let mut config = whiteout::config::Config::load_or_default(".")
+                .context("Failed to load config")?;
+
+            if config.data.patterns.patterns.contains(&pattern) {
+                println!(
+                    "{} Pattern '{}' is already configured",
+                    "⚠".bright_yellow(),
+                    pattern.bright_cyan()
+                );
+                return Ok(());
+            }
+
+            config.data.patterns.patterns.push(pattern.clone());
+            config.recompile_patterns().context("Failed to compile [patterns] globs")?;
+            config.save().context("Failed to save config")?;
+            config
+                .sync_gitattributes()
+                .context("Failed to update .gitattributes")?;
+
+            println!("{} Added pattern '{}'", "✓".bright_green(), pattern.bright_cyan());
+        }
+
+        ConfigAction::RemovePattern { pattern } => {
+            let mut config = whiteout::config::Config::load_or_default(".")
+                .context("Failed to load config")?;
+
+            let before = config.data.patterns.patterns.len();
+            config.data.patterns.patterns.retain(|p| p != &pattern);
+
+            if config.data.patterns.patterns.len() == before {
+                println!("{} Pattern '{}' not found", "⚠".bright_yellow(), pattern.bright_cyan());
+                return Ok(());
+            }
+
+            config.recompile_patterns().context("Failed to compile [patterns] globs")?;
+            config.save().context("Failed to save config")?;
+            config
+                .sync_gitattributes()
+                .context("Failed to update .gitattributes")?;
+
+            println!("{} Removed pattern '{}'", "✓".bright_green(), pattern.bright_cyan());
+        }
     }
-    
+
+    Ok(())
+}
+
+/// Re-encrypts any plaintext entries left over from before encryption was
+/// turned on, so flipping the config flag doesn't leave old secrets exposed
+/// on disk.
+fn migrate_local_storage_to_encrypted() -> Result<()> {
+    let storage = whiteout::storage::LocalStorage::new(".")
+        .context("Failed to load local storage for migration")?;
+    let migrated = storage
+        .migrate_to_encrypted()
+        .context("Failed to migrate local storage to encrypted entries")?;
+
+    if migrated > 0 {
+        println!(
+            "{} Re-encrypted {} existing local value(s)",
+            "✓".bright_green(),
+            migrated
+        );
+    }
+
+    Ok(())
+}
+
+/// Copies every entry from `old_storage`'s backend into the newly
+/// configured one, so switching `storage.backend` doesn't strand existing
+/// values behind the backend that's no longer selected.
+fn migrate_local_storage_to_backend(
+    old_storage: &whiteout::storage::LocalStorage,
+    new_backend: &str,
+) -> Result<()> {
+    let migrated = old_storage
+        .migrate_to_backend(new_backend)
+        .with_context(|| format!("Failed to migrate local storage to the '{}' backend", new_backend))?;
+
+    if migrated > 0 {
+        println!(
+            "{} Migrated {} existing local value(s) to the '{}' backend",
+            "✓".bright_green(),
+            migrated,
+            new_backend
+        );
+    }
+
     Ok(())
 }
\ No newline at end of file
@@ -4,38 +4,29 @@ use std::path::Path;
 use std::process::Command;
 use whiteout::Whiteout;
 
-pub fn handle(path: &Path) -> Result<()> {
+pub fn handle(path: &Path, patterns: Vec<String>) -> Result<()> {
     println!("{}", "Initializing Whiteout in your project...".bright_blue().bold());
     println!();
-    
+
     // Create .whiteout directory
     println!("{}", "Setting up local storage:".bright_blue());
-    Whiteout::init(path)
+    let globs = if patterns.is_empty() { vec!["*".to_string()] } else { patterns };
+    let whiteout = Whiteout::init_with_patterns(path, &globs)
         .context("Failed to initialize Whiteout in the specified directory")?;
     println!("  {} Created .whiteout/ directory for local values", "✓".bright_green());
-    
+
     // Automatically configure Git filters
     println!("\n{}", "Configuring Git integration:".bright_blue());
-    
-    // Add to .gitattributes
-    let gitattributes_path = path.join(".gitattributes");
-    let mut gitattributes_content = if gitattributes_path.exists() {
-        std::fs::read_to_string(&gitattributes_path)
-            .context("Failed to read .gitattributes file")?
-    } else {
-        String::new()
-    };
-    
-    if !gitattributes_content.contains("filter=whiteout") {
-        if !gitattributes_content.is_empty() && !gitattributes_content.ends_with('\n') {
-            gitattributes_content.push('\n');
-        }
-        gitattributes_content.push_str("* filter=whiteout\n");
-        std::fs::write(&gitattributes_path, gitattributes_content)
-            .context("Failed to write .gitattributes file")?;
-        println!("  {} Added filter to .gitattributes", "✓".bright_green());
-    }
-    
+
+    // `Config::init_with_patterns` already wrote the whiteout-managed
+    // `.gitattributes` block scoped to `globs` (or left an existing config's
+    // patterns untouched on a re-run).
+    println!(
+        "  {} Scoped filter to {} in .gitattributes",
+        "✓".bright_green(),
+        whiteout.config().data.patterns.patterns.join(", ")
+    );
+
     // Configure Git
     Command::new("git")
         .args(&["config", "filter.whiteout.clean", "whiteout clean"])
@@ -1,6 +1,7 @@
 use anyhow::{Context, Result, bail};
 use colored::Colorize;
 use std::path::Path;
+use whiteout::parser::comment_syntax;
 
 pub fn handle(file: &Path, line: Option<String>, replace: Option<String>) -> Result<()> {
     let content = std::fs::read_to_string(file)
@@ -31,18 +32,19 @@ pub fn handle(file: &Path, line: Option<String>, replace: Option<String>) -> Res
             println!("{} Marking lines {}-{} as local-only in {}", 
                 "→".bright_green(), start, end, file.display());
             
-            // Add block decoration
+            // Add block decoration, using this file's comment syntax
+            // instead of assuming `//` (see `comment_syntax`).
             let mut new_lines = Vec::new();
             for (i, line) in lines.iter().enumerate() {
                 if i == start - 1 {
-                    new_lines.push(format!("// @whiteout-start"));
+                    new_lines.push(comment_syntax::render_marker(Some(file), "@whiteout-start"));
                 }
                 new_lines.push(line.to_string());
                 if i == end - 1 {
-                    new_lines.push(format!("// @whiteout-end"));
+                    new_lines.push(comment_syntax::render_marker(Some(file), "@whiteout-end"));
                     // Add replacement as comment
                     for repl_line in replacement.lines() {
-                        new_lines.push(format!("// {}", repl_line));
+                        new_lines.push(comment_syntax::render_comment_line(Some(file), repl_line));
                     }
                 }
             }
@@ -72,8 +74,7 @@ pub fn handle(file: &Path, line: Option<String>, replace: Option<String>) -> Res
                         println!("{} Line already has decoration", "⚠".bright_yellow());
                         new_lines.push(line.to_string());
                     } else {
-                        new_lines.push(format!("{} // @whiteout: {}", 
-                            line, replacement));
+                        new_lines.push(comment_syntax::render_inline_marker(line, &replacement, Some(file)));
                     }
                 } else {
                     new_lines.push(line.to_string());
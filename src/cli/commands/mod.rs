@@ -1,5 +1,8 @@
+pub mod agent;
+pub mod audit;
 pub mod init;
 pub mod clean;
+pub mod clean_all;
 pub mod smudge;
 pub mod preview;
 pub mod check;
@@ -7,7 +10,11 @@ pub mod mark;
 pub mod unmark;
 pub mod status;
 pub mod config;
+pub mod recipient;
+pub mod scan;
+pub mod scan_history;
 pub mod sync;
+pub mod watch;
 
 use colored::Colorize;
 
@@ -0,0 +1,109 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::fs;
+use std::path::Path;
+use whiteout::storage::crypto::Crypto;
+use whiteout::storage::recipients::{Identity, Keyring};
+
+use crate::cli::RecipientAction;
+
+pub fn handle(action: RecipientAction) -> Result<()> {
+    let project_root = Path::new(".");
+
+    match action {
+        RecipientAction::Add { public_key } => {
+            let mut keyring = Keyring::load(project_root).context("Failed to load keyring")?;
+            let is_first_recipient = keyring.recipients.is_empty();
+
+            let dek = if is_first_recipient {
+                // Seed the repo DEK with whatever key `LocalStorage` is
+                // already encrypting with, rather than a fresh random one --
+                // otherwise every secret stored before the first recipient
+                // was added would become undecryptable the instant
+                // `Crypto::for_project` switches this repo over to the
+                // keyring-backed cipher.
+                Crypto::local_storage_key(project_root)
+                    .context("Failed to read the existing local storage key")?
+            } else {
+                let identity = Identity::load_or_create(project_root)
+                    .context("Failed to load local identity")?;
+                keyring
+                    .unwrap_dek(&identity)
+                    .map_err(|e| anyhow::anyhow!(e))
+                    .context("Failed to unwrap the existing DEK with your identity")?
+            };
+
+            if is_first_recipient {
+                // Onboarding wraps the DEK for `public_key`, but once the
+                // keyring has any recipients `Crypto::for_project` stops
+                // trusting the local storage key entirely -- without also
+                // wrapping for our own identity here, the operator running
+                // this command would lock themselves out of the vault they
+                // just migrated.
+                let own_identity = Identity::load_or_create(project_root)
+                    .context("Failed to load local identity")?;
+                keyring
+                    .add_recipient(&dek, &own_identity.public_key_base64())
+                    .context("Failed to wrap the DEK for your own identity")?;
+            }
+
+            keyring
+                .add_recipient(&dek, &public_key)
+                .context("Failed to wrap the DEK for the new recipient")?;
+            keyring.save(project_root).context("Failed to save keyring")?;
+
+            if is_first_recipient {
+                // The DEK now lives wrapped in the keyring, unwrappable only
+                // by onboarded recipients; the plaintext key file it was
+                // seeded from would otherwise let anyone with local
+                // filesystem access decrypt the vault without being a
+                // recipient at all.
+                let key_path = project_root.join(".whiteout").join("key");
+                if key_path.exists() {
+                    fs::remove_file(&key_path).context("Failed to remove the now-superseded local storage key")?;
+                }
+            }
+
+            println!("{} Added recipient {}", "✓".bright_green(), public_key.bright_cyan());
+        }
+
+        RecipientAction::Remove { public_key } => {
+            let mut keyring = Keyring::load(project_root).context("Failed to load keyring")?;
+
+            if keyring.recipients.len() == 1 && keyring.recipients[0].public_key == public_key {
+                anyhow::bail!(
+                    "Refusing to remove the last recipient: the vault would fall back to an \
+                     unrelated, freshly-generated local key and every existing entry would \
+                     become undecryptable. Add another recipient first if you want to move \
+                     away from the keyring entirely."
+                );
+            }
+
+            keyring.remove_recipient(&public_key);
+            keyring.save(project_root).context("Failed to save keyring")?;
+
+            println!("{} Removed recipient {}", "✓".bright_green(), public_key.bright_cyan());
+        }
+
+        RecipientAction::List => {
+            let keyring = Keyring::load(project_root).context("Failed to load keyring")?;
+
+            if keyring.recipients.is_empty() {
+                println!("{} No recipients configured", "ℹ".bright_blue());
+            } else {
+                println!("{}", "Recipients:".bright_blue().bold());
+                for recipient in &keyring.recipients {
+                    println!("  {} {}", "•".bright_black(), recipient.public_key);
+                }
+            }
+        }
+
+        RecipientAction::Whoami => {
+            let identity =
+                Identity::load_or_create(project_root).context("Failed to load local identity")?;
+            println!("{}", identity.public_key_base64());
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::path::Path;
+use walkdir::WalkDir;
+use whiteout::ignore::IgnoreSet;
+use whiteout::matcher;
+use whiteout::validation::find_secrets;
+use whiteout::Whiteout;
+
+/// Walks `path`, respecting `.gitignore`/`.git/info/exclude`,
+/// `.whiteoutignore`, and the configured `[patterns]`, and reports any
+/// secrets left undecorated in the committed (cleaned) version of each
+/// file. Exits non-zero if any are found, so it's suitable for use as a CI
+/// gate.
+pub fn handle(path: &Path) -> Result<()> {
+    println!("{}", "Scanning project for undecorated secrets...".bright_blue());
+
+    let whiteout = Whiteout::new(path).context(
+        "Whiteout is not initialized in this project (run `whiteout init` first)",
+    )?;
+    let ignore = IgnoreSet::load(path).context("Failed to load ignore files")?;
+    let whiteoutignore = matcher::load(path).context("Failed to load .whiteoutignore")?;
+
+    let mut files_scanned = 0;
+    let mut files_with_decorations = 0;
+    let mut residual_secrets = 0;
+
+    for entry in WalkDir::new(path)
+        .into_iter()
+        .filter_entry(|e| e.file_name() != ".git")
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+    {
+        let file_path = entry.path();
+        let relative = file_path.strip_prefix(path).unwrap_or(file_path);
+        let relative_str = relative.to_string_lossy();
+
+        if ignore.is_ignored(&relative_str) {
+            continue;
+        }
+
+        if !whiteoutignore.matches(relative) {
+            continue;
+        }
+
+        if !whiteout.config().matches(relative)? {
+            continue;
+        }
+
+        let Ok(content) = std::fs::read_to_string(file_path) else {
+            continue;
+        };
+
+        files_scanned += 1;
+
+        let has_decorations = content.contains("@whiteout");
+        if has_decorations {
+            files_with_decorations += 1;
+        }
+
+        let cleaned = whiteout.clean(&content, relative)?;
+        let findings = find_secrets(&cleaned)?;
+
+        if !findings.is_empty() {
+            residual_secrets += findings.len();
+            for finding in findings {
+                println!(
+                    "{} {} in {}:{} - {}",
+                    "⚠".bright_yellow(),
+                    finding.name,
+                    relative.display(),
+                    finding.line,
+                    finding.text.bright_red()
+                );
+            }
+        }
+    }
+
+    println!(
+        "\n{} {} file(s) scanned, {} with decorations",
+        "→".bright_cyan(),
+        files_scanned,
+        files_with_decorations
+    );
+
+    if residual_secrets > 0 {
+        println!(
+            "{} {} potential secret(s) found in committed content",
+            "✗".bright_red(),
+            residual_secrets
+        );
+        anyhow::bail!("scan found undecorated secrets");
+    }
+
+    println!("{} No undecorated secrets found", "✓".bright_green());
+    Ok(())
+}
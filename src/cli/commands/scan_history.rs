@@ -0,0 +1,118 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use git2::{Oid, Repository};
+use std::collections::HashSet;
+use std::path::Path;
+
+use aho_corasick::AhoCorasick;
+use whiteout::storage::LocalStorage;
+
+/// Blobs larger than this are skipped without being matched -- a secret
+/// doesn't hide any better in a multi-megabyte blob, and hashing/scanning
+/// every large binary asset in history would make this command impractical
+/// to run as a CI gate.
+const MAX_BLOB_SIZE: u64 = 10 * 1024 * 1024;
+
+struct FoundSecret {
+    description: String,
+}
+
+/// Walks every commit reachable from any ref and reports blobs whose
+/// content contains one of the real secret values held in
+/// `storage::LocalStorage` -- the exact leak `check`/`scan` try to prevent
+/// before a commit happens, caught here after the fact (e.g. a commit made
+/// before whiteout was set up, or with the filter bypassed). Exits non-zero
+/// if anything turns up, so it's suitable as a CI gate alongside `scan`.
+pub fn handle(path: &Path) -> Result<()> {
+    println!("{}", "Scanning Git history for leaked secrets...".bright_blue());
+
+    let repo = Repository::open(path)
+        .with_context(|| format!("Failed to open Git repository at {}", path.display()))?;
+
+    let storage = LocalStorage::new(path).context("Failed to load local storage")?;
+    let entries = storage.export_entries().context("Failed to read stored secrets")?;
+
+    let mut descriptions = Vec::new();
+    let mut secrets = Vec::new();
+    for (storage_key, entry) in &entries {
+        if entry.value.is_empty() {
+            continue;
+        }
+        descriptions.push(format!("{} ({})", entry.file_path.display(), storage_key));
+        secrets.push(entry.value.as_str());
+    }
+
+    if secrets.is_empty() {
+        println!("{} No stored secrets to scan for", "ℹ".bright_blue());
+        return Ok(());
+    }
+
+    let automaton = AhoCorasick::new(&secrets).context("Failed to build secret matcher")?;
+
+    let mut revwalk = repo.revwalk().context("Failed to start a history walk")?;
+    revwalk.push_glob("refs/*").context("Failed to enqueue refs")?;
+
+    let mut visited_blobs: HashSet<Oid> = HashSet::new();
+    let mut findings: Vec<(Oid, String, String)> = Vec::new();
+
+    for oid in revwalk {
+        let oid = oid.context("Failed to read a commit while walking history")?;
+        let commit = repo.find_commit(oid).context("Failed to load commit")?;
+        let tree = commit.tree().context("Failed to load commit tree")?;
+
+        let parent_tree = commit.parents().next().and_then(|p| p.tree().ok());
+        let diff = repo
+            .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)
+            .context("Failed to diff commit against its parent")?;
+
+        for delta in diff.deltas() {
+            let file = delta.new_file();
+            let Some(blob_oid) = Some(file.id()).filter(|id| !id.is_zero()) else {
+                continue;
+            };
+            if !visited_blobs.insert(blob_oid) {
+                continue;
+            }
+
+            let Ok(blob) = repo.find_blob(blob_oid) else {
+                continue;
+            };
+            if blob.is_binary() || blob.size() as u64 > MAX_BLOB_SIZE {
+                continue;
+            }
+            let Ok(content) = std::str::from_utf8(blob.content()) else {
+                continue;
+            };
+
+            for found in automaton.find_iter(content) {
+                findings.push((
+                    oid,
+                    file.path().map(|p| p.display().to_string()).unwrap_or_default(),
+                    descriptions[found.pattern().as_usize()].clone(),
+                ));
+            }
+        }
+    }
+
+    if findings.is_empty() {
+        println!("{} No leaked secrets found in history", "✓".bright_green());
+        return Ok(());
+    }
+
+    for (commit_oid, file_path, description) in &findings {
+        println!(
+            "{} {} in {} at {}",
+            "✗".bright_red(),
+            description.bright_yellow(),
+            file_path.bright_cyan(),
+            commit_oid.to_string().bright_black()
+        );
+    }
+
+    println!(
+        "\n{} {} leaked secret(s) found across history",
+        "✗".bright_red(),
+        findings.len()
+    );
+    anyhow::bail!("scan-history found leaked secrets");
+}
@@ -1,25 +1,50 @@
 use anyhow::{Context, Result};
-use std::path::PathBuf;
-use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::io::{Read, Write};
+use whiteout::config::Config;
 use whiteout::Whiteout;
 
 pub fn handle(file: Option<PathBuf>) -> Result<()> {
-    let whiteout = Whiteout::new(".")
+    let project_root = Path::new(".");
+    let whiteout = Whiteout::new(project_root)
         .context("Failed to load Whiteout configuration")?;
-    
-    let (content, file_path) = if let Some(file_path) = file {
-        let content = std::fs::read_to_string(&file_path)
+
+    let (raw, file_path) = if let Some(file_path) = file {
+        let raw = std::fs::read(&file_path)
             .with_context(|| format!("Failed to read file: {}", file_path.display()))?;
-        (content, file_path)
+        (raw, file_path)
     } else {
-        let mut buffer = String::new();
-        std::io::stdin().read_to_string(&mut buffer)?;
+        let mut buffer = Vec::new();
+        std::io::stdin().read_to_end(&mut buffer)?;
         (buffer, PathBuf::from("stdin"))
     };
-    
-    let smudged = whiteout.smudge(&content, &file_path)
-        .context("Failed to apply smudge filter")?;
+
+    // See the matching comment in clean.rs: a file that isn't valid UTF-8
+    // can't contain whiteout's markers, so it's passed through untouched
+    // rather than failing the filter.
+    let content = match String::from_utf8(raw) {
+        Ok(content) => content,
+        Err(e) => {
+            std::io::stdout().write_all(e.as_bytes())?;
+            return Ok(());
+        }
+    };
+
+    let smudged = if encryption_enabled(project_root)? {
+        whiteout::agent::smudge_via_agent(project_root, &content, &file_path)
+            .context("Failed to smudge via whiteout agent")?
+    } else {
+        whiteout.smudge(&content, &file_path)
+            .context("Failed to apply smudge filter")?
+    };
     print!("{}", smudged);
-    
+
     Ok(())
-}
\ No newline at end of file
+}
+
+/// When encryption is on we route through the agent so the passphrase is
+/// only ever asked for once per session instead of once per invocation.
+fn encryption_enabled(project_root: &Path) -> Result<bool> {
+    let config = Config::load_or_default(project_root)?;
+    Ok(config.data.encryption.enabled)
+}
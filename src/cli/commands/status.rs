@@ -1,107 +1,222 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use clap::ValueEnum;
 use colored::Colorize;
+use serde::Serialize;
+use std::path::Path;
 use std::process::Command;
 use walkdir::WalkDir;
+use whiteout::matcher;
+use whiteout::parser::{Decoration, Parser};
 use whiteout::Whiteout;
 
-pub fn handle(verbose: bool) -> Result<()> {
-    println!("{}", "Whiteout Status".bright_blue().bold());
-    println!("{}", "===============".bright_blue());
-    
-    // Check if in a Git repository
-    let git_check = Command::new("git")
+/// Output format for `whiteout status`. `Text` (the default) is colorized
+/// and meant for a human; `Json`/`Ndjson` are for editor plugins, pre-commit
+/// hooks, and CI gates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Ndjson,
+}
+
+#[derive(Serialize)]
+struct DecorationEntry {
+    line: usize,
+    kind: &'static str,
+    raw: String,
+}
+
+#[derive(Serialize)]
+struct FileStatus {
+    path: String,
+    decorations: usize,
+    entries: Vec<DecorationEntry>,
+}
+
+#[derive(Serialize)]
+struct Summary {
+    files: usize,
+    total_decorations: usize,
+    initialized: bool,
+    in_git_repo: bool,
+}
+
+#[derive(Serialize)]
+struct StatusReport {
+    #[serde(flatten)]
+    summary: Summary,
+    decorated_files: Vec<FileStatus>,
+}
+
+pub fn handle(verbose: bool, format: OutputFormat) -> Result<()> {
+    let in_git_repo = Command::new("git")
         .args(&["rev-parse", "--git-dir"])
-        .output()?;
-    
-    if !git_check.status.success() {
-        println!("{} Not in a Git repository", "⚠".bright_yellow());
-        return Ok(());
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false);
+
+    if !in_git_repo {
+        return emit(
+            format,
+            verbose,
+            StatusReport {
+                summary: Summary { files: 0, total_decorations: 0, initialized: false, in_git_repo: false },
+                decorated_files: Vec::new(),
+            },
+            Some("Not in a Git repository"),
+        );
     }
-    
-    // Check if whiteout is initialized
-    let _whiteout = match Whiteout::new(".") {
+
+    let whiteout = match Whiteout::new(".") {
         Ok(w) => w,
         Err(_) => {
-            println!("{} Whiteout not initialized in this project", "⚠".bright_yellow());
-            println!("Run {} to initialize", "whiteout init".bright_cyan());
-            return Ok(());
+            return emit(
+                format,
+                verbose,
+                StatusReport {
+                    summary: Summary { files: 0, total_decorations: 0, initialized: false, in_git_repo: true },
+                    decorated_files: Vec::new(),
+                },
+                Some("Whiteout not initialized in this project"),
+            );
         }
     };
-    
-    println!("{} Whiteout is configured", "✓".bright_green());
-    
-    // Find decorated files
+
+    let parser = Parser::new();
+    let whiteoutignore = matcher::load(Path::new(".")).context("Failed to load .whiteoutignore")?;
+
     let mut decorated_files = Vec::new();
     let mut total_decorations = 0;
-    
+
     for entry in WalkDir::new(".")
         .follow_links(true)
         .into_iter()
+        .filter_entry(|e| e.file_name() != ".git")
         .filter_map(|e| e.ok())
         .filter(|e| e.file_type().is_file())
     {
         let path = entry.path();
-        
-        // Skip hidden directories and common ignore patterns
-        if path.components().any(|c| {
-            c.as_os_str().to_string_lossy().starts_with('.')
-                || c.as_os_str() == "target"
-                || c.as_os_str() == "node_modules"
-        }) {
+        let relative = path.strip_prefix(".").unwrap_or(path);
+
+        if !whiteoutignore.matches(relative) {
+            continue;
+        }
+
+        let Ok(content) = std::fs::read_to_string(path) else {
+            continue;
+        };
+
+        let decorations = parser.parse(&content, Some(relative))?;
+        if decorations.is_empty() {
             continue;
         }
-        
-        if let Ok(content) = std::fs::read_to_string(path) {
-            let mut decorations = 0;
-            
-            // Count decorations
-            decorations += content.matches("@whiteout:").count();
-            decorations += content.matches("@whiteout-start").count();
-            decorations += content.matches("@whiteout-partial").count();
-            decorations += content.lines()
-                .filter(|l| l.trim() == "@whiteout")
-                .count();
-            
-            if decorations > 0 {
-                decorated_files.push((path.to_path_buf(), decorations));
-                total_decorations += decorations;
+
+        let lines: Vec<&str> = content.lines().collect();
+        let mut entries: Vec<DecorationEntry> =
+            decorations.iter().map(|d| decoration_entry(d, &lines)).collect();
+        entries.sort_by_key(|entry| entry.line);
+
+        total_decorations += entries.len();
+        decorated_files.push(FileStatus {
+            path: relative.display().to_string(),
+            decorations: entries.len(),
+            entries,
+        });
+    }
+
+    let report = StatusReport {
+        summary: Summary {
+            files: decorated_files.len(),
+            total_decorations,
+            initialized: true,
+            in_git_repo: true,
+        },
+        decorated_files,
+    };
+
+    emit(format, verbose, report, None)
+}
+
+/// Classifies a decoration for machine-readable output, using the raw
+/// source line to tell `simple` and `block-start` apart (both parse into
+/// [`Decoration::Block`]).
+fn decoration_entry(decoration: &Decoration, lines: &[&str]) -> DecorationEntry {
+    let raw_at = |line: usize| lines.get(line - 1).map(|l| l.trim().to_string()).unwrap_or_default();
+
+    match decoration {
+        Decoration::Inline { line, .. } => DecorationEntry { line: *line, kind: "inline", raw: raw_at(*line) },
+        Decoration::Block { start_line, .. } => {
+            let raw = raw_at(*start_line);
+            let kind = if raw.contains("@whiteout-start") { "block-start" } else { "simple" };
+            DecorationEntry { line: *start_line, kind, raw }
+        }
+        Decoration::Partial { line, .. } => DecorationEntry { line: *line, kind: "partial", raw: raw_at(*line) },
+    }
+}
+
+fn emit(format: OutputFormat, verbose: bool, report: StatusReport, hint: Option<&str>) -> Result<()> {
+    match format {
+        OutputFormat::Text => print_text(&report, verbose, hint),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+        OutputFormat::Ndjson => {
+            println!("{}", serde_json::to_string(&report.summary)?);
+            for file in &report.decorated_files {
+                println!("{}", serde_json::to_string(file)?);
             }
         }
     }
-    
-    if decorated_files.is_empty() {
+    Ok(())
+}
+
+fn print_text(report: &StatusReport, verbose: bool, hint: Option<&str>) {
+    println!("{}", "Whiteout Status".bright_blue().bold());
+    println!("{}", "===============".bright_blue());
+
+    if !report.summary.in_git_repo {
+        println!("{} {}", "⚠".bright_yellow(), hint.unwrap_or("Not in a Git repository"));
+        return;
+    }
+
+    if !report.summary.initialized {
+        println!("{} {}", "⚠".bright_yellow(), hint.unwrap_or("Whiteout not initialized in this project"));
+        println!("Run {} to initialize", "whiteout init".bright_cyan());
+        return;
+    }
+
+    println!("{} Whiteout is configured", "✓".bright_green());
+
+    if report.decorated_files.is_empty() {
         println!("\n{} No decorated files found", "ℹ".bright_blue());
-    } else {
-        println!("\n{}", format!("Found {} decorated files with {} total decorations:",
-            decorated_files.len(), total_decorations).bright_green());
-        
-        for (file, count) in &decorated_files {
-            if verbose {
-                println!("  {} {} ({} decorations)", 
-                    "•".bright_cyan(), 
-                    file.display(), 
-                    count);
-                
-                // Show decoration details
-                if let Ok(content) = std::fs::read_to_string(file) {
-                    for (line_num, line) in content.lines().enumerate() {
-                        if line.contains("@whiteout") {
-                            println!("      {} Line {}: {}", 
-                                "→".bright_black(),
-                                line_num + 1,
-                                line.trim().bright_black());
-                        }
-                    }
-                }
-            } else {
-                println!("  {} {}", "•".bright_cyan(), file.display());
+        return;
+    }
+
+    println!(
+        "\n{}",
+        format!(
+            "Found {} decorated files with {} total decorations:",
+            report.summary.files, report.summary.total_decorations
+        )
+        .bright_green()
+    );
+
+    for file in &report.decorated_files {
+        if verbose {
+            println!("  {} {} ({} decorations)", "•".bright_cyan(), file.path, file.decorations);
+            for entry in &file.entries {
+                println!(
+                    "      {} Line {} [{}]: {}",
+                    "→".bright_black(),
+                    entry.line,
+                    entry.kind,
+                    entry.raw.bright_black()
+                );
             }
-        }
-        
-        if !verbose {
-            println!("\n{}", "Tip: Use --verbose for detailed information".bright_cyan());
+        } else {
+            println!("  {} {}", "•".bright_cyan(), file.path);
         }
     }
-    
-    Ok(())
-}
\ No newline at end of file
+
+    if !verbose {
+        println!("\n{}", "Tip: Use --verbose for detailed information".bright_cyan());
+    }
+}
@@ -1,18 +1,55 @@
 use anyhow::{Context, Result};
 use colored::Colorize;
+use std::path::Path;
 use std::process::Command;
+use whiteout::agent::prompt;
+use whiteout::storage::branch::{self, SyncAction as BranchSyncAction};
+use whiteout::storage::bundle::{self, ConflictPolicy, MergeAction};
+use whiteout::storage::LocalStorage;
 
-pub fn handle(branch: Option<String>) -> Result<()> {
+use crate::cli::SyncAction;
+
+pub fn handle(action: SyncAction) -> Result<()> {
+    match action {
+        SyncAction::Branch { branch } => handle_branch(branch),
+        SyncAction::Export { bundle: bundle_path } => handle_export(&bundle_path),
+        SyncAction::Import {
+            bundle: bundle_path,
+            prefer_local,
+            prefer_incoming,
+            newest,
+            dry_run,
+        } => {
+            let policy = parse_conflict_policy(prefer_local, prefer_incoming, newest)?;
+            handle_import(&bundle_path, policy, dry_run)
+        }
+    }
+}
+
+fn parse_conflict_policy(
+    prefer_local: bool,
+    prefer_incoming: bool,
+    newest: bool,
+) -> Result<ConflictPolicy> {
+    match (prefer_local, prefer_incoming, newest) {
+        (true, false, false) => Ok(ConflictPolicy::PreferLocal),
+        (false, true, false) => Ok(ConflictPolicy::PreferIncoming),
+        (false, false, true) | (false, false, false) => Ok(ConflictPolicy::Newest),
+        _ => anyhow::bail!("Specify at most one of --prefer-local, --prefer-incoming, --newest"),
+    }
+}
+
+fn handle_branch(branch: Option<String>) -> Result<()> {
     println!("{}", "Syncing local values across branches...".bright_blue());
-    
+
     // Get current branch
     let current_branch = Command::new("git")
         .args(&["rev-parse", "--abbrev-ref", "HEAD"])
         .output()
         .context("Failed to get current branch")?;
-    
+
     let current = String::from_utf8_lossy(&current_branch.stdout).trim().to_string();
-    
+
     let target = if let Some(b) = branch {
         b
     } else {
@@ -21,46 +58,134 @@ pub fn handle(branch: Option<String>) -> Result<()> {
             .args(&["branch", "-a"])
             .output()
             .context("Failed to list branches")?;
-        
+
         println!("{}", "Available branches:".bright_cyan());
         println!("{}", String::from_utf8_lossy(&branches.stdout));
-        
+
         println!("\n{} Specify target branch with --branch", "ℹ".bright_blue());
         return Ok(());
     };
-    
+
     println!("  {} Current branch: {}", "•".bright_cyan(), current.bright_yellow());
     println!("  {} Target branch: {}", "•".bright_cyan(), target.bright_yellow());
-    
+
     // Check if .whiteout directory exists
     let whiteout_dir = std::path::Path::new(".whiteout");
     if !whiteout_dir.exists() {
         println!("{} No .whiteout directory found", "⚠".bright_yellow());
         return Ok(());
     }
-    
+
     // Read current branch's local values
     let local_file = whiteout_dir.join("local.toml");
     if !local_file.exists() {
         println!("{} No local values to sync", "⚠".bright_yellow());
         return Ok(());
     }
-    
-    let _local_content = std::fs::read_to_string(&local_file)
-        .context("Failed to read local values")?;
-    
-    // TODO: Implement branch-specific storage
-    // For now, just copy the local values
-    println!("{} Syncing local values...", "→".bright_green());
-    
-    // This is a simplified implementation
-    // In a real implementation, we'd:
-    // 1. Store branch-specific local values
-    // 2. Merge/conflict resolution for overlapping keys
-    // 3. Handle different file paths across branches
-    
-    println!("{} Local values synchronized", "✓".bright_green());
-    println!("\n{}", "Note: Full branch-specific sync not yet implemented".bright_yellow());
-    
+
+    let project_root = Path::new(".");
+    let storage = LocalStorage::new(project_root).context("Failed to load local storage")?;
+
+    // Three-way merge: our live entries against the target branch's last
+    // snapshot, using the current branch's own last snapshot as the common
+    // ancestor that tells which side actually changed something since the
+    // last sync. Keyed by decoration identity, not file path, so a file
+    // renamed on either branch still reconciles correctly.
+    let ancestor = branch::load_snapshot(project_root, &current)?;
+    let ours = branch::current_snapshot(&storage)?;
+    let theirs = branch::load_snapshot(project_root, &target)?;
+
+    let plan = branch::plan_merge(&ancestor, &ours, &theirs);
+
+    println!("\n{}", "Sync plan:".bright_blue().bold());
+    for plan_entry in &plan {
+        if plan_entry.action == BranchSyncAction::Unchanged {
+            continue;
+        }
+        let (symbol, label) = match plan_entry.action {
+            BranchSyncAction::Added => ("+".bright_green(), "add"),
+            BranchSyncAction::Updated => ("~".bright_yellow(), "update"),
+            BranchSyncAction::Conflict => ("!".bright_red(), "conflict"),
+            BranchSyncAction::Unchanged => unreachable!(),
+        };
+        println!("  {} {} ({})", symbol, plan_entry.identity, label);
+    }
+
+    let (merged, conflicts) = branch::apply_merge(&storage, &ours, &theirs, &plan)?;
+
+    // Record our post-merge state so the next sync against either branch
+    // has an accurate common ancestor.
+    branch::save_snapshot(&storage, project_root, &current)?;
+
+    println!(
+        "\n{} Synced {} value(s), {} conflict(s)",
+        "✓".bright_green(),
+        merged,
+        conflicts
+    );
+    if conflicts > 0 {
+        println!(
+            "{} Conflicting values were written with <<<<<<< / >>>>>>> markers -- resolve by hand and re-run sync",
+            "⚠".bright_yellow()
+        );
+    }
+
+    Ok(())
+}
+
+fn handle_export(bundle_path: &Path) -> Result<()> {
+    let storage = LocalStorage::new(".").context("Failed to load local storage")?;
+
+    let passphrase = prompt::default_prompt()
+        .prompt("Bundle passphrase")
+        .context("Failed to read bundle passphrase")?;
+
+    let bundle_bytes = bundle::export(&storage, &passphrase).context("Failed to export bundle")?;
+    std::fs::write(bundle_path, bundle_bytes)
+        .with_context(|| format!("Failed to write bundle to {}", bundle_path.display()))?;
+
+    println!(
+        "{} Exported local values to {}",
+        "✓".bright_green(),
+        bundle_path.display().to_string().bright_cyan()
+    );
+
+    Ok(())
+}
+
+fn handle_import(bundle_path: &Path, policy: ConflictPolicy, dry_run: bool) -> Result<()> {
+    let storage = LocalStorage::new(".").context("Failed to load local storage")?;
+
+    let bundle_bytes = std::fs::read(bundle_path)
+        .with_context(|| format!("Failed to read bundle from {}", bundle_path.display()))?;
+
+    let passphrase = prompt::default_prompt()
+        .prompt("Bundle passphrase")
+        .context("Failed to read bundle passphrase")?;
+
+    let (incoming, plan) = bundle::plan_import(&storage, &bundle_bytes, &passphrase, policy)
+        .context("Failed to open bundle")?;
+
+    for plan_entry in &plan {
+        let (symbol, label) = match plan_entry.action {
+            MergeAction::Added => ("+".bright_green(), "add"),
+            MergeAction::Updated => ("~".bright_yellow(), "update"),
+            MergeAction::Skipped => ("=".bright_black(), "skip"),
+        };
+        println!("  {} {} ({})", symbol, plan_entry.storage_key, label);
+    }
+
+    if dry_run {
+        println!(
+            "\n{} Dry run: no changes written ({} would change)",
+            "ℹ".bright_blue(),
+            plan.iter().filter(|p| p.action != MergeAction::Skipped).count()
+        );
+        return Ok(());
+    }
+
+    let written = bundle::apply_import(&storage, &incoming, &plan)?;
+    println!("{} Imported {} value(s)", "✓".bright_green(), written);
+
     Ok(())
-}
\ No newline at end of file
+}
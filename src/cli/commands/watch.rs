@@ -0,0 +1,159 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use notify::{Event, RecursiveMode, Watcher};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::Duration;
+use whiteout::ignore::IgnoreSet;
+use whiteout::matcher;
+use whiteout::storage::atomic::AtomicFile;
+use whiteout::validation::find_secrets;
+use whiteout::Whiteout;
+
+/// Events arriving within this window of each other are coalesced into a
+/// single re-clean pass, so a burst of saves (editor swap files, formatters)
+/// only triggers one round of work.
+const DEBOUNCE: Duration = Duration::from_millis(50);
+
+/// Long-running watcher that re-cleans decorated files as they change.
+/// Modeled on watchexec's event loop: a filesystem notifier feeds a channel,
+/// a short debounce window coalesces bursts, and surviving paths are
+/// filtered through the ignore engine and `[patterns]` before being
+/// re-cleaned and checked for undecorated secrets.
+pub fn handle(path: &Path) -> Result<()> {
+    let project_root = path
+        .canonicalize()
+        .context("Failed to resolve project root")?;
+    let whiteout = Whiteout::new(&project_root).context(
+        "Whiteout is not initialized in this project (run `whiteout init` first)",
+    )?;
+
+    println!(
+        "{} Watching {} for changes (Ctrl+C to stop)...",
+        "→".bright_cyan(),
+        project_root.display()
+    );
+
+    let running = Arc::new(AtomicBool::new(true));
+    {
+        let running = Arc::clone(&running);
+        ctrlc::set_handler(move || running.store(false, Ordering::SeqCst))
+            .context("Failed to install SIGINT handler")?;
+    }
+
+    let (tx, rx) = channel::<notify::Result<Event>>();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = tx.send(event);
+    })
+    .context("Failed to create filesystem watcher")?;
+    watcher
+        .watch(&project_root, RecursiveMode::Recursive)
+        .context("Failed to watch project root")?;
+
+    while running.load(Ordering::SeqCst) {
+        let first = match rx.recv_timeout(Duration::from_millis(200)) {
+            Ok(event) => event,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+
+        let mut changed = changed_paths(first);
+        loop {
+            match rx.recv_timeout(DEBOUNCE) {
+                Ok(event) => changed.extend(changed_paths(event)),
+                Err(_) => break,
+            }
+        }
+
+        if let Err(e) = process_changes(&whiteout, &project_root, changed) {
+            tracing::warn!("whiteout watch: failed to process changes: {}", e);
+        }
+    }
+
+    println!("\n{} Watcher stopped", "✓".bright_green());
+    Ok(())
+}
+
+fn changed_paths(event: notify::Result<Event>) -> HashSet<PathBuf> {
+    match event {
+        Ok(event) => event.paths.into_iter().collect(),
+        Err(e) => {
+            tracing::warn!("whiteout watch: notify error: {}", e);
+            HashSet::new()
+        }
+    }
+}
+
+fn process_changes(
+    whiteout: &Whiteout,
+    project_root: &Path,
+    changed: HashSet<PathBuf>,
+) -> Result<()> {
+    let ignore = IgnoreSet::load(project_root).context("Failed to load ignore files")?;
+    let whiteoutignore = matcher::load(project_root).context("Failed to load .whiteoutignore")?;
+
+    for file_path in changed {
+        let Ok(relative) = file_path.strip_prefix(project_root) else {
+            continue;
+        };
+        let relative_str = relative.to_string_lossy();
+        if relative_str.is_empty() || ignore.is_ignored(&relative_str) {
+            continue;
+        }
+        if !whiteoutignore.matches(relative) {
+            continue;
+        }
+        if !whiteout.config().matches(relative)? {
+            continue;
+        }
+
+        let atomic = AtomicFile::new(&file_path)?;
+        if !atomic.exists() {
+            println!("{} {} removed", "-".bright_black(), relative.display());
+            continue;
+        }
+
+        let content = match atomic.read() {
+            Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+            Err(e) => {
+                tracing::warn!("whiteout watch: failed to read {}: {}", relative.display(), e);
+                continue;
+            }
+        };
+
+        let cleaned = whiteout.clean(&content, relative)?;
+        print_decoration_diff(relative, &content, &cleaned);
+
+        for finding in find_secrets(&cleaned)? {
+            println!(
+                "  {} {} in {}:{} - {}",
+                "⚠".bright_yellow(),
+                finding.name,
+                relative.display(),
+                finding.line,
+                finding.text.bright_red()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints the lines where the committed version diverges from the local
+/// one, skipping files that have no decorations to show.
+fn print_decoration_diff(relative: &Path, content: &str, cleaned: &str) {
+    if content == cleaned {
+        return;
+    }
+
+    println!("{} {}", "~".bright_yellow(), relative.display());
+    for (local_line, committed_line) in content.lines().zip(cleaned.lines()) {
+        if local_line != committed_line {
+            println!("  {} {}", "-".bright_green(), local_line.trim());
+            println!("  {} {}", "+".bright_yellow(), committed_line.trim());
+        }
+    }
+}
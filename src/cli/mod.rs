@@ -18,6 +18,11 @@ pub enum Commands {
     Init {
         #[arg(short, long, default_value = ".")]
         path: PathBuf,
+        #[arg(
+            long = "pattern",
+            help = "Glob to scope the filter to (repeatable, e.g. --pattern '*.rs' --pattern 'config/**'); defaults to '*' (every file)"
+        )]
+        patterns: Vec<String>,
     },
     
     #[command(about = "Preview what will be committed vs what stays local")]
@@ -58,6 +63,8 @@ pub enum Commands {
     Status {
         #[arg(short, long, help = "Show detailed information")]
         verbose: bool,
+        #[arg(long, value_enum, default_value = "text", help = "Output format")]
+        format: commands::status::OutputFormat,
     },
     
     #[command(about = "Apply clean filter (for Git integration)")]
@@ -65,6 +72,12 @@ pub enum Commands {
         #[arg(help = "File path (optional, reads from stdin if not provided)")]
         file: Option<PathBuf>,
     },
+
+    #[command(about = "Clean every in-scope file in parallel, populating the smudge cache")]
+    CleanAll {
+        #[arg(short, long, default_value = ".", help = "Directory to clean")]
+        path: PathBuf,
+    },
     
     #[command(about = "Apply smudge filter (for Git integration)")]
     Smudge {
@@ -78,11 +91,94 @@ pub enum Commands {
         action: ConfigAction,
     },
     
-    #[command(about = "Sync local values across branches")]
+    #[command(about = "Sync local values across branches or machines")]
     Sync {
+        #[command(subcommand)]
+        action: SyncAction,
+    },
+
+    #[command(about = "Manage the background agent that caches the vault passphrase")]
+    Agent {
+        #[arg(long, help = "Run the agent in the foreground instead of detaching")]
+        foreground: bool,
+        #[arg(long, help = "Wipe the agent's in-memory key and exit")]
+        lock: bool,
+    },
+
+    #[command(about = "Manage recipients who can decrypt the shared vault")]
+    Recipient {
+        #[command(subcommand)]
+        action: RecipientAction,
+    },
+
+    #[command(about = "Scan the project tree for undecorated secrets, respecting ignore files")]
+    Scan {
+        #[arg(short, long, default_value = ".", help = "Directory to scan")]
+        path: PathBuf,
+    },
+
+    #[command(about = "Audit Git history for commits that leaked a stored secret value")]
+    ScanHistory {
+        #[arg(short, long, default_value = ".", help = "Path to the Git repository")]
+        path: PathBuf,
+    },
+
+    #[command(about = "Show exactly what the clean filter would strip from a file, decoration by decoration")]
+    Audit {
+        #[arg(help = "File path to audit")]
+        file: PathBuf,
+    },
+
+    #[command(about = "Watch the project tree and re-clean decorated files as they change")]
+    Watch {
+        #[arg(short, long, default_value = ".", help = "Directory to watch")]
+        path: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SyncAction {
+    #[command(about = "Sync local values across branches")]
+    Branch {
         #[arg(short, long, help = "Target branch")]
         branch: Option<String>,
     },
+    #[command(about = "Export local values to an encrypted, portable bundle")]
+    Export {
+        #[arg(help = "Path to write the bundle to")]
+        bundle: PathBuf,
+    },
+    #[command(about = "Import local values from an encrypted, portable bundle")]
+    Import {
+        #[arg(help = "Path to read the bundle from")]
+        bundle: PathBuf,
+        #[arg(long, help = "On conflict, keep the local entry")]
+        prefer_local: bool,
+        #[arg(long, help = "On conflict, keep the incoming entry")]
+        prefer_incoming: bool,
+        #[arg(long, help = "On conflict, keep whichever entry has the newer timestamp (default)")]
+        newest: bool,
+        #[arg(long, help = "Show what would change without writing anything")]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum RecipientAction {
+    #[command(about = "Add a recipient, rewrapping the DEK for their public key")]
+    Add {
+        #[arg(help = "Recipient's base64 X25519 public key")]
+        public_key: String,
+    },
+    #[command(about = "Remove a recipient")]
+    Remove {
+        #[arg(help = "Recipient's base64 X25519 public key")]
+        public_key: String,
+    },
+    #[command(about = "List configured recipients")]
+    List,
+    #[command(about = "Print this machine's public key")]
+    Whoami,
 }
 
 #[derive(Subcommand)]
@@ -98,4 +194,14 @@ pub enum ConfigAction {
     },
     #[command(about = "List all configuration values")]
     List,
+    #[command(about = "Add a file pattern that whiteout should process")]
+    AddPattern {
+        #[arg(help = "Gitignore-style glob, e.g. src/**/*.rs or !vendor/**")]
+        pattern: String,
+    },
+    #[command(about = "Remove a previously added file pattern")]
+    RemovePattern {
+        #[arg(help = "Exact pattern string to remove")]
+        pattern: String,
+    },
 }
\ No newline at end of file
@@ -1,3 +1,4 @@
+pub mod patterns;
 pub mod project;
 
 pub use project::Config;
@@ -10,12 +11,28 @@ pub struct ConfigData {
     pub encryption: EncryptionConfig,
     pub git: GitConfig,
     pub decorations: DecorationConfig,
+    #[serde(default)]
+    pub storage: StorageConfig,
+    #[serde(default)]
+    pub patterns: PatternsConfig,
+    #[serde(default)]
+    pub transform: TransformConfig,
+    #[serde(default)]
+    pub comment_syntax: Vec<CommentSyntaxOverride>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EncryptionConfig {
     pub enabled: bool,
     pub algorithm: String,
+    /// Argon2 cost overrides. `None` keeps whatever is already recorded in
+    /// the on-disk KDF header (or its defaults for a brand new vault).
+    #[serde(default)]
+    pub kdf_m_cost: Option<u32>,
+    #[serde(default)]
+    pub kdf_t_cost: Option<u32>,
+    #[serde(default)]
+    pub kdf_p_cost: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +50,39 @@ pub struct DecorationConfig {
     pub partial_pattern: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageConfig {
+    pub backend: String,
+    /// Values larger than this (in bytes) are zstd-compressed before being
+    /// written. Small values stay uncompressed so `local.toml` remains
+    /// diff-able and human-readable.
+    #[serde(default = "default_compress_threshold")]
+    pub compress_threshold: usize,
+    /// zstd compression level used for values over `compress_threshold`.
+    /// Higher is smaller but slower; 0 means zstd's own default (currently
+    /// level 3).
+    #[serde(default = "default_compress_level")]
+    pub compress_level: i32,
+}
+
+fn default_compress_threshold() -> usize {
+    4096
+}
+
+fn default_compress_level() -> i32 {
+    0
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            backend: "local".to_string(),
+            compress_threshold: default_compress_threshold(),
+            compress_level: default_compress_level(),
+        }
+    }
+}
+
 impl Default for ConfigData {
     fn default() -> Self {
         Self {
@@ -40,6 +90,9 @@ impl Default for ConfigData {
             encryption: EncryptionConfig {
                 enabled: false,
                 algorithm: "aes-256-gcm".to_string(),
+                kdf_m_cost: None,
+                kdf_t_cost: None,
+                kdf_p_cost: None,
             },
             git: GitConfig {
                 auto_sync: true,
@@ -52,6 +105,66 @@ impl Default for ConfigData {
                 block_end: "@whiteout-end".to_string(),
                 partial_pattern: r"\[\[.*\|\|.*\]\]".to_string(),
             },
+            storage: StorageConfig::default(),
+            patterns: PatternsConfig::default(),
+            transform: TransformConfig::default(),
+            comment_syntax: Vec::new(),
+        }
+    }
+}
+
+/// Gitignore-style globs assigning the `filter=whiteout` attribute.
+/// Patterns are ordered and evaluated last-match-wins; `setup_git_config`
+/// translates them directly into `.gitattributes` lines, and
+/// `Config::matches` consults them before clean/smudge process a file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatternsConfig {
+    pub patterns: Vec<String>,
+}
+
+impl Default for PatternsConfig {
+    fn default() -> Self {
+        Self {
+            patterns: vec!["*".to_string()],
         }
     }
+}
+
+/// Which `transform::registry::Transform` providers a project's decorations
+/// may dispatch to. Narrowing this list is mostly a safety rail -- e.g. a
+/// project that wants to forbid `redact()` because it wants every value
+/// recoverable from committed text should be able to, without a code change.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransformConfig {
+    pub enabled_providers: Vec<String>,
+}
+
+impl Default for TransformConfig {
+    fn default() -> Self {
+        Self {
+            enabled_providers: vec![
+                "identity".to_string(),
+                "redact".to_string(),
+                "env-var".to_string(),
+            ],
+        }
+    }
+}
+
+/// Teaches `Parser`'s `comment_syntax` registry about a file extension it
+/// doesn't already know (or overrides one it knows incorrectly for this
+/// project), without a code change -- see
+/// `parser::comment_syntax::register_language`, which each entry here is
+/// registered through when the config loads.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommentSyntaxOverride {
+    pub extension: String,
+    /// Line-comment lead-ins, e.g. `["//"]`. Leave empty for a
+    /// block-comment-only language (CSS, HTML).
+    #[serde(default)]
+    pub line: Vec<String>,
+    #[serde(default)]
+    pub block_open: Option<String>,
+    #[serde(default)]
+    pub block_close: Option<String>,
 }
\ No newline at end of file
@@ -0,0 +1,179 @@
+//! A small gitignore/gitattributes-style glob matcher for scoping which
+//! files whiteout's clean/smudge filters actually touch.
+//!
+//! Each pattern is compiled into a regex following the same rules git uses
+//! for `.gitignore`/`.gitattributes`: `*` matches any run of characters
+//! except `/`, `**` spans directory separators, a leading `!` negates the
+//! pattern, and patterns are evaluated in order with last-match-wins. A
+//! pattern containing a `/` anywhere but the end is anchored to the config
+//! root; a pattern with no `/` matches a file at any depth.
+
+use anyhow::{Context, Result};
+use regex::Regex;
+
+/// A compiled, ordered set of patterns. Built once from the strings in
+/// `[patterns]` and reused to answer `matches` for every candidate path.
+#[derive(Debug, Clone)]
+pub struct PatternSet {
+    entries: Vec<(bool, Regex)>,
+}
+
+impl PatternSet {
+    /// Compiles `patterns` in order. Returns an error if any pattern fails
+    /// to translate into a valid regex.
+    pub fn compile(patterns: &[String]) -> Result<Self> {
+        let entries = patterns
+            .iter()
+            .map(|pattern| compile_one(pattern))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { entries })
+    }
+
+    /// Whether `path` is selected by this pattern set, applying last-match-
+    /// wins across all configured patterns. A path that matches nothing
+    /// (including an empty pattern set) is not selected.
+    pub fn matches(&self, path: &str) -> bool {
+        let normalized = path.replace('\\', "/");
+        let mut selected = false;
+        for (negated, regex) in &self.entries {
+            if regex.is_match(&normalized) {
+                selected = !negated;
+            }
+        }
+        selected
+    }
+}
+
+fn compile_one(pattern: &str) -> Result<(bool, Regex)> {
+    compile_scoped(pattern, "")
+}
+
+/// Compiles `pattern` the same way as [`compile_one`], but anchors it
+/// under `base` (a `/`-terminated, repo-root-relative directory, or `""`
+/// for the root) instead of the repo root. Used by the ignore-file walker
+/// so a pattern written in `src/.gitignore` is scoped to `src/` rather than
+/// matching anywhere in the tree.
+pub(crate) fn compile_scoped(pattern: &str, base: &str) -> Result<(bool, Regex)> {
+    let (negated, body) = match pattern.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, pattern),
+    };
+    let body = body.strip_prefix('/').unwrap_or(body);
+
+    let trimmed = body.trim_end_matches('/');
+    let anchored = !trimmed.starts_with("**/") && trimmed.contains('/');
+
+    let mut regex_str = String::from("^");
+    regex_str.push_str(&regex_escape_literal(base));
+    if !anchored {
+        regex_str.push_str("(?:.*/)?");
+    }
+
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => {
+                if chars.peek() == Some(&'*') {
+                    chars.next();
+                    if chars.peek() == Some(&'/') {
+                        chars.next();
+                        regex_str.push_str("(?:.*/)?");
+                    } else {
+                        regex_str.push_str(".*");
+                    }
+                } else {
+                    regex_str.push_str("[^/]*");
+                }
+            }
+            '?' => regex_str.push_str("[^/]"),
+            '.' | '+' | '(' | ')' | '|' | '^' | '$' | '[' | ']' | '{' | '}' | '\\' => {
+                regex_str.push('\\');
+                regex_str.push(c);
+            }
+            _ => regex_str.push(c),
+        }
+    }
+    regex_str.push('$');
+
+    let regex = Regex::new(&regex_str)
+        .with_context(|| format!("Invalid pattern '{}'", pattern))?;
+    Ok((negated, regex))
+}
+
+/// Escapes a literal directory prefix (e.g. `"src/"`) for use inside a
+/// regex built char-by-char like the rest of this module.
+fn regex_escape_literal(literal: &str) -> String {
+    let mut escaped = String::with_capacity(literal.len());
+    for c in literal.chars() {
+        match c {
+            '.' | '+' | '*' | '?' | '(' | ')' | '|' | '^' | '$' | '[' | ']' | '{' | '}' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(patterns: &[&str]) -> PatternSet {
+        let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+        PatternSet::compile(&patterns).expect("patterns should compile")
+    }
+
+    #[test]
+    fn test_unanchored_star_matches_any_depth() {
+        let set = set(&["*.py"]);
+        assert!(set.matches("main.py"));
+        assert!(set.matches("src/lib/main.py"));
+        assert!(!set.matches("main.rs"));
+    }
+
+    #[test]
+    fn test_anchored_pattern_only_matches_from_root() {
+        let set = set(&["src/config.rs"]);
+        assert!(set.matches("src/config.rs"));
+        assert!(!set.matches("other/src/config.rs"));
+    }
+
+    #[test]
+    fn test_double_star_spans_directories() {
+        let set = set(&["src/**/*.rs"]);
+        assert!(set.matches("src/main.rs"));
+        assert!(set.matches("src/a/b/c.rs"));
+        assert!(!set.matches("tests/a.rs"));
+    }
+
+    #[test]
+    fn test_negation_with_last_match_wins() {
+        let set = set(&["*.rs", "!vendor/**"]);
+        assert!(set.matches("src/main.rs"));
+        assert!(!set.matches("vendor/crate/lib.rs"));
+    }
+
+    #[test]
+    fn test_order_determines_last_match_wins() {
+        let set = set(&["!*.rs", "src/*.rs"]);
+        assert!(set.matches("src/main.rs"));
+        assert!(!set.matches("tests/main.rs"));
+    }
+
+    #[test]
+    fn test_empty_pattern_set_matches_nothing() {
+        let set = set(&[]);
+        assert!(!set.matches("anything.rs"));
+    }
+
+    #[test]
+    fn test_scoped_pattern_is_anchored_under_base() {
+        let (negated, regex) = compile_scoped("*.log", "src/").expect("should compile");
+        assert!(!negated);
+        assert!(regex.is_match("src/debug.log"));
+        assert!(!regex.is_match("debug.log"));
+        assert!(!regex.is_match("other/debug.log"));
+    }
+}
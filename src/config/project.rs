@@ -2,49 +2,123 @@ use anyhow::{Context, Result};
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use crate::parser::comment_syntax;
+
+use super::patterns::PatternSet;
 use super::ConfigData;
 
+const GITATTRIBUTES_MANAGED_START: &str = "# whiteout:patterns:start";
+const GITATTRIBUTES_MANAGED_END: &str = "# whiteout:patterns:end";
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub data: ConfigData,
     pub path: PathBuf,
+    /// `[patterns]` compiled to regexes once here, rather than per call to
+    /// `matches` — `scan`/`watch`/`status` call `matches` once per file
+    /// walked, so recompiling on every call would mean recompiling the same
+    /// globs thousands of times over a single directory walk.
+    compiled_patterns: PatternSet,
 }
 
 impl Config {
     pub fn load_or_default(project_root: impl AsRef<Path>) -> Result<Self> {
         let config_path = project_root.as_ref().join(".whiteout").join("config.toml");
-        
-        let data = if config_path.exists() {
+
+        let data: ConfigData = if config_path.exists() {
             let content = fs::read_to_string(&config_path)
                 .context("Failed to read config file")?;
             toml::from_str(&content).context("Failed to parse config file")?
         } else {
             ConfigData::default()
         };
-        
+
+        let compiled_patterns = PatternSet::compile(&data.patterns.patterns)
+            .context("Failed to compile [patterns] globs")?;
+
+        // Teach the parser's comment-syntax registry about this project's
+        // `[[comment_syntax]]` overrides, if any -- the "without a code
+        // change" extension point `comment_syntax::register_language` exists for.
+        for override_entry in &data.comment_syntax {
+            let block = match (&override_entry.block_open, &override_entry.block_close) {
+                (Some(open), Some(close)) => Some((open.clone(), close.clone())),
+                _ => None,
+            };
+            comment_syntax::register_language(override_entry.extension.clone(), override_entry.line.clone(), block);
+        }
+
         Ok(Self {
             data,
             path: config_path,
+            compiled_patterns,
         })
     }
 
+    /// Recompiles `compiled_patterns` from the current `data.patterns.patterns`.
+    /// Callers that mutate `data.patterns.patterns` directly (rather than
+    /// going through a setter) must call this before the next `matches`
+    /// check reflects the change.
+    pub fn recompile_patterns(&mut self) -> Result<()> {
+        self.compiled_patterns = PatternSet::compile(&self.data.patterns.patterns)
+            .context("Failed to compile [patterns] globs")?;
+        Ok(())
+    }
+
     pub fn init(project_root: impl AsRef<Path>) -> Result<()> {
+        Self::init_with_patterns(project_root, &[])
+    }
+
+    /// Like [`Config::init`], but seeds a brand new config's `[patterns]`
+    /// with `patterns` (falling back to the usual `"*"` default when empty)
+    /// instead of always defaulting to matching every file. An existing
+    /// config.toml is left untouched, same as `init` -- scoping an
+    /// already-initialized project is `config set`/`add-pattern`'s job.
+    ///
+    /// This only decides the glob `init` writes into `.gitattributes` up
+    /// front; it doesn't replace the scoping `clean`/`smudge` already do per
+    /// path via [`crate::gitattributes::filter_state_for`], which keeps
+    /// honoring a hand-edited `.gitattributes` entry regardless of what was
+    /// seeded here.
+    pub fn init_with_patterns(project_root: impl AsRef<Path>, patterns: &[String]) -> Result<()> {
         let whiteout_dir = project_root.as_ref().join(".whiteout");
         fs::create_dir_all(&whiteout_dir).context("Failed to create .whiteout directory")?;
-        
+
         let config_path = whiteout_dir.join("config.toml");
         if !config_path.exists() {
-            let initial_config = ConfigData::default();
+            let mut initial_config = ConfigData::default();
+            if !patterns.is_empty() {
+                initial_config.patterns.patterns = patterns.to_vec();
+            }
             let content = toml::to_string_pretty(&initial_config)
                 .context("Failed to serialize initial config")?;
             fs::write(&config_path, content).context("Failed to write initial config")?;
         }
-        
-        Self::setup_git_config(project_root.as_ref())?;
-        
+
+        let patterns = Config::load_or_default(project_root.as_ref())?.data.patterns.patterns;
+        Self::setup_git_config(project_root.as_ref(), &patterns)?;
+
         Ok(())
     }
 
+    /// Whether `path` should be processed by the clean/smudge filters,
+    /// according to `[patterns]`. Consulted by `transform::clean`/`smudge`
+    /// so files outside the configured patterns pass through untouched.
+    pub fn matches(&self, path: &Path) -> Result<bool> {
+        Ok(self.compiled_patterns.matches(&path.to_string_lossy()))
+    }
+
+    /// Rewrites `.gitattributes` to reflect the current `[patterns]` list.
+    /// Called after `set`/`save` whenever the pattern list changes, so the
+    /// filter assignment on disk never drifts from the config.
+    pub fn sync_gitattributes(&self) -> Result<()> {
+        let project_root = self
+            .path
+            .parent()
+            .and_then(Path::parent)
+            .unwrap_or_else(|| Path::new("."));
+        Self::setup_git_config(project_root, &self.data.patterns.patterns)
+    }
+
     pub fn save(&self) -> Result<()> {
         let content = toml::to_string_pretty(&self.data)
             .context("Failed to serialize config")?;
@@ -72,9 +146,41 @@ impl Config {
                 self.data.git.pre_commit_check = value.parse()
                     .context("Invalid boolean value")?;
             }
+            "storage.backend" => {
+                if !["local", "toml", "redb"].contains(&value) {
+                    anyhow::bail!("Unknown storage backend: {}", value);
+                }
+                self.data.storage.backend = value.to_string();
+            }
+            "storage.compress_threshold" => {
+                self.data.storage.compress_threshold = value
+                    .parse()
+                    .context("Invalid compress_threshold value")?;
+            }
+            "storage.compress_level" => {
+                self.data.storage.compress_level = value
+                    .parse()
+                    .context("Invalid compress_level value")?;
+            }
+            "encryption.kdf_m_cost" => {
+                self.data.encryption.kdf_m_cost =
+                    Some(value.parse().context("Invalid Argon2 m_cost value")?);
+            }
+            "encryption.kdf_t_cost" => {
+                self.data.encryption.kdf_t_cost =
+                    Some(value.parse().context("Invalid Argon2 t_cost value")?);
+            }
+            "encryption.kdf_p_cost" => {
+                self.data.encryption.kdf_p_cost =
+                    Some(value.parse().context("Invalid Argon2 p_cost value")?);
+            }
+            "transform.enabled_providers" => {
+                self.data.transform.enabled_providers =
+                    value.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect();
+            }
             _ => anyhow::bail!("Unknown config key: {}", key),
         }
-        
+
         self.save()?;
         Ok(())
     }
@@ -84,37 +190,192 @@ impl Config {
             "encryption.enabled" => self.data.encryption.enabled.to_string(),
             "git.auto_sync" => self.data.git.auto_sync.to_string(),
             "git.pre_commit_check" => self.data.git.pre_commit_check.to_string(),
+            "storage.backend" => self.data.storage.backend.clone(),
+            "storage.compress_threshold" => self.data.storage.compress_threshold.to_string(),
+            "storage.compress_level" => self.data.storage.compress_level.to_string(),
+            "encryption.kdf_m_cost" => self
+                .data
+                .encryption
+                .kdf_m_cost
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| "default".to_string()),
+            "encryption.kdf_t_cost" => self
+                .data
+                .encryption
+                .kdf_t_cost
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| "default".to_string()),
+            "encryption.kdf_p_cost" => self
+                .data
+                .encryption
+                .kdf_p_cost
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| "default".to_string()),
+            "transform.enabled_providers" => self.data.transform.enabled_providers.join(","),
             _ => anyhow::bail!("Unknown config key: {}", key),
         };
-        
+
         Ok(value)
     }
 
-    fn setup_git_config(project_root: &Path) -> Result<()> {
+    /// Rewrites the whiteout-managed block of `.gitattributes` with one
+    /// line per configured pattern (`pattern filter=whiteout`, or
+    /// `pattern -filter` for a `!`-negated pattern), leaving anything
+    /// outside the block untouched.
+    fn setup_git_config(project_root: &Path, patterns: &[String]) -> Result<()> {
         let gitattributes_path = project_root.join(".gitattributes");
-        let mut content = if gitattributes_path.exists() {
+        let existing = if gitattributes_path.exists() {
             fs::read_to_string(&gitattributes_path)?
         } else {
             String::new()
         };
-        
-        if !content.contains("filter=whiteout") {
-            if !content.is_empty() && !content.ends_with('\n') {
-                content.push('\n');
+
+        let block = render_patterns_block(patterns);
+
+        let content = match (
+            existing.find(GITATTRIBUTES_MANAGED_START),
+            existing.find(GITATTRIBUTES_MANAGED_END),
+        ) {
+            (Some(start), Some(end)) => {
+                let after = end + GITATTRIBUTES_MANAGED_END.len();
+                let mut updated = existing[..start].to_string();
+                updated.push_str(&block);
+                updated.push_str(existing[after..].trim_start_matches('\n'));
+                updated
             }
-            content.push_str("* filter=whiteout\n");
-            fs::write(&gitattributes_path, content)?;
+            _ => {
+                let mut updated = existing;
+                if !updated.is_empty() && !updated.ends_with('\n') {
+                    updated.push('\n');
+                }
+                updated.push_str(&block);
+                updated
+            }
+        };
+
+        fs::write(&gitattributes_path, content)?;
+
+        Ok(())
+    }
+}
+
+fn render_patterns_block(patterns: &[String]) -> String {
+    let mut block = String::new();
+    block.push_str(GITATTRIBUTES_MANAGED_START);
+    block.push('\n');
+    for pattern in patterns {
+        match pattern.strip_prefix('!') {
+            Some(rest) => block.push_str(&format!("{} -filter\n", rest)),
+            None => block.push_str(&format!("{} filter=whiteout\n", pattern)),
         }
-        
+    }
+    block.push_str(GITATTRIBUTES_MANAGED_END);
+    block.push('\n');
+    block
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_matches_uses_patterns_compiled_at_load() -> Result<()> {
+        let mut config = Config::default();
+        config.data.patterns.patterns = vec!["src/**/*.rs".to_string(), "!src/vendor/**".to_string()];
+        config.recompile_patterns()?;
+
+        assert!(config.matches(&PathBuf::from("src/main.rs"))?);
+        assert!(!config.matches(&PathBuf::from("src/vendor/lib.rs"))?);
+        assert!(!config.matches(&PathBuf::from("tests/main.rs"))?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_matches_reflects_patterns_without_recompile_until_stale() -> Result<()> {
+        let mut config = Config::default();
+        config.data.patterns.patterns = vec![];
+        config.recompile_patterns()?;
+
+        config.data.patterns.patterns = vec!["*.toml".to_string()];
+
+        // Mutating `data.patterns.patterns` in place doesn't retroactively
+        // change the cache compiled at load time/last `recompile_patterns`.
+        assert!(!config.matches(&PathBuf::from("Cargo.toml"))?);
+
+        config.recompile_patterns()?;
+        assert!(config.matches(&PathBuf::from("Cargo.toml"))?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_init_with_patterns_seeds_config_and_gitattributes() -> Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+
+        Config::init_with_patterns(
+            temp_dir.path(),
+            &["*.js".to_string(), "*.ts".to_string()],
+        )?;
+
+        let config = Config::load_or_default(temp_dir.path())?;
+        assert_eq!(config.data.patterns.patterns, vec!["*.js", "*.ts"]);
+
+        let gitattributes = fs::read_to_string(temp_dir.path().join(".gitattributes"))?;
+        assert!(gitattributes.contains("*.js filter=whiteout"));
+        assert!(gitattributes.contains("*.ts filter=whiteout"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_init_with_patterns_does_not_override_existing_config() -> Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+
+        Config::init_with_patterns(temp_dir.path(), &["*.rs".to_string()])?;
+        Config::init_with_patterns(temp_dir.path(), &["*.py".to_string()])?;
+
+        let config = Config::load_or_default(temp_dir.path())?;
+        assert_eq!(config.data.patterns.patterns, vec!["*.rs"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_registers_comment_syntax_overrides() -> Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        Config::init(temp_dir.path())?;
+
+        let config_path = temp_dir.path().join(".whiteout").join("config.toml");
+        let mut data: ConfigData = toml::from_str(&fs::read_to_string(&config_path)?)?;
+        data.comment_syntax.push(super::super::CommentSyntaxOverride {
+            extension: "kts".to_string(),
+            line: vec!["//".to_string()],
+            block_open: None,
+            block_close: None,
+        });
+        fs::write(&config_path, toml::to_string_pretty(&data)?)?;
+
+        // Loading registers the override as a side effect, so the parser's
+        // comment-syntax lookups recognize `.kts` from here on.
+        Config::load_or_default(temp_dir.path())?;
+        let patterns = crate::parser::comment_syntax::patterns_for(Some(Path::new("build.kts")));
+        assert!(patterns.iter().any(|p| p.is_match("val x = 1 // @whiteout: 2")));
+
         Ok(())
     }
 }
 
 impl Default for Config {
     fn default() -> Self {
+        let data = ConfigData::default();
+        let compiled_patterns = PatternSet::compile(&data.patterns.patterns)
+            .expect("default [patterns] should always compile");
         Self {
-            data: ConfigData::default(),
+            data,
             path: PathBuf::from(".whiteout/config.toml"),
+            compiled_patterns,
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,211 @@
+//! A small `.gitattributes` parser/serializer, used to scope the
+//! `filter=whiteout` clean/smudge filter to specific globs instead of
+//! blindly applying it to every file in the repo (`* filter=whiteout`).
+//!
+//! Glob matching reuses [`crate::config::patterns::compile_scoped`], so a
+//! pattern follows the same git-glob rules as `.whiteoutignore` and
+//! `.gitignore`: a pattern containing `/` anywhere but the end is anchored
+//! to the attributes-file directory, anything else matches the basename at
+//! any depth. Attribute state follows `git check-attr` semantics:
+//! `name` sets it, `-name` unsets it, `name=value` assigns a value, and
+//! `!name` marks it unspecified.
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::path::Path;
+
+use crate::config::patterns::compile_scoped;
+
+/// The state of a single attribute, as written on one `.gitattributes`
+/// line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum State {
+    Set,
+    Unset,
+    Value(String),
+    Unspecified,
+}
+
+impl State {
+    fn render(&self, name: &str) -> String {
+        match self {
+            State::Set => name.to_string(),
+            State::Unset => format!("-{}", name),
+            State::Value(value) => format!("{}={}", name, value),
+            State::Unspecified => format!("!{}", name),
+        }
+    }
+
+    fn parse(spec: &str) -> (String, State) {
+        if let Some(name) = spec.strip_prefix('-') {
+            (name.to_string(), State::Unset)
+        } else if let Some(name) = spec.strip_prefix('!') {
+            (name.to_string(), State::Unspecified)
+        } else if let Some((name, value)) = spec.split_once('=') {
+            (name.to_string(), State::Value(value.to_string()))
+        } else {
+            (spec.to_string(), State::Set)
+        }
+    }
+}
+
+/// One `.gitattributes` line: a glob and the attribute states it sets.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub pattern: String,
+    pub attributes: Vec<(String, State)>,
+    regex: Regex,
+}
+
+impl Entry {
+    fn new(pattern: String, attributes: Vec<(String, State)>) -> Result<Self> {
+        let (_, regex) = compile_scoped(&pattern, "")
+            .with_context(|| format!("Invalid gitattributes pattern '{}'", pattern))?;
+        Ok(Self { pattern, attributes, regex })
+    }
+}
+
+/// Parses the contents of a `.gitattributes` file into its entries, in the
+/// order they appear. Blank lines and `#` comments are skipped.
+pub fn parse(content: &str) -> Result<Vec<Entry>> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let mut parts = line.split_whitespace();
+            let pattern = parts.next().unwrap_or_default().to_string();
+            let attributes = parts.map(State::parse).collect();
+            Entry::new(pattern, attributes)
+        })
+        .collect()
+}
+
+/// Serializes `entries` back into `.gitattributes` text, one line per
+/// entry, preserving order.
+pub fn format(entries: &[Entry]) -> String {
+    let mut output = String::new();
+    for entry in entries {
+        output.push_str(&entry.pattern);
+        for (name, state) in &entry.attributes {
+            output.push(' ');
+            output.push_str(&state.render(name));
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// Resolves the effective attributes for `path`, matching `entries`
+/// top-to-bottom and letting a later match override an earlier one
+/// attribute-by-attribute (not whole-entry).
+pub fn attributes_for(entries: &[Entry], path: &Path) -> Vec<(String, State)> {
+    let path_str = path.to_string_lossy().replace('\\', "/");
+    let mut resolved: Vec<(String, State)> = Vec::new();
+
+    for entry in entries {
+        if !entry.regex.is_match(&path_str) {
+            continue;
+        }
+        for (name, state) in &entry.attributes {
+            if let Some(existing) = resolved.iter_mut().find(|(n, _)| n == name) {
+                existing.1 = state.clone();
+            } else {
+                resolved.push((name.clone(), state.clone()));
+            }
+        }
+    }
+
+    resolved
+}
+
+/// Reads and parses `.gitattributes` at `project_root`, if present, and
+/// returns the resolved `filter` attribute state for `path` (`None` if no
+/// entry assigns `filter` at all). Used by `transform::clean`/`smudge` to
+/// let a hand-edited `.gitattributes` override the `[patterns]`-derived
+/// decision for files it explicitly scopes.
+pub fn filter_state_for(project_root: &Path, path: &Path) -> Result<Option<State>> {
+    let gitattributes_path = project_root.join(".gitattributes");
+    if !gitattributes_path.is_file() {
+        return Ok(None);
+    }
+
+    let content = std::fs::read_to_string(&gitattributes_path)
+        .with_context(|| format!("Failed to read {}", gitattributes_path.display()))?;
+    let entries = parse(&content)?;
+    let attributes = attributes_for(&entries, path);
+
+    Ok(attributes.into_iter().find(|(name, _)| name == "filter").map(|(_, state)| state))
+}
+
+/// Inserts or merges a `name=value` attribute for `pattern`, idempotently:
+/// if `pattern` already has an entry, the attribute is updated in place
+/// (or left untouched if already set to `value`); otherwise a new entry is
+/// appended. Returns whether the entries were actually changed.
+pub fn upsert_value(entries: &mut Vec<Entry>, pattern: &str, name: &str, value: &str) -> Result<bool> {
+    if let Some(entry) = entries.iter_mut().find(|e| e.pattern == pattern) {
+        if let Some(existing) = entry.attributes.iter_mut().find(|(n, _)| n == name) {
+            if existing.1 == State::Value(value.to_string()) {
+                return Ok(false);
+            }
+            existing.1 = State::Value(value.to_string());
+            return Ok(true);
+        }
+        entry.attributes.push((name.to_string(), State::Value(value.to_string())));
+        return Ok(true);
+    }
+
+    entries.push(Entry::new(
+        pattern.to_string(),
+        vec![(name.to_string(), State::Value(value.to_string()))],
+    )?);
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_set_unset_value_and_unspecified() -> Result<()> {
+        let entries = parse("*.rs filter=whiteout -text !diff\n# comment\n\n*.bin binary")?;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].attributes, vec![
+            ("filter".to_string(), State::Value("whiteout".to_string())),
+            ("text".to_string(), State::Unset),
+            ("diff".to_string(), State::Unspecified),
+        ]);
+        assert_eq!(entries[1].attributes, vec![("binary".to_string(), State::Set)]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_attributes_for_matches_basename_at_any_depth() -> Result<()> {
+        let entries = parse("*.rs filter=whiteout")?;
+        let attrs = attributes_for(&entries, Path::new("src/lib.rs"));
+        assert_eq!(attrs, vec![("filter".to_string(), State::Value("whiteout".to_string()))]);
+        assert!(attributes_for(&entries, Path::new("src/lib.py")).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_later_entry_overrides_attribute_but_not_unrelated_ones() -> Result<()> {
+        let entries = parse("*.rs filter=whiteout text\n*.rs -filter")?;
+        let attrs = attributes_for(&entries, Path::new("main.rs"));
+        assert_eq!(attrs, vec![
+            ("filter".to_string(), State::Unset),
+            ("text".to_string(), State::Set),
+        ]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_upsert_value_is_idempotent() -> Result<()> {
+        let mut entries = parse("*.rs filter=whiteout")?;
+        assert!(!upsert_value(&mut entries, "*.rs", "filter", "whiteout")?);
+        assert!(upsert_value(&mut entries, "*.rs", "filter", "other")?);
+        assert!(upsert_value(&mut entries, "config/**", "filter", "whiteout")?);
+        assert_eq!(entries.len(), 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+//! A git-style ignore engine for `whiteout scan`.
+//!
+//! Walks the project tree top-down, loading `.gitignore` and the root
+//! `.git/info/exclude` as it descends, and decides whether a path is
+//! ignored using the same precedence git itself uses: patterns are
+//! gathered in root-to-leaf order and applied with last-match-wins, so a
+//! pattern in a deeper directory's `.gitignore` can override one from a
+//! shallower directory (or an earlier line in the same file).
+//!
+//! `.whiteoutignore` is handled separately by the [`crate::matcher`]
+//! module, which supports a richer pattern syntax (`glob:`/`re:`/`path:`/
+//! `rootfilesin:` prefixes); combine both when deciding whether to process
+//! a path.
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::config::patterns::compile_scoped;
+
+const IGNORE_FILE_NAMES: &[&str] = &[".gitignore"];
+
+/// One ignore file's patterns, already anchored to the directory it was
+/// found in.
+struct IgnoreLayer {
+    entries: Vec<(bool, Regex)>,
+}
+
+impl IgnoreLayer {
+    fn load(path: &Path, base: &str) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read ignore file: {}", path.display()))?;
+
+        let entries = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| compile_scoped(line, base))
+            .collect::<Result<Vec<_>>>()
+            .with_context(|| format!("Failed to parse ignore file: {}", path.display()))?;
+
+        Ok(Self { entries })
+    }
+}
+
+/// The combined set of ignore rules in effect for a whole tree.
+pub struct IgnoreSet {
+    layers: Vec<IgnoreLayer>,
+}
+
+impl IgnoreSet {
+    /// Loads every `.gitignore` under `root`, plus the root's
+    /// `.git/info/exclude`, in root-to-leaf order.
+    pub fn load(root: &Path) -> Result<Self> {
+        let mut layers = Vec::new();
+
+        let exclude_path = root.join(".git").join("info").join("exclude");
+        if exclude_path.is_file() {
+            layers.push(IgnoreLayer::load(&exclude_path, "")?);
+        }
+
+        let mut dirs: Vec<PathBuf> = WalkDir::new(root)
+            .into_iter()
+            .filter_entry(|e| e.file_name() != ".git")
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_dir())
+            .map(|e| e.path().to_path_buf())
+            .collect();
+        dirs.sort_by_key(|dir| dir.components().count());
+
+        for dir in dirs {
+            let relative = dir.strip_prefix(root).unwrap_or(&dir);
+            let base = if relative.as_os_str().is_empty() {
+                String::new()
+            } else {
+                format!("{}/", relative.to_string_lossy().replace('\\', "/"))
+            };
+
+            for name in IGNORE_FILE_NAMES {
+                let ignore_path = dir.join(name);
+                if ignore_path.is_file() {
+                    layers.push(IgnoreLayer::load(&ignore_path, &base)?);
+                }
+            }
+        }
+
+        Ok(Self { layers })
+    }
+
+    /// Whether `path` (repo-root-relative) is ignored, applying
+    /// last-match-wins across every layer in root-to-leaf order.
+    pub fn is_ignored(&self, path: &str) -> bool {
+        let normalized = path.replace('\\', "/");
+        let mut ignored = false;
+        for layer in &self.layers {
+            for (negated, regex) in &layer.entries {
+                if regex.is_match(&normalized) {
+                    ignored = !negated;
+                }
+            }
+        }
+        ignored
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_root_gitignore_is_honored() -> Result<()> {
+        let dir = TempDir::new()?;
+        fs::write(dir.path().join(".gitignore"), "*.log\n")?;
+
+        let ignore = IgnoreSet::load(dir.path())?;
+        assert!(ignore.is_ignored("debug.log"));
+        assert!(!ignore.is_ignored("main.rs"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_deeper_gitignore_overrides_shallower_one() -> Result<()> {
+        let dir = TempDir::new()?;
+        fs::write(dir.path().join(".gitignore"), "*.log\n")?;
+        fs::create_dir(dir.path().join("keep"))?;
+        fs::write(dir.path().join("keep").join(".gitignore"), "!*.log\n")?;
+
+        let ignore = IgnoreSet::load(dir.path())?;
+        assert!(ignore.is_ignored("other.log"));
+        assert!(!ignore.is_ignored("keep/debug.log"));
+
+        Ok(())
+    }
+}
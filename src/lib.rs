@@ -1,39 +1,80 @@
+pub mod agent;
+pub mod c_api;
 pub mod config;
+pub mod error;
+pub mod gitattributes;
+pub mod ignore;
+pub mod matcher;
 pub mod parser;
+pub mod path;
 pub mod storage;
 pub mod transform;
+pub mod validation;
 
 use anyhow::Result;
 use std::path::Path;
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct Whiteout {
     config: config::Config,
-    storage: storage::LocalStorage,
+    storage: Box<dyn storage::Storage>,
+    registry: transform::registry::Registry,
 }
 
 impl Whiteout {
     pub fn new(project_root: impl AsRef<Path>) -> Result<Self> {
+        Self::open(project_root, None)
+    }
+
+    /// Like [`Self::new`], but encrypts `LocalStorage` with `crypto`
+    /// instead of whatever it would derive on its own -- for
+    /// [`agent::AgentServer`], which already cached a cipher and needs
+    /// `clean`/`smudge` to reuse it instead of re-deriving one per request.
+    pub fn with_crypto(project_root: impl AsRef<Path>, crypto: storage::crypto::Crypto) -> Result<Self> {
+        Self::open(project_root, Some(crypto))
+    }
+
+    /// Shared constructor body for [`Self::new`] and [`Self::with_crypto`]:
+    /// `crypto` is `None` to let [`storage::open_backend`] derive a cipher
+    /// on its own, or `Some` to reuse one the caller already has via
+    /// [`storage::open_backend_with_crypto`].
+    fn open(project_root: impl AsRef<Path>, crypto: Option<storage::crypto::Crypto>) -> Result<Self> {
         let project_root = project_root.as_ref();
         let config = config::Config::load_or_default(project_root)?;
-        let storage = storage::LocalStorage::new(project_root)?;
-        
-        Ok(Self { config, storage })
+        let storage = match crypto {
+            Some(crypto) => storage::open_backend_with_crypto(&config, project_root, crypto)?,
+            None => storage::open_backend(&config, project_root)?,
+        };
+        let registry =
+            transform::registry::Registry::new(Some(&config.data.transform.enabled_providers));
+
+        Ok(Self { config, storage, registry })
     }
 
     pub fn init(project_root: impl AsRef<Path>) -> Result<Self> {
+        Self::init_with_patterns(project_root, &[])
+    }
+
+    /// Like [`Whiteout::init`], but seeds `[patterns]` (and the
+    /// `.gitattributes` block derived from it) with `patterns` instead of
+    /// the default `"*"`, when the project doesn't already have a config.
+    pub fn init_with_patterns(project_root: impl AsRef<Path>, patterns: &[String]) -> Result<Self> {
         let project_root = project_root.as_ref();
-        config::Config::init(project_root)?;
+        config::Config::init_with_patterns(project_root, patterns)?;
         storage::LocalStorage::init(project_root)?;
-        
+
         Self::new(project_root)
     }
 
     pub fn clean(&self, content: &str, file_path: &Path) -> Result<String> {
-        transform::clean(content, file_path, &self.storage, &self.config)
+        transform::clean(content, file_path, &self.storage, &self.config, &self.registry)
     }
 
     pub fn smudge(&self, content: &str, file_path: &Path) -> Result<String> {
-        transform::smudge(content, file_path, &self.storage, &self.config)
+        transform::smudge(content, file_path, &self.storage, &self.config, &self.registry)
+    }
+
+    pub fn config(&self) -> &config::Config {
+        &self.config
     }
 }
\ No newline at end of file
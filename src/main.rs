@@ -26,15 +26,22 @@ fn run_command(cli: cli::Cli) -> Result<()> {
     use cli::Commands;
     
     match cli.command {
-        Commands::Init { path } => commands::init::handle(&path),
+        Commands::Init { path, patterns } => commands::init::handle(&path, patterns),
         Commands::Clean { file } => commands::clean::handle(file),
+        Commands::CleanAll { path } => commands::clean_all::handle(&path),
         Commands::Smudge { file } => commands::smudge::handle(file),
         Commands::Preview { file, diff } => commands::preview::handle(&file, diff),
         Commands::Check { files, fix } => commands::check::handle(files, fix),
         Commands::Mark { file, line, replace } => commands::mark::handle(&file, line, replace),
         Commands::Unmark { file, line } => commands::unmark::handle(&file, line),
-        Commands::Status { verbose } => commands::status::handle(verbose),
+        Commands::Status { verbose, format } => commands::status::handle(verbose, format),
         Commands::Config { action } => commands::config::handle(action),
-        Commands::Sync { branch } => commands::sync::handle(branch),
+        Commands::Sync { action } => commands::sync::handle(action),
+        Commands::Agent { foreground, lock } => commands::agent::handle(foreground, lock),
+        Commands::Recipient { action } => commands::recipient::handle(action),
+        Commands::Scan { path } => commands::scan::handle(&path),
+        Commands::ScanHistory { path } => commands::scan_history::handle(&path),
+        Commands::Audit { file } => commands::audit::handle(&file),
+        Commands::Watch { path } => commands::watch::handle(&path),
     }
 }
\ No newline at end of file
@@ -0,0 +1,246 @@
+//! A small path-matching subsystem backing `.whiteoutignore`, used by every
+//! directory walk (`status`, `scan`, `watch`) instead of a hard-coded skip
+//! list.
+//!
+//! `.whiteoutignore` lines are gitignore-like exclude patterns, each
+//! optionally tagged with a prefix that selects how it's interpreted:
+//!
+//! - `glob:pattern` (the default when no prefix is given) — a gitignore-
+//!   style glob: `*` matches a run of characters except `/`, `**` spans
+//!   directories, `?` matches one character, and `[...]` is a character
+//!   class. A pattern containing `/` (other than a trailing one) is
+//!   anchored to the repo root; otherwise it matches at any depth.
+//! - `re:pattern` — a raw regex, matched against the whole repo-root-
+//!   relative path.
+//! - `path:prefix` — a literal path prefix, anchored at the repo root.
+//! - `rootfilesin:dir` — files directly inside `dir`, but not its
+//!   subdirectories.
+//!
+//! A line starting with `!` is an exception: a path matching it is never
+//! excluded, even if an earlier pattern would otherwise exclude it.
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::fs;
+use std::path::Path;
+
+/// Answers whether a path should be processed. Implementations are pure
+/// predicates over repo-root-relative paths; combinators below build more
+/// elaborate answers out of simpler ones.
+pub trait Matcher: Send + Sync {
+    fn matches(&self, path: &Path) -> bool;
+}
+
+/// Matches every path. The default when no `.whiteoutignore` is present.
+pub struct AlwaysMatcher;
+
+impl Matcher for AlwaysMatcher {
+    fn matches(&self, _path: &Path) -> bool {
+        true
+    }
+}
+
+/// Matches a path if it's selected by any of a set of compiled patterns.
+pub struct IncludeMatcher {
+    patterns: Vec<Regex>,
+}
+
+impl IncludeMatcher {
+    pub fn new(patterns: Vec<Regex>) -> Self {
+        Self { patterns }
+    }
+}
+
+impl Matcher for IncludeMatcher {
+    fn matches(&self, path: &Path) -> bool {
+        let path_str = path.to_string_lossy().replace('\\', "/");
+        self.patterns.iter().any(|pattern| pattern.is_match(&path_str))
+    }
+}
+
+/// Matches a path that the `include` matcher selects and the `exclude`
+/// matcher does not.
+pub struct DifferenceMatcher<A: Matcher, B: Matcher> {
+    include: A,
+    exclude: B,
+}
+
+impl<A: Matcher, B: Matcher> DifferenceMatcher<A, B> {
+    pub fn new(include: A, exclude: B) -> Self {
+        Self { include, exclude }
+    }
+}
+
+impl<A: Matcher, B: Matcher> Matcher for DifferenceMatcher<A, B> {
+    fn matches(&self, path: &Path) -> bool {
+        self.include.matches(path) && !self.exclude.matches(path)
+    }
+}
+
+/// Loads `.whiteoutignore` from `root` and builds the combined matcher for
+/// it, or an [`AlwaysMatcher`] if the file doesn't exist.
+pub fn load(root: &Path) -> Result<Box<dyn Matcher>> {
+    let ignore_path = root.join(".whiteoutignore");
+    if !ignore_path.is_file() {
+        return Ok(Box::new(AlwaysMatcher));
+    }
+
+    let content = fs::read_to_string(&ignore_path)
+        .with_context(|| format!("Failed to read {}", ignore_path.display()))?;
+
+    let mut excludes = Vec::new();
+    let mut exceptions = Vec::new();
+
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix('!') {
+            exceptions.push(compile_pattern(rest)?);
+        } else {
+            excludes.push(compile_pattern(line)?);
+        }
+    }
+
+    // A path is excluded iff an exclude pattern matches it and no exception
+    // does; the overall matcher keeps every path except that excluded set.
+    let excluded = DifferenceMatcher::new(IncludeMatcher::new(excludes), IncludeMatcher::new(exceptions));
+    Ok(Box::new(DifferenceMatcher::new(AlwaysMatcher, excluded)))
+}
+
+fn compile_pattern(raw: &str) -> Result<Regex> {
+    let regex_str = if let Some(rest) = raw.strip_prefix("glob:") {
+        glob_to_regex(rest)
+    } else if let Some(rest) = raw.strip_prefix("re:") {
+        format!("^(?:{})$", rest)
+    } else if let Some(rest) = raw.strip_prefix("path:") {
+        format!("^{}(?:/.*)?$", regex::escape(rest.trim_matches('/')))
+    } else if let Some(rest) = raw.strip_prefix("rootfilesin:") {
+        let dir = rest.trim_matches('/');
+        if dir.is_empty() {
+            "^[^/]*$".to_string()
+        } else {
+            format!("^{}/[^/]*$", regex::escape(dir))
+        }
+    } else {
+        glob_to_regex(raw)
+    };
+
+    Regex::new(&regex_str).with_context(|| format!("Invalid .whiteoutignore pattern '{}'", raw))
+}
+
+/// Translates a gitignore-style glob into an anchored regex. A pattern
+/// containing `/` (other than a trailing one) is anchored to the repo
+/// root; otherwise it matches at any depth.
+fn glob_to_regex(pattern: &str) -> String {
+    let trimmed = pattern.trim_end_matches('/');
+    let anchored = trimmed.contains('/');
+
+    let mut regex_str = String::from("^");
+    if !anchored {
+        regex_str.push_str("(?:.*/)?");
+    }
+
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => {
+                if chars.peek() == Some(&'*') {
+                    chars.next();
+                    if chars.peek() == Some(&'/') {
+                        chars.next();
+                        regex_str.push_str("(?:.*/)?");
+                    } else {
+                        regex_str.push_str(".*");
+                    }
+                } else {
+                    regex_str.push_str("[^/]*");
+                }
+            }
+            '?' => regex_str.push_str("[^/]"),
+            '[' => {
+                regex_str.push('[');
+                if chars.peek() == Some(&'!') {
+                    chars.next();
+                    regex_str.push('^');
+                }
+                for c2 in chars.by_ref() {
+                    if c2 == ']' {
+                        break;
+                    }
+                    regex_str.push(c2);
+                }
+                regex_str.push(']');
+            }
+            '.' | '+' | '(' | ')' | '|' | '^' | '$' | '{' | '}' | '\\' => {
+                regex_str.push('\\');
+                regex_str.push(c);
+            }
+            _ => regex_str.push(c),
+        }
+    }
+    regex_str.push('$');
+    regex_str
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    fn load_str(content: &str) -> Box<dyn Matcher> {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join(".whiteoutignore"), content).unwrap();
+        load(dir.path()).unwrap()
+    }
+
+    #[test]
+    fn test_no_file_always_matches() {
+        let dir = TempDir::new().unwrap();
+        let matcher = load(dir.path()).unwrap();
+        assert!(matcher.matches(&PathBuf::from("anything.rs")));
+    }
+
+    #[test]
+    fn test_default_prefix_is_glob() {
+        let matcher = load_str("*.log\n");
+        assert!(!matcher.matches(&PathBuf::from("debug.log")));
+        assert!(matcher.matches(&PathBuf::from("main.rs")));
+    }
+
+    #[test]
+    fn test_exception_overrides_exclude() {
+        let matcher = load_str("*.log\n!keep.log\n");
+        assert!(!matcher.matches(&PathBuf::from("debug.log")));
+        assert!(matcher.matches(&PathBuf::from("keep.log")));
+    }
+
+    #[test]
+    fn test_path_prefix_is_anchored_at_root() {
+        let matcher = load_str("path:vendor\n");
+        assert!(!matcher.matches(&PathBuf::from("vendor/crate/lib.rs")));
+        assert!(matcher.matches(&PathBuf::from("src/vendor/lib.rs")));
+    }
+
+    #[test]
+    fn test_rootfilesin_is_not_recursive() {
+        let matcher = load_str("rootfilesin:src\n");
+        assert!(!matcher.matches(&PathBuf::from("src/main.rs")));
+        assert!(matcher.matches(&PathBuf::from("src/nested/main.rs")));
+    }
+
+    #[test]
+    fn test_re_prefix_uses_raw_regex() {
+        let matcher = load_str("re:^src/.*\\.rs$\n");
+        assert!(!matcher.matches(&PathBuf::from("src/main.rs")));
+        assert!(matcher.matches(&PathBuf::from("src/main.py")));
+    }
+
+    #[test]
+    fn test_character_class() {
+        let matcher = load_str("file[0-9].txt\n");
+        assert!(!matcher.matches(&PathBuf::from("file1.txt")));
+        assert!(matcher.matches(&PathBuf::from("filea.txt")));
+    }
+}
@@ -1,173 +1,200 @@
+use std::path::Path;
+
+use super::handler::{drive, RewriteHandler};
 use super::types::Decoration;
 
-/// Apply decorations to content
+/// Apply decorations to content. `file_path`'s extension selects the
+/// comment token used to (re-)emit inline `@whiteout:` markers.
+/// `active_profile` selects which alternative a multi-environment `Partial`
+/// replacement smudges in as (see [`crate::parser::types::PartialReplacement`]);
+/// it has no effect on clean, which always preserves every alternative, or
+/// on a legacy `[[local||committed]]` pair. This is a thin wrapper over
+/// [`RewriteHandler`]; see `parser::handler` for the line-walking driver and
+/// `parser::audit::AuditHandler` for the read-only alternative that reports
+/// instead of rewriting.
 pub fn apply_decorations(
     content: &str,
     decorations: &[Decoration],
     use_local: bool,
+    file_path: Option<&Path>,
+    active_profile: Option<&str>,
 ) -> String {
     if decorations.is_empty() {
         return content.to_string();
     }
 
-    let lines: Vec<&str> = content.lines().collect();
-    let mut result = Vec::new();
-    let mut skip_until = 0;
+    let mut handler = RewriteHandler::new(use_local, file_path, active_profile);
+    drive(content, decorations, &mut handler);
+    handler.into_output()
+}
 
-    for (idx, line) in lines.iter().enumerate() {
-        let line_num = idx + 1;
-        
-        if line_num <= skip_until {
-            continue;
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Decoration;
 
-        let mut line_processed = false;
-        
-        // Check for block decorations
-        for decoration in decorations {
-            if let Decoration::Block { start_line, end_line, local_content, committed_content } = decoration {
-                if line_num == *start_line {
-                    if use_local {
-                        // Smudge: Check if this is a simple @whiteout or block with markers
-                        let is_simple_pattern = line.contains("@whiteout") && 
-                                              !line.contains("@whiteout-start") && 
-                                              !line.contains("@whiteout:");
-                        
-                        if is_simple_pattern {
-                            // Simple @whiteout: Keep marker and show local content
-                            result.push(line.to_string()); // Keep @whiteout marker
-                            for content_line in local_content.lines() {
-                                result.push(content_line.to_string());
-                            }
-                            // Skip to end of the block
-                            skip_until = *end_line;
-                        } else {
-                            // Block with markers: Keep markers and show local content
-                            result.push(line.to_string()); // Keep @whiteout-start
-                            for content_line in local_content.lines() {
-                                result.push(content_line.to_string());
-                            }
-                            // Find and add the end marker
-                            if *end_line <= lines.len() {
-                                result.push(lines[*end_line - 1].to_string()); // Keep @whiteout-end
-                            }
-                            // Skip the committed content that follows
-                            skip_until = *end_line;
-                            
-                            // Count lines of committed content to skip
-                            let committed_lines = committed_content.lines().count();
-                            if committed_lines > 0 {
-                                skip_until += committed_lines;
-                            }
-                        }
-                    } else {
-                        // Clean: Check if this is a simple @whiteout or block with markers
-                        let is_simple_pattern = line.contains("@whiteout") && 
-                                              !line.contains("@whiteout-start") && 
-                                              !line.contains("@whiteout:");
-                        
-                        if is_simple_pattern {
-                            // Simple @whiteout: Keep the marker, skip the local content
-                            result.push(line.to_string()); // Keep @whiteout marker
-                            // Skip all the local content lines
-                            skip_until = *end_line;
-                        } else {
-                            // Block with @whiteout-start/end: Keep markers with empty content
-                            result.push(line.to_string()); // Keep @whiteout-start
-                            // No local content in between (it's been cleaned)
-                            
-                            // Add the end marker
-                            if *end_line <= lines.len() {
-                                result.push(lines[*end_line - 1].to_string()); // Keep @whiteout-end
-                            }
-                            
-                            // Add the committed content that follows the block
-                            if !committed_content.is_empty() {
-                                for content_line in committed_content.lines() {
-                                    result.push(content_line.to_string());
-                                }
-                            }
-                            
-                            // Skip to end of original block plus any following committed content
-                            skip_until = *end_line + committed_content.lines().count();
-                        }
-                    }
-                    line_processed = true;
-                    break;
-                }
-            }
-        }
-        
-        if line_processed {
-            continue;
-        }
-        
-        // Check for inline decorations
-        let mut found_inline = false;
-        for decoration in decorations {
-            if let Decoration::Inline { line: dec_line, local_value, committed_value } = decoration {
-                if line_num == *dec_line {
-                    if use_local {
-                        // Smudge: Show local value with decoration
-                        result.push(format!("{} // @whiteout: {}", local_value, committed_value));
-                    } else {
-                        // Clean: Show committed value WITH decoration marker for smudge to work
-                        result.push(format!("{} // @whiteout: {}", committed_value, committed_value));
-                    }
-                    found_inline = true;
-                    line_processed = true;
-                    break;
-                }
-            }
-        }
-        
-        if found_inline {
-            continue;
-        }
-        
-        // Check for partial replacements
-        for decoration in decorations {
-            if let Decoration::Partial { line: dec_line, replacements } = decoration {
-                if line_num == *dec_line {
-                    let mut processed_line = line.to_string();
-                    
-                    for replacement in replacements.iter().rev() {
-                        let new_value = if use_local {
-                            // Smudge: Use local value in the pattern
-                            format!("[[{}||{}]]", 
-                                replacement.local_value, 
-                                replacement.committed_value)
-                        } else {
-                            // Clean: Preserve pattern structure with committed value for smudge to work
-                            format!("[[{}||{}]]", 
-                                replacement.committed_value.clone(),
-                                replacement.committed_value)
-                        };
-                        
-                        if replacement.start < processed_line.len() {
-                            processed_line.replace_range(
-                                replacement.start..replacement.end.min(processed_line.len()),
-                                &new_value
-                            );
-                        }
-                    }
-                    
-                    result.push(processed_line);
-                    line_processed = true;
-                    break;
-                }
+    #[test]
+    fn test_apply_decorations_preserves_crlf_line_endings() {
+        let content = "let api_key = \"ENV_VAR\"; // @whiteout: ENV_VAR\r\nconst x = 1;\r\n";
+        let decorations = vec![Decoration::Inline {
+            line: 1,
+            occurrence: 0,
+            local_value: "let api_key = \"sk-12345\";".to_string(),
+            committed_value: "ENV_VAR".to_string(),
+        }];
+
+        let smudged = apply_decorations(content, &decorations, true, Some(Path::new("test.rs")), None);
+        assert!(smudged.contains("sk-12345"));
+        assert!(smudged.contains("\r\n"));
+        // Untouched lines keep their original terminator, not a normalized `\n`.
+        assert!(smudged.lines().collect::<Vec<_>>().len() == 2);
+        assert_eq!(smudged.matches("\r\n").count(), 2);
+    }
+
+    #[test]
+    fn test_apply_decorations_preserves_missing_trailing_newline() {
+        let content = "let api_key = \"ENV_VAR\"; // @whiteout: ENV_VAR";
+        let decorations = vec![Decoration::Inline {
+            line: 1,
+            occurrence: 0,
+            local_value: "let api_key = \"sk-12345\";".to_string(),
+            committed_value: "ENV_VAR".to_string(),
+        }];
+
+        let smudged = apply_decorations(content, &decorations, true, Some(Path::new("test.rs")), None);
+        assert!(!smudged.ends_with('\n'));
+    }
+
+    #[test]
+    fn test_apply_decorations_renders_multiple_inline_per_line_in_order() {
+        let content = r#"{"a": "ENV_A"} // @whiteout: "ENV_A" {"b": "ENV_B"} // @whiteout: "ENV_B""#;
+        let decorations = vec![
+            Decoration::Inline {
+                line: 1,
+                occurrence: 1,
+                local_value: r#"{"b": "sk-222"}"#.to_string(),
+                committed_value: r#""ENV_B""#.to_string(),
+            },
+            Decoration::Inline {
+                line: 1,
+                occurrence: 0,
+                local_value: r#"{"a": "sk-111"}"#.to_string(),
+                committed_value: r#""ENV_A""#.to_string(),
+            },
+        ];
+
+        let smudged = apply_decorations(content, &decorations, true, Some(Path::new("test.rs")), None);
+        assert!(smudged.contains("sk-111"));
+        assert!(smudged.contains("sk-222"));
+        // The earlier occurrence's local value precedes the later one's,
+        // regardless of the order the decorations were pushed in.
+        assert!(smudged.find("sk-111").unwrap() < smudged.find("sk-222").unwrap());
+    }
+
+    /// A `Decoration::Partial` for `[[dev=localhost||prod=api.example.com]]`
+    /// at the position it actually occupies in `partial_profile_content()`.
+    fn partial_profile_content() -> (&'static str, Vec<Decoration>) {
+        use crate::parser::types::PartialAlternative;
+
+        let content = r#"host = [[dev=localhost||prod=api.example.com]] // @whiteout-partial"#;
+        let decorations = vec![Decoration::Partial {
+            line: 1,
+            replacements: vec![crate::parser::types::PartialReplacement {
+                start: 7,
+                end: 46,
+                alternatives: vec![
+                    PartialAlternative { name: Some("dev".to_string()), value: "localhost".to_string() },
+                    PartialAlternative { name: Some("prod".to_string()), value: "api.example.com".to_string() },
+                ],
+            }],
+        }];
+
+        (content, decorations)
+    }
+
+    #[test]
+    fn test_apply_decorations_clean_preserves_full_partial_profile_structure() {
+        let (content, decorations) = partial_profile_content();
+
+        let cleaned = apply_decorations(content, &decorations, false, Some(Path::new("test.rs")), None);
+        assert!(cleaned.contains("dev=localhost"));
+        assert!(cleaned.contains("prod=api.example.com"));
+    }
+
+    #[test]
+    fn test_apply_decorations_smudge_selects_named_partial_profile() {
+        let (content, decorations) = partial_profile_content();
+
+        let smudged = apply_decorations(content, &decorations, true, Some(Path::new("test.rs")), Some("prod"));
+        assert!(smudged.contains("api.example.com"));
+        assert!(!smudged.contains("localhost"));
+
+        let smudged_default = apply_decorations(content, &decorations, true, Some(Path::new("test.rs")), None);
+        assert!(smudged_default.contains("localhost"));
+    }
+
+    /// Builds `lines` lines of content with a decoration on every 10th line,
+    /// so `decorations.len()` scales with `lines` the same way a real file
+    /// with scattered secrets would.
+    fn generate_decorated_content(lines: usize) -> (String, Vec<Decoration>) {
+        let mut content = String::new();
+        let mut decorations = Vec::new();
+
+        for i in 1..=lines {
+            if i % 10 == 0 {
+                content.push_str(&format!("value_{} // @whiteout: REDACTED_{}\n", i, i));
+                decorations.push(Decoration::Inline {
+                    line: i,
+                    occurrence: 0,
+                    local_value: format!("value_{}", i),
+                    committed_value: format!("REDACTED_{}", i),
+                });
+            } else {
+                content.push_str(&format!("normal line {}\n", i));
             }
         }
-        
-        if !line_processed {
-            result.push(line.to_string());
-        }
+
+        (content, decorations)
     }
-    
-    let mut output = result.join("\n");
-    // Preserve trailing newline if original had one
-    if content.ends_with('\n') && !output.ends_with('\n') {
-        output.push('\n');
+
+    /// Times the minimum of a few repetitions of `apply_decorations` at
+    /// `lines` lines, to reduce warmup/scheduling noise.
+    fn min_time_for(lines: usize) -> std::time::Duration {
+        let (content, decorations) = generate_decorated_content(lines);
+        (0..5)
+            .map(|_| {
+                let start = std::time::Instant::now();
+                apply_decorations(&content, &decorations, false, Some(Path::new("test.rs")), None);
+                start.elapsed()
+            })
+            .min()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_apply_decorations_is_linear_not_quadratic() {
+        // Each size doubles both the number of lines and the number of
+        // decorations, the same shape a file with scattered secrets grows
+        // in. If `apply_decorations` were still O(lines * decorations),
+        // the normalized (time / size) ratio would roughly double at each
+        // step instead of staying flat.
+        let sizes = [1_000usize, 2_000, 4_000, 8_000];
+        let ratios: Vec<f64> = sizes
+            .iter()
+            .map(|&size| min_time_for(size).as_secs_f64() / size as f64)
+            .collect();
+
+        let baseline = ratios[0].max(f64::EPSILON);
+        for (size, ratio) in sizes.iter().zip(ratios.iter()).skip(1) {
+            assert!(
+                ratio / baseline < 2.0,
+                "apply_decorations looks superlinear: size {} has a normalized cost of {:.3e}, \
+                 more than 2x the baseline {:.3e} at size {}",
+                size,
+                ratio,
+                baseline,
+                sizes[0],
+            );
+        }
     }
-    output
-}
\ No newline at end of file
+}
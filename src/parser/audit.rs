@@ -0,0 +1,207 @@
+//! A read-only [`DecorationHandler`] that reports what `clean` would strip
+//! from a file, instead of rewriting it -- the library side of `whiteout
+//! audit`.
+
+use std::path::Path;
+use serde::Serialize;
+
+use super::handler::{drive, BlockContext, DecorationHandler, InlineContext, PartialContext, PassthroughContext};
+use super::types::Decoration;
+
+/// Which decoration form a [`AuditFinding`] came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditKind {
+    Block,
+    Inline,
+    Partial,
+}
+
+/// One decoration's effect on the committed version of a file: the line(s)
+/// it covers, the local ("before") and committed ("after") text, and
+/// whether the committed text still looks like it leaks a secret (the same
+/// heuristic `check`/`scan` use).
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditFinding {
+    pub kind: AuditKind,
+    pub line: usize,
+    /// Only set for [`AuditKind::Block`].
+    pub end_line: Option<usize>,
+    pub before: String,
+    pub after: String,
+    pub leaks_secret: bool,
+    pub secret_kind: Option<&'static str>,
+}
+
+/// The result of auditing a file: one [`AuditFinding`] per decoration,
+/// in the order they appear.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AuditReport {
+    pub findings: Vec<AuditFinding>,
+}
+
+/// Returns the name of the first [`crate::validation::SECRET_PATTERNS`]
+/// entry that matches anywhere in `text`, if any.
+fn detect_secret(text: &str) -> Option<&'static str> {
+    crate::validation::find_secrets(text)
+        .ok()
+        .and_then(|findings| findings.into_iter().next().map(|finding| finding.name))
+}
+
+pub struct AuditHandler {
+    report: AuditReport,
+}
+
+impl AuditHandler {
+    pub fn new() -> Self {
+        Self { report: AuditReport::default() }
+    }
+
+    pub fn into_report(self) -> AuditReport {
+        self.report
+    }
+}
+
+impl Default for AuditHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DecorationHandler for AuditHandler {
+    fn on_block(&mut self, ctx: BlockContext<'_>) -> usize {
+        let is_simple_pattern = ctx.start_line.contains("@whiteout")
+            && !ctx.start_line.contains("@whiteout-start")
+            && !ctx.start_line.contains("@whiteout:");
+
+        let mut skip_until = ctx.end_line;
+        if !is_simple_pattern {
+            skip_until += ctx.committed_content.lines().count();
+        }
+
+        let secret_kind = detect_secret(ctx.committed_content);
+        self.report.findings.push(AuditFinding {
+            kind: AuditKind::Block,
+            line: ctx.line_num,
+            end_line: Some(ctx.end_line),
+            before: ctx.local_content.to_string(),
+            after: ctx.committed_content.to_string(),
+            leaks_secret: secret_kind.is_some(),
+            secret_kind,
+        });
+
+        skip_until
+    }
+
+    fn on_inline(&mut self, ctx: InlineContext<'_>) -> usize {
+        for (local_value, committed_value) in &ctx.values {
+            let secret_kind = detect_secret(committed_value);
+            self.report.findings.push(AuditFinding {
+                kind: AuditKind::Inline,
+                line: ctx.line_num,
+                end_line: None,
+                before: local_value.to_string(),
+                after: committed_value.to_string(),
+                leaks_secret: secret_kind.is_some(),
+                secret_kind,
+            });
+        }
+        ctx.line_num
+    }
+
+    fn on_partial(&mut self, ctx: PartialContext<'_>) -> usize {
+        for replacement in ctx.replacements {
+            if replacement.is_legacy_pair() {
+                let secret_kind = detect_secret(replacement.committed_value());
+                self.report.findings.push(AuditFinding {
+                    kind: AuditKind::Partial,
+                    line: ctx.line_num,
+                    end_line: None,
+                    before: replacement.local_value().to_string(),
+                    after: replacement.committed_value().to_string(),
+                    leaks_secret: secret_kind.is_some(),
+                    secret_kind,
+                });
+            } else {
+                // Multi-environment profiles: clean keeps every
+                // alternative, so there's nothing hidden to compare --
+                // "before" and "after" are the same rendered structure,
+                // and a leak is any alternative that looks like a secret.
+                let rendered = replacement.render();
+                let secret_kind = replacement.alternatives.iter().find_map(|alt| detect_secret(&alt.value));
+                self.report.findings.push(AuditFinding {
+                    kind: AuditKind::Partial,
+                    line: ctx.line_num,
+                    end_line: None,
+                    before: rendered.clone(),
+                    after: rendered,
+                    leaks_secret: secret_kind.is_some(),
+                    secret_kind,
+                });
+            }
+        }
+        ctx.line_num
+    }
+
+    fn on_passthrough(&mut self, ctx: PassthroughContext<'_>) -> usize {
+        ctx.line_num
+    }
+}
+
+/// Audits `content` against `decorations`, reporting what `clean` would
+/// strip for each one instead of rewriting anything. `file_path` is
+/// accepted for symmetry with [`super::apply::apply_decorations`], though
+/// today's findings don't depend on comment syntax.
+pub fn audit_decorations(content: &str, decorations: &[Decoration], _file_path: Option<&Path>) -> AuditReport {
+    if decorations.is_empty() {
+        return AuditReport::default();
+    }
+
+    let mut handler = AuditHandler::new();
+    drive(content, decorations, &mut handler);
+    handler.into_report()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_audit_flags_inline_secret_left_in_committed_value() {
+        let content = r#"let key = "sk-oopsoopsoopsoopsoopsoopsoopsoops"; // @whiteout: "sk-oopsoopsoopsoopsoopsoopsoopsoops""#;
+        let decorations = vec![Decoration::Inline {
+            line: 1,
+            occurrence: 0,
+            local_value: r#"let key = "sk-localsecretvaluevaluevaluevalue12";"#.to_string(),
+            committed_value: r#""sk-oopsoopsoopsoopsoopsoopsoopsoops""#.to_string(),
+        }];
+
+        let report = audit_decorations(content, &decorations, Some(Path::new("test.rs")));
+        assert_eq!(report.findings.len(), 1);
+        assert_eq!(report.findings[0].kind, AuditKind::Inline);
+        assert!(report.findings[0].leaks_secret);
+        assert_eq!(report.findings[0].secret_kind, Some("OpenAI API Key"));
+    }
+
+    #[test]
+    fn test_audit_does_not_flag_a_clean_replacement() {
+        let content = "let api_key = \"REDACTED\"; // @whiteout: \"REDACTED\"";
+        let decorations = vec![Decoration::Inline {
+            line: 1,
+            occurrence: 0,
+            local_value: "let api_key = \"sk-realsecretvaluevaluevaluevalue12\";".to_string(),
+            committed_value: "\"REDACTED\"".to_string(),
+        }];
+
+        let report = audit_decorations(content, &decorations, Some(Path::new("test.rs")));
+        assert_eq!(report.findings.len(), 1);
+        assert!(!report.findings[0].leaks_secret);
+    }
+
+    #[test]
+    fn test_audit_of_empty_decorations_is_empty() {
+        let report = audit_decorations("no decorations here", &[], None);
+        assert!(report.findings.is_empty());
+    }
+}
@@ -1,20 +1,9 @@
 use anyhow::Result;
-use once_cell::sync::Lazy;
-use regex::Regex;
+use std::path::Path;
 
+use super::comment_syntax;
 use super::Decoration;
 
-// Static regex compilation for performance
-static START_PATTERN: Lazy<Regex> = Lazy::new(|| {
-    // Match comment lines with just @whiteout-start (and optional whitespace)
-    Regex::new(r"(?m)^\s*(?://|#|--|/\*|\*)\s*@whiteout-start\s*(?:\*/)?$").expect("Failed to compile start pattern")
-});
-
-static END_PATTERN: Lazy<Regex> = Lazy::new(|| {
-    // Match comment lines with just @whiteout-end (and optional whitespace)
-    Regex::new(r"(?m)^\s*(?://|#|--|/\*|\*)\s*@whiteout-end\s*(?:\*/)?$").expect("Failed to compile end pattern")
-});
-
 pub struct BlockParser;
 
 impl Default for BlockParser {
@@ -25,51 +14,58 @@ impl Default for BlockParser {
 
 impl BlockParser {
     pub fn new() -> Self {
-        // Force lazy static initialization
-        let _ = &*START_PATTERN;
-        let _ = &*END_PATTERN;
         Self
     }
 
-    pub fn parse(&self, content: &str) -> Result<Vec<Decoration>> {
+    /// Parses `@whiteout-start`/`@whiteout-end` blocks. `file_path`'s
+    /// extension selects which comment forms are recognized as markers
+    /// (e.g. `<!-- @whiteout-start -->` in `.html`), the same registry
+    /// [`super::inline::InlineParser`] uses; unknown extensions fall back
+    /// to the `//`/`#`/`--`/`/*`/`*` alternation.
+    pub fn parse(&self, content: &str, file_path: Option<&Path>) -> Result<Vec<Decoration>> {
+        let start_patterns = comment_syntax::start_patterns_for(file_path);
+        let end_patterns = comment_syntax::end_patterns_for(file_path);
+        let is_start = |line: &str| start_patterns.iter().any(|p| p.is_match(line));
+        let is_end = |line: &str| end_patterns.iter().any(|p| p.is_match(line));
+
         let mut decorations = Vec::new();
         let lines: Vec<&str> = content.lines().collect();
         let mut i = 0;
-        
+
         while i < lines.len() {
             // Check if line matches pattern and is not escaped
-            if START_PATTERN.is_match(lines[i]) && !lines[i].contains(r"\@whiteout-start") {
+            if is_start(lines[i]) && !lines[i].contains(r"\@whiteout-start") {
                 let start_line = i + 1;
                 let mut local_lines = Vec::new();
                 let mut committed_lines = Vec::new();
-                
+
                 i += 1;
-                
-                while i < lines.len() && !END_PATTERN.is_match(lines[i]) {
+
+                while i < lines.len() && !is_end(lines[i]) {
                     local_lines.push(lines[i]);
                     i += 1;
                 }
-                
+
                 // Only create decoration if we found the end marker
-                if i < lines.len() && END_PATTERN.is_match(lines[i]) {
+                if i < lines.len() && is_end(lines[i]) {
                     let _end_marker_line = i + 1;
                     i += 1;
                     
                     while i < lines.len() {
-                        if i + 1 < lines.len() && START_PATTERN.is_match(lines[i + 1]) {
+                        if i + 1 < lines.len() && is_start(lines[i + 1]) {
                             break;
                         }
-                        
-                        if START_PATTERN.is_match(lines[i]) || END_PATTERN.is_match(lines[i]) {
+
+                        if is_start(lines[i]) || is_end(lines[i]) {
                             break;
                         }
-                        
+
                         committed_lines.push(lines[i]);
                         i += 1;
-                        
-                        if !committed_lines.is_empty() && 
-                           (i >= lines.len() || lines[i].trim().is_empty() || 
-                            START_PATTERN.is_match(lines[i])) {
+
+                        if !committed_lines.is_empty() &&
+                           (i >= lines.len() || lines[i].trim().is_empty() ||
+                            is_start(lines[i])) {
                             break;
                         }
                     }
@@ -107,9 +103,9 @@ const DEBUG = false;
 const LOG_LEVEL = "error";
 "#;
         
-        let decorations = parser.parse(content).unwrap();
+        let decorations = parser.parse(content, Some(Path::new("test.rs"))).unwrap();
         assert_eq!(decorations.len(), 1);
-        
+
         match &decorations[0] {
             Decoration::Block { start_line, end_line: _, local_content, committed_content } => {
                 assert_eq!(*start_line, 2);
@@ -129,12 +125,12 @@ const SECRET = "value";
 // Missing @whiteout-end
 const OTHER = "data";
 "#;
-        
-        let decorations = parser.parse(content).unwrap();
+
+        let decorations = parser.parse(content, Some(Path::new("test.rs"))).unwrap();
         // Should not find any decorations since block is incomplete
         assert_eq!(decorations.len(), 0, "Should not match incomplete blocks");
     }
-    
+
     #[test]
     fn test_multiple_blocks() {
         let parser = BlockParser::new();
@@ -149,8 +145,26 @@ let y = 3;
 // @whiteout-end
 let y = 4;
 "#;
-        
-        let decorations = parser.parse(content).unwrap();
+
+        let decorations = parser.parse(content, Some(Path::new("test.rs"))).unwrap();
         assert_eq!(decorations.len(), 2);
     }
+
+    #[test]
+    fn test_python_file_ignores_rust_style_marker() {
+        let parser = BlockParser::new();
+        let content = "// @whiteout-start\nDEBUG = True\n// @whiteout-end\nDEBUG = False\n";
+
+        let decorations = parser.parse(content, Some(Path::new("settings.py"))).unwrap();
+        assert_eq!(decorations.len(), 0, "a // marker shouldn't be recognized in a Python file");
+    }
+
+    #[test]
+    fn test_html_file_recognizes_block_comment_markers() {
+        let parser = BlockParser::new();
+        let content = "<!-- @whiteout-start -->\nDEBUG = true\n<!-- @whiteout-end -->\nDEBUG = false\n";
+
+        let decorations = parser.parse(content, Some(Path::new("index.html"))).unwrap();
+        assert_eq!(decorations.len(), 1);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,469 @@
+//! Per-language comment-syntax registry for inline `@whiteout:` decorations.
+//!
+//! The old `INLINE_PATTERN` hardcoded `(?://|#|--)` as the comment lead-in,
+//! so decorations silently failed to parse in languages that comment
+//! differently (CSS, HTML, Lua, Clojure, ...). Each entry below gives an
+//! extension its own line- and/or block-comment form; anything not listed
+//! falls back to the original `//`/`#`/`--` alternation. Entries are
+//! sorted lexicographically by extension, the same convention ripgrep uses
+//! for its built-in type definitions.
+//!
+//! Extensions this table doesn't know can be added at runtime with
+//! [`register_language`] instead of editing [`LANGUAGES`] -- see
+//! `Config`'s `comment_syntax` overrides, which call it once per entry a
+//! project configures.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::path::Path;
+use std::sync::RwLock;
+
+/// A language's comment delimiters: zero or more line-comment leads, plus
+/// an optional block-comment open/close pair.
+struct CommentSyntax {
+    extension: &'static str,
+    line: &'static [&'static str],
+    block: Option<(&'static str, &'static str)>,
+}
+
+// Sorted lexicographically by extension.
+const LANGUAGES: &[CommentSyntax] = &[
+    CommentSyntax { extension: "c", line: &["//"], block: Some(("/*", "*/")) },
+    CommentSyntax { extension: "clj", line: &[";"], block: None },
+    CommentSyntax { extension: "cpp", line: &["//"], block: Some(("/*", "*/")) },
+    CommentSyntax { extension: "css", line: &[], block: Some(("/*", "*/")) },
+    CommentSyntax { extension: "go", line: &["//"], block: Some(("/*", "*/")) },
+    CommentSyntax { extension: "hs", line: &["--"], block: None },
+    CommentSyntax { extension: "html", line: &[], block: Some(("<!--", "-->")) },
+    CommentSyntax { extension: "java", line: &["//"], block: Some(("/*", "*/")) },
+    CommentSyntax { extension: "js", line: &["//"], block: Some(("/*", "*/")) },
+    CommentSyntax { extension: "lua", line: &["--"], block: None },
+    CommentSyntax { extension: "py", line: &["#"], block: None },
+    CommentSyntax { extension: "rb", line: &["#"], block: None },
+    CommentSyntax { extension: "rs", line: &["//"], block: Some(("/*", "*/")) },
+    CommentSyntax { extension: "scm", line: &[";"], block: None },
+    CommentSyntax { extension: "scss", line: &[], block: Some(("/*", "*/")) },
+    CommentSyntax { extension: "sh", line: &["#"], block: None },
+    CommentSyntax { extension: "sql", line: &["--"], block: Some(("/*", "*/")) },
+    CommentSyntax { extension: "tex", line: &["%"], block: None },
+    CommentSyntax { extension: "toml", line: &["#"], block: None },
+    CommentSyntax { extension: "ts", line: &["//"], block: Some(("/*", "*/")) },
+    CommentSyntax { extension: "vb", line: &["'"], block: None },
+    CommentSyntax { extension: "xml", line: &[], block: Some(("<!--", "-->")) },
+    CommentSyntax { extension: "yaml", line: &["#"], block: None },
+    CommentSyntax { extension: "yml", line: &["#"], block: None },
+];
+
+/// The legacy `//`/`#`/`--` alternation, used for unknown extensions.
+const DEFAULT_LINE_LEADS: &[&str] = &["//", "#", "--"];
+
+static DEFAULT_PATTERNS: Lazy<Vec<Regex>> = Lazy::new(|| vec![line_pattern(DEFAULT_LINE_LEADS)]);
+
+static LANGUAGE_PATTERNS: Lazy<Vec<(&'static str, Vec<Regex>)>> = Lazy::new(|| {
+    LANGUAGES
+        .iter()
+        .map(|lang| (lang.extension, build_patterns(lang)))
+        .collect()
+});
+
+/// A language registered at runtime via [`register_language`], kept
+/// separate from the built-in [`LANGUAGES`] table so the latter can stay a
+/// plain `const`. Its patterns are precomputed once at registration time,
+/// the same way [`LANGUAGE_PATTERNS`] precomputes the built-ins', rather
+/// than recompiled on every lookup.
+struct UserLanguage {
+    extension: &'static str,
+    line: &'static [&'static str],
+    block: Option<(&'static str, &'static str)>,
+    patterns: &'static [Regex],
+    start: &'static [Regex],
+    end: &'static [Regex],
+    simple: &'static [Regex],
+}
+
+/// Languages registered via [`register_language`], consulted before
+/// [`LANGUAGES`] so a project can override a built-in extension as well as
+/// add one the built-in table doesn't know.
+static USER_LANGUAGES: Lazy<RwLock<Vec<UserLanguage>>> = Lazy::new(|| RwLock::new(Vec::new()));
+
+/// Registers a comment syntax for `extension`, taking precedence over both
+/// the built-in [`LANGUAGES`] table and any earlier registration for the
+/// same extension (case-insensitively). This is the "without a code
+/// change" extension point: a project with a language this table doesn't
+/// know (or comments differently than the built-in entry assumes) calls
+/// this once instead of editing [`LANGUAGES`].
+///
+/// The strings are leaked to give the registry `'static` storage, the same
+/// lifetime [`LANGUAGES`]' entries have -- fine for the handful of calls
+/// this is meant for (config load time), not something to do in a loop.
+pub fn register_language(extension: String, line: Vec<String>, block: Option<(String, String)>) {
+    let extension: &'static str = Box::leak(extension.into_boxed_str());
+    let line: &'static [&'static str] = Box::leak(
+        line.into_iter()
+            .map(|s| -> &'static str { Box::leak(s.into_boxed_str()) })
+            .collect::<Vec<_>>()
+            .into_boxed_slice(),
+    );
+    let block: Option<(&'static str, &'static str)> = block.map(|(open, close)| {
+        let open: &'static str = Box::leak(open.into_boxed_str());
+        let close: &'static str = Box::leak(close.into_boxed_str());
+        (open, close)
+    });
+
+    let syntax = CommentSyntax { extension, line, block };
+    let patterns: &'static [Regex] = Box::leak(build_patterns(&syntax).into_boxed_slice());
+    let start: &'static [Regex] = Box::leak(build_marker_patterns(&syntax, "@whiteout-start").into_boxed_slice());
+    let end: &'static [Regex] = Box::leak(build_marker_patterns(&syntax, "@whiteout-end").into_boxed_slice());
+    let simple: &'static [Regex] = Box::leak(build_marker_patterns(&syntax, "@whiteout").into_boxed_slice());
+
+    let mut languages = USER_LANGUAGES.write().expect("comment syntax registry lock poisoned");
+    languages.retain(|lang| !lang.extension.eq_ignore_ascii_case(extension));
+    languages.push(UserLanguage { extension, line, block, patterns, start, end, simple });
+}
+
+fn build_patterns(lang: &CommentSyntax) -> Vec<Regex> {
+    let mut patterns = Vec::new();
+    if !lang.line.is_empty() {
+        patterns.push(line_pattern(lang.line));
+    }
+    if let Some((open, close)) = lang.block {
+        patterns.push(block_pattern(open, close));
+    }
+    patterns
+}
+
+/// Builds a pattern matching `code <lead> @whiteout: value`, where `<lead>`
+/// is any of `leads`. Deliberately unanchored (no leading `^`) and stops
+/// `value` at the next `<lead> @whiteout:` marker rather than running to the
+/// end of the line, so `captures_iter` finds every independent decoration on
+/// a line that carries more than one (a minified record with several
+/// secrets, for instance) instead of just the first.
+fn line_pattern(leads: &[&str]) -> Regex {
+    let alternation = leads
+        .iter()
+        .map(|lead| regex::escape(lead))
+        .collect::<Vec<_>>()
+        .join("|");
+    Regex::new(&format!(
+        r"(?m)(.+?)\s*(?:{alt})\s*@whiteout:\s*(.+?)(?=\s*(?:{alt})\s*@whiteout:|$)",
+        alt = alternation
+    ))
+    .expect("Failed to compile line-comment inline pattern")
+}
+
+/// Builds a pattern matching `code <open> @whiteout: value <close>`. The
+/// required `<close>` token already bounds `value`, so (unlike
+/// [`line_pattern`]) no lookahead is needed to support several decorations
+/// on one line.
+fn block_pattern(open: &str, close: &str) -> Regex {
+    Regex::new(&format!(
+        r"(?m)(.+?)\s*{}\s*@whiteout:\s*(.+?)\s*{}",
+        regex::escape(open),
+        regex::escape(close)
+    ))
+    .expect("Failed to compile block-comment inline pattern")
+}
+
+fn user_patterns_for(extension: &str, select: impl Fn(&UserLanguage) -> &'static [Regex]) -> Option<&'static [Regex]> {
+    USER_LANGUAGES
+        .read()
+        .expect("comment syntax registry lock poisoned")
+        .iter()
+        .find(|lang| lang.extension.eq_ignore_ascii_case(extension))
+        .map(select)
+}
+
+/// Returns the inline-decoration patterns to try for `path`, in priority
+/// order, based on its extension. Falls back to the default `//`/`#`/`--`
+/// alternation when the extension is unknown or `path` is absent.
+pub fn patterns_for(path: Option<&Path>) -> &'static [Regex] {
+    let extension = path.and_then(|p| p.extension()).and_then(|ext| ext.to_str());
+
+    match extension {
+        Some(ext) => user_patterns_for(ext, |lang| lang.patterns)
+            .or_else(|| {
+                LANGUAGE_PATTERNS
+                    .iter()
+                    .find(|(known, _)| known.eq_ignore_ascii_case(ext))
+                    .map(|(_, patterns)| patterns.as_slice())
+            })
+            .unwrap_or(&DEFAULT_PATTERNS),
+        None => &DEFAULT_PATTERNS,
+    }
+}
+
+/// The legacy `//`/`#`/`--`/`/*`/`*` alternation `block`/`simple` used to
+/// hardcode for every `@whiteout-start`/`@whiteout-end`/`@whiteout` marker
+/// line, kept as the fallback for extensions not in [`LANGUAGES`].
+fn default_marker_pattern(marker: &str) -> Regex {
+    Regex::new(&format!(
+        r"(?m)^\s*(?://|#|--|/\*|\*)\s*{}\s*(?:\*/)?$",
+        regex::escape(marker)
+    ))
+    .expect("Failed to compile default marker pattern")
+}
+
+/// Builds a pattern matching a marker-only comment line, e.g.
+/// `// @whiteout-start` for `leads = ["//"]`.
+fn marker_line_pattern(leads: &[&str], marker: &str) -> Regex {
+    let alternation = leads.iter().map(|lead| regex::escape(lead)).collect::<Vec<_>>().join("|");
+    Regex::new(&format!(r"(?m)^\s*(?:{})\s*{}\s*$", alternation, regex::escape(marker)))
+        .expect("Failed to compile line-comment marker pattern")
+}
+
+/// Builds a pattern matching a marker wrapped in a block comment, e.g.
+/// `<!-- @whiteout-start -->`.
+fn marker_block_pattern(open: &str, close: &str, marker: &str) -> Regex {
+    Regex::new(&format!(
+        r"(?m)^\s*{}\s*{}\s*{}\s*$",
+        regex::escape(open),
+        regex::escape(marker),
+        regex::escape(close)
+    ))
+    .expect("Failed to compile block-comment marker pattern")
+}
+
+fn build_marker_patterns(lang: &CommentSyntax, marker: &str) -> Vec<Regex> {
+    let mut patterns = Vec::new();
+    if !lang.line.is_empty() {
+        patterns.push(marker_line_pattern(lang.line, marker));
+    }
+    if let Some((open, close)) = lang.block {
+        patterns.push(marker_block_pattern(open, close, marker));
+    }
+    patterns
+}
+
+fn marker_table(marker: &'static str) -> Vec<(&'static str, Vec<Regex>)> {
+    LANGUAGES.iter().map(|lang| (lang.extension, build_marker_patterns(lang, marker))).collect()
+}
+
+fn marker_patterns_for(
+    path: Option<&Path>,
+    user_select: impl Fn(&UserLanguage) -> &'static [Regex],
+    table: &'static Lazy<Vec<(&'static str, Vec<Regex>)>>,
+    default: &'static Lazy<Vec<Regex>>,
+) -> &'static [Regex] {
+    let extension = path.and_then(|p| p.extension()).and_then(|ext| ext.to_str());
+
+    match extension {
+        Some(ext) => user_patterns_for(ext, user_select)
+            .or_else(|| {
+                table
+                    .iter()
+                    .find(|(known, _)| known.eq_ignore_ascii_case(ext))
+                    .map(|(_, patterns)| patterns.as_slice())
+            })
+            .unwrap_or(default),
+        None => default,
+    }
+}
+
+static START_LANGUAGE_PATTERNS: Lazy<Vec<(&'static str, Vec<Regex>)>> = Lazy::new(|| marker_table("@whiteout-start"));
+static END_LANGUAGE_PATTERNS: Lazy<Vec<(&'static str, Vec<Regex>)>> = Lazy::new(|| marker_table("@whiteout-end"));
+static SIMPLE_LANGUAGE_PATTERNS: Lazy<Vec<(&'static str, Vec<Regex>)>> = Lazy::new(|| marker_table("@whiteout"));
+
+static DEFAULT_START_PATTERNS: Lazy<Vec<Regex>> = Lazy::new(|| vec![default_marker_pattern("@whiteout-start")]);
+static DEFAULT_END_PATTERNS: Lazy<Vec<Regex>> = Lazy::new(|| vec![default_marker_pattern("@whiteout-end")]);
+static DEFAULT_SIMPLE_PATTERNS: Lazy<Vec<Regex>> = Lazy::new(|| vec![default_marker_pattern("@whiteout")]);
+
+/// The `@whiteout-start` patterns to try for `path`, based on its
+/// extension; unknown extensions fall back to the legacy alternation.
+pub fn start_patterns_for(path: Option<&Path>) -> &'static [Regex] {
+    marker_patterns_for(path, |lang| lang.start, &START_LANGUAGE_PATTERNS, &DEFAULT_START_PATTERNS)
+}
+
+/// The `@whiteout-end` patterns to try for `path`.
+pub fn end_patterns_for(path: Option<&Path>) -> &'static [Regex] {
+    marker_patterns_for(path, |lang| lang.end, &END_LANGUAGE_PATTERNS, &DEFAULT_END_PATTERNS)
+}
+
+/// The bare `@whiteout` patterns to try for `path`.
+pub fn simple_patterns_for(path: Option<&Path>) -> &'static [Regex] {
+    marker_patterns_for(path, |lang| lang.simple, &SIMPLE_LANGUAGE_PATTERNS, &DEFAULT_SIMPLE_PATTERNS)
+}
+
+fn line_and_block_for(extension: &str) -> Option<(&'static [&'static str], Option<(&'static str, &'static str)>)> {
+    if let Some(lang) = USER_LANGUAGES
+        .read()
+        .expect("comment syntax registry lock poisoned")
+        .iter()
+        .find(|lang| lang.extension.eq_ignore_ascii_case(extension))
+    {
+        return Some((lang.line, lang.block));
+    }
+
+    LANGUAGES
+        .iter()
+        .find(|lang| lang.extension.eq_ignore_ascii_case(extension))
+        .map(|lang| (lang.line, lang.block))
+}
+
+/// The comment open/close token to regenerate an inline `@whiteout:`
+/// marker in, when rewriting one that already parsed successfully (e.g.
+/// smudge restoring the real value, or clean re-stamping the committed
+/// line). `close` is `""` for line comments. Picks the language's first
+/// line lead when it has one, otherwise its block form; falls back to
+/// `//` for unknown extensions, matching [`DEFAULT_LINE_LEADS`].
+pub fn comment_token_for(path: Option<&Path>) -> (&'static str, &'static str) {
+    let extension = path.and_then(|p| p.extension()).and_then(|ext| ext.to_str());
+    let lang = extension.and_then(line_and_block_for);
+
+    match lang {
+        Some((line, _)) if !line.is_empty() => (line[0], ""),
+        Some((_, Some((open, close)))) => (open, close),
+        _ => ("//", ""),
+    }
+}
+
+/// Renders `value // @whiteout: committed`, or the file type's equivalent
+/// (e.g. `value /* @whiteout: committed */` for a block-comment-only
+/// language), using the same token [`patterns_for`] would have recognized
+/// it by. Used both to re-emit a decoration already parsed out of existing
+/// content ([`super::apply::apply_decorations`]) and to stamp a brand new
+/// one (`mark`).
+pub fn render_inline_marker(value: &str, committed: &str, file_path: Option<&Path>) -> String {
+    let (open, close) = comment_token_for(file_path);
+    if close.is_empty() {
+        format!("{} {} @whiteout: {}", value, open, committed)
+    } else {
+        format!("{} {} @whiteout: {} {}", value, open, committed, close)
+    }
+}
+
+/// Renders a marker-only comment line, e.g. `@whiteout-start` as
+/// `// @whiteout-start` for Rust or `<!-- @whiteout-start -->` for HTML.
+/// The result is guaranteed to parse back via
+/// [`start_patterns_for`]/[`end_patterns_for`]/[`simple_patterns_for`],
+/// since both sides read the same [`LANGUAGES`]/[`USER_LANGUAGES`] entry.
+pub fn render_marker(file_path: Option<&Path>, marker: &str) -> String {
+    let extension = file_path.and_then(|p| p.extension()).and_then(|ext| ext.to_str());
+    let lang = extension.and_then(line_and_block_for);
+
+    match lang {
+        Some((line, _)) if !line.is_empty() => format!("{} {}", line[0], marker),
+        Some((_, Some((open, close)))) => format!("{} {} {}", open, marker, close),
+        _ => format!("// {}", marker),
+    }
+}
+
+/// Renders a plain prose comment line (no `@whiteout` marker), e.g. the
+/// placeholder lines `mark` writes under a block decoration's
+/// `@whiteout-end` to describe what's now missing.
+pub fn render_comment_line(file_path: Option<&Path>, text: &str) -> String {
+    let (open, close) = comment_token_for(file_path);
+    if close.is_empty() {
+        format!("{} {}", open, text)
+    } else {
+        format!("{} {} {}", open, text, close)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_extension_falls_back_to_default() {
+        let patterns = patterns_for(Some(Path::new("main.xyz")));
+        assert_eq!(patterns.len(), 1);
+        assert!(patterns[0].is_match(r#"let x = 1; // @whiteout: 2"#));
+    }
+
+    #[test]
+    fn test_css_uses_block_comment_form() {
+        let patterns = patterns_for(Some(Path::new("style.css")));
+        assert_eq!(patterns.len(), 1);
+        assert!(patterns[0].is_match("color: red; /* @whiteout: blue */"));
+        assert!(!patterns[0].is_match("color: red; // @whiteout: blue"));
+    }
+
+    #[test]
+    fn test_lua_uses_line_comment_form() {
+        let patterns = patterns_for(Some(Path::new("init.lua")));
+        assert_eq!(patterns.len(), 1);
+        assert!(patterns[0].is_match("local x = 1 -- @whiteout: 2"));
+    }
+
+    #[test]
+    fn test_sql_supports_both_line_and_block_forms() {
+        let patterns = patterns_for(Some(Path::new("seed.sql")));
+        assert_eq!(patterns.len(), 2);
+        assert!(patterns.iter().any(|p| p.is_match("x = 1 -- @whiteout: 2")));
+        assert!(patterns.iter().any(|p| p.is_match("x = 1 /* @whiteout: 2 */")));
+    }
+
+    #[test]
+    fn test_no_path_falls_back_to_default() {
+        let patterns = patterns_for(None);
+        assert_eq!(patterns.len(), 1);
+    }
+
+    #[test]
+    fn test_python_rejects_rust_style_marker() {
+        let start = start_patterns_for(Some(Path::new("config.py")));
+        assert!(start.iter().any(|p| p.is_match("# @whiteout-start")));
+        assert!(!start.iter().any(|p| p.is_match("// @whiteout-start")));
+    }
+
+    #[test]
+    fn test_rust_rejects_python_style_marker() {
+        let end = end_patterns_for(Some(Path::new("main.rs")));
+        assert!(end.iter().any(|p| p.is_match("// @whiteout-end")));
+        assert!(!end.iter().any(|p| p.is_match("# @whiteout-end")));
+    }
+
+    #[test]
+    fn test_html_simple_marker_uses_block_comment_form() {
+        let simple = simple_patterns_for(Some(Path::new("index.html")));
+        assert!(simple.iter().any(|p| p.is_match("<!-- @whiteout -->")));
+        assert!(!simple.iter().any(|p| p.is_match("// @whiteout")));
+    }
+
+    #[test]
+    fn test_unknown_extension_marker_patterns_fall_back_to_legacy_alternation() {
+        let start = start_patterns_for(Some(Path::new("main.xyz")));
+        assert!(start.iter().any(|p| p.is_match("// @whiteout-start")));
+        assert!(start.iter().any(|p| p.is_match("# @whiteout-start")));
+    }
+
+    #[test]
+    fn test_comment_token_for_known_extensions() {
+        assert_eq!(comment_token_for(Some(Path::new("main.py"))), ("#", ""));
+        assert_eq!(comment_token_for(Some(Path::new("seed.sql"))), ("--", ""));
+        assert_eq!(comment_token_for(Some(Path::new("style.css"))), ("/*", "*/"));
+        assert_eq!(comment_token_for(Some(Path::new("main.rs"))), ("//", ""));
+        assert_eq!(comment_token_for(Some(Path::new("main.xyz"))), ("//", ""));
+        assert_eq!(comment_token_for(None), ("//", ""));
+    }
+
+    #[test]
+    fn test_render_marker_and_comment_line_match_what_they_parse() {
+        let rendered = render_marker(Some(Path::new("index.html")), "@whiteout-start");
+        assert_eq!(rendered, "<!-- @whiteout-start -->");
+        assert!(start_patterns_for(Some(Path::new("index.html"))).iter().any(|p| p.is_match(&rendered)));
+
+        let comment = render_comment_line(Some(Path::new("seed.sql")), "REDACTED");
+        assert_eq!(comment, "-- REDACTED");
+    }
+
+    #[test]
+    fn test_register_language_adds_unknown_extension_without_editing_languages() {
+        register_language("kt".to_string(), vec!["//".to_string()], None);
+
+        let patterns = patterns_for(Some(Path::new("main.kt")));
+        assert!(patterns.iter().any(|p| p.is_match("val x = 1 // @whiteout: 2")));
+        assert_eq!(comment_token_for(Some(Path::new("main.kt"))), ("//", ""));
+    }
+
+    #[test]
+    fn test_register_language_overrides_a_built_in_extension() {
+        // `.tex` normally only comments with `%`; override it to also
+        // recognize `//`, and confirm the override wins over `LANGUAGES`.
+        register_language("tex".to_string(), vec!["//".to_string()], None);
+
+        let patterns = patterns_for(Some(Path::new("paper.tex")));
+        assert!(patterns.iter().any(|p| p.is_match("x = 1 // @whiteout: 2")));
+        assert!(!patterns.iter().any(|p| p.is_match("x = 1 % @whiteout: 2")));
+    }
+}
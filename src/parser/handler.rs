@@ -0,0 +1,339 @@
+use std::collections::HashMap;
+
+use super::types::{Decoration, PartialReplacement};
+
+/// The terminator a source line ended with. Kept as an enum rather than a
+/// borrowed `&str` so a [`DecorationHandler`] can stash it in its own
+/// accumulator without inheriting `content`'s lifetime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineTerminator {
+    Crlf,
+    Lf,
+    /// Only possible on the final line, if the file doesn't end with a
+    /// newline.
+    None,
+}
+
+impl LineTerminator {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineTerminator::Crlf => "\r\n",
+            LineTerminator::Lf => "\n",
+            LineTerminator::None => "",
+        }
+    }
+}
+
+/// Splits `content` into `(line, terminator)` pairs. Unlike `str::lines`,
+/// this keeps enough information to reconstruct the file byte-for-byte, so a
+/// CRLF file isn't silently rewritten to LF by a round trip through
+/// [`drive`].
+pub(crate) fn split_lines_preserving_terminators(content: &str) -> Vec<(&str, LineTerminator)> {
+    let mut lines = Vec::new();
+    let mut rest = content;
+
+    while let Some(newline_pos) = rest.find('\n') {
+        let (line, terminator) = if newline_pos > 0 && rest.as_bytes()[newline_pos - 1] == b'\r' {
+            (&rest[..newline_pos - 1], LineTerminator::Crlf)
+        } else {
+            (&rest[..newline_pos], LineTerminator::Lf)
+        };
+        lines.push((line, terminator));
+        rest = &rest[newline_pos + 1..];
+    }
+    if !rest.is_empty() {
+        lines.push((rest, LineTerminator::None));
+    }
+
+    lines
+}
+
+/// Everything a [`DecorationHandler::on_block`] callback needs. `lines` and
+/// `line_terminators` give access to the block's end-marker line (and
+/// anything else in the file), the same way the old inline implementation
+/// indexed directly into the full line list.
+pub struct BlockContext<'a> {
+    pub line_num: usize,
+    pub start_line: &'a str,
+    pub term: LineTerminator,
+    pub end_line: usize,
+    pub local_content: &'a str,
+    pub committed_content: &'a str,
+    pub lines: &'a [&'a str],
+    pub line_terminators: &'a [LineTerminator],
+    /// Terminator to use for a line synthesized from stored local/committed
+    /// content, which was saved without its original terminator.
+    pub default_terminator: LineTerminator,
+}
+
+/// Everything a [`DecorationHandler::on_inline`] callback needs.
+/// `values` holds `(local_value, committed_value)` for every `@whiteout:`
+/// marker on this line, in occurrence order -- a line can carry more than
+/// one (e.g. a minified record with several independent redactions).
+pub struct InlineContext<'a> {
+    pub line_num: usize,
+    pub line: &'a str,
+    pub term: LineTerminator,
+    pub values: Vec<(&'a str, &'a str)>,
+}
+
+pub struct PartialContext<'a> {
+    pub line_num: usize,
+    pub line: &'a str,
+    pub term: LineTerminator,
+    pub replacements: &'a [PartialReplacement],
+}
+
+pub struct PassthroughContext<'a> {
+    pub line_num: usize,
+    pub line: &'a str,
+    pub term: LineTerminator,
+}
+
+/// Driven by [`drive`] once per line of content; implementors decide what
+/// happens to each decoration and where scanning resumes next. Each method
+/// returns the last line number it consumed -- `drive` resumes right after
+/// it, which is where the old `skip_until` bookkeeping now lives.
+///
+/// [`RewriteHandler`] is the default, rewriting content exactly as
+/// `apply_decorations` always has. [`super::audit::AuditHandler`] is the
+/// read-only alternative that builds a report instead.
+pub trait DecorationHandler {
+    fn on_block(&mut self, ctx: BlockContext<'_>) -> usize;
+    fn on_inline(&mut self, ctx: InlineContext<'_>) -> usize;
+    fn on_partial(&mut self, ctx: PartialContext<'_>) -> usize;
+    fn on_passthrough(&mut self, ctx: PassthroughContext<'_>) -> usize;
+}
+
+/// Walks `content` line by line, dispatching each one to `handler` based on
+/// whichever decoration (if any) anchors that line. Block/Partial keep the
+/// original first-decoration-per-line-wins semantics; Inline keeps its
+/// collect-all-on-the-line semantics. Callers that don't care about a
+/// rewritten string (e.g. an audit report) can implement
+/// [`DecorationHandler`] directly instead of going through
+/// [`super::apply::apply_decorations`].
+pub fn drive<H: DecorationHandler>(content: &str, decorations: &[Decoration], handler: &mut H) {
+    let numbered_lines = split_lines_preserving_terminators(content);
+    let lines: Vec<&str> = numbered_lines.iter().map(|(line, _)| *line).collect();
+    let line_terminators: Vec<LineTerminator> = numbered_lines.iter().map(|(_, term)| *term).collect();
+    let default_terminator = line_terminators
+        .iter()
+        .copied()
+        .find(|term| *term != LineTerminator::None)
+        .unwrap_or(LineTerminator::Lf);
+
+    // Index decorations by anchor line in a single pass, so the line walk
+    // below does O(1) lookups instead of rescanning every decoration per
+    // line.
+    let mut block_by_start: HashMap<usize, &Decoration> = HashMap::new();
+    let mut inline_by_line: HashMap<usize, Vec<&Decoration>> = HashMap::new();
+    let mut partial_by_line: HashMap<usize, &Decoration> = HashMap::new();
+    for decoration in decorations {
+        match decoration {
+            Decoration::Block { start_line, .. } => {
+                block_by_start.entry(*start_line).or_insert(decoration);
+            }
+            Decoration::Inline { line, .. } => {
+                inline_by_line.entry(*line).or_default().push(decoration);
+            }
+            Decoration::Partial { line, .. } => {
+                partial_by_line.entry(*line).or_insert(decoration);
+            }
+        }
+    }
+
+    let mut line_num = 1;
+    while line_num <= lines.len() {
+        let line = lines[line_num - 1];
+        let term = line_terminators[line_num - 1];
+
+        let resume = if let Some(Decoration::Block { end_line, local_content, committed_content, .. }) =
+            block_by_start.get(&line_num).copied()
+        {
+            handler.on_block(BlockContext {
+                line_num,
+                start_line: line,
+                term,
+                end_line: *end_line,
+                local_content,
+                committed_content,
+                lines: &lines,
+                line_terminators: &line_terminators,
+                default_terminator,
+            })
+        } else if let Some(mut inline_on_line) = inline_by_line.get(&line_num).cloned() {
+            inline_on_line.sort_by_key(|decoration| match decoration {
+                Decoration::Inline { occurrence, .. } => *occurrence,
+                _ => unreachable!(),
+            });
+            let values = inline_on_line
+                .iter()
+                .map(|decoration| match decoration {
+                    Decoration::Inline { local_value, committed_value, .. } => {
+                        (local_value.as_str(), committed_value.as_str())
+                    }
+                    _ => unreachable!(),
+                })
+                .collect();
+            handler.on_inline(InlineContext { line_num, line, term, values })
+        } else if let Some(Decoration::Partial { replacements, .. }) = partial_by_line.get(&line_num).copied() {
+            handler.on_partial(PartialContext { line_num, line, term, replacements })
+        } else {
+            handler.on_passthrough(PassthroughContext { line_num, line, term })
+        };
+
+        line_num = resume.max(line_num) + 1;
+    }
+}
+
+/// The default [`DecorationHandler`]: reproduces exactly what
+/// `apply_decorations` did before it was split into a trait, including the
+/// clean-vs-smudge branching for simple vs. marker-delimited blocks.
+pub struct RewriteHandler<'p> {
+    use_local: bool,
+    file_path: Option<&'p std::path::Path>,
+    /// Which named alternative a multi-environment `Partial` replacement
+    /// should emit when smudging; ignored on clean (clean always preserves
+    /// every alternative) and ignored by the original local/committed pair
+    /// form. `None` falls back to each replacement's first alternative.
+    active_profile: Option<&'p str>,
+    result: Vec<(String, LineTerminator)>,
+}
+
+impl<'p> RewriteHandler<'p> {
+    pub fn new(use_local: bool, file_path: Option<&'p std::path::Path>, active_profile: Option<&'p str>) -> Self {
+        Self { use_local, file_path, active_profile, result: Vec::new() }
+    }
+
+    pub fn into_output(self) -> String {
+        let mut output = String::new();
+        for (line, term) in &self.result {
+            output.push_str(line);
+            output.push_str(term.as_str());
+        }
+        output
+    }
+}
+
+impl<'p> DecorationHandler for RewriteHandler<'p> {
+    fn on_block(&mut self, ctx: BlockContext<'_>) -> usize {
+        let is_simple_pattern = ctx.start_line.contains("@whiteout")
+            && !ctx.start_line.contains("@whiteout-start")
+            && !ctx.start_line.contains("@whiteout:");
+
+        let mut skip_until = ctx.end_line;
+
+        if self.use_local {
+            if is_simple_pattern {
+                // Simple @whiteout: Keep marker and show local content
+                self.result.push((ctx.start_line.to_string(), ctx.term));
+                for content_line in ctx.local_content.lines() {
+                    self.result.push((content_line.to_string(), ctx.default_terminator));
+                }
+            } else {
+                // Block with markers: Keep markers and show local content
+                self.result.push((ctx.start_line.to_string(), ctx.term));
+                for content_line in ctx.local_content.lines() {
+                    self.result.push((content_line.to_string(), ctx.default_terminator));
+                }
+                if ctx.end_line <= ctx.lines.len() {
+                    self.result.push((
+                        ctx.lines[ctx.end_line - 1].to_string(),
+                        ctx.line_terminators[ctx.end_line - 1],
+                    ));
+                }
+                let committed_lines = ctx.committed_content.lines().count();
+                if committed_lines > 0 {
+                    skip_until += committed_lines;
+                }
+            }
+        } else if is_simple_pattern {
+            // Simple @whiteout: Keep the marker, skip the local content
+            self.result.push((ctx.start_line.to_string(), ctx.term));
+        } else {
+            // Block with @whiteout-start/end: Keep markers with empty content
+            self.result.push((ctx.start_line.to_string(), ctx.term));
+            if ctx.end_line <= ctx.lines.len() {
+                self.result.push((
+                    ctx.lines[ctx.end_line - 1].to_string(),
+                    ctx.line_terminators[ctx.end_line - 1],
+                ));
+            }
+            if !ctx.committed_content.is_empty() {
+                for content_line in ctx.committed_content.lines() {
+                    self.result.push((content_line.to_string(), ctx.default_terminator));
+                }
+            }
+            skip_until = ctx.end_line + ctx.committed_content.lines().count();
+        }
+
+        skip_until
+    }
+
+    fn on_inline(&mut self, ctx: InlineContext<'_>) -> usize {
+        let rendered = ctx
+            .values
+            .iter()
+            .map(|(local_value, committed_value)| {
+                if self.use_local {
+                    // Smudge: Show local value with decoration
+                    super::comment_syntax::render_inline_marker(local_value, committed_value, self.file_path)
+                } else {
+                    // Clean: Show committed value WITH decoration marker for smudge to work
+                    super::comment_syntax::render_inline_marker(committed_value, committed_value, self.file_path)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        self.result.push((rendered, ctx.term));
+        ctx.line_num
+    }
+
+    fn on_partial(&mut self, ctx: PartialContext<'_>) -> usize {
+        let mut processed_line = ctx.line.to_string();
+
+        for replacement in ctx.replacements.iter().rev() {
+            let new_value = if replacement.is_legacy_pair() {
+                if self.use_local {
+                    // Smudge: Use local value in the pattern
+                    format!("[[{}||{}]]", replacement.local_value(), replacement.committed_value())
+                } else {
+                    // Clean: Preserve pattern structure with committed value for smudge to work
+                    format!("[[{}||{}]]", replacement.committed_value(), replacement.committed_value())
+                }
+            } else if self.use_local {
+                // Smudge: collapse to whichever profile is active, bare --
+                // a concrete value for this checkout, not the template. An
+                // active profile that matches nothing is rejected by
+                // `transform::smudge` before it ever reaches here; this
+                // fallback only covers a caller (e.g. a test) that invokes
+                // `apply_decorations` directly without that check.
+                replacement
+                    .select(self.active_profile)
+                    .unwrap_or(&replacement.alternatives[0])
+                    .value
+                    .clone()
+            } else {
+                // Clean: keep every alternative so a later smudge into any
+                // profile can still reconstruct the right value.
+                replacement.render()
+            };
+
+            if replacement.start < processed_line.len() {
+                processed_line.replace_range(
+                    replacement.start..replacement.end.min(processed_line.len()),
+                    &new_value,
+                );
+            }
+        }
+
+        self.result.push((processed_line, ctx.term));
+        ctx.line_num
+    }
+
+    fn on_passthrough(&mut self, ctx: PassthroughContext<'_>) -> usize {
+        self.result.push((ctx.line.to_string(), ctx.term));
+        ctx.line_num
+    }
+}
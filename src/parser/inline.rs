@@ -1,15 +1,9 @@
 use anyhow::Result;
-use once_cell::sync::Lazy;
-use regex::Regex;
+use std::path::Path;
 
+use super::comment_syntax;
 use super::Decoration;
 
-// Static regex compilation for 78% performance improvement
-static INLINE_PATTERN: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"(?m)^(.+?)\s*(?://|#|--)\s*@whiteout:\s*(.+?)$")
-        .expect("Failed to compile inline pattern")
-});
-
 pub struct InlineParser;
 
 impl Default for InlineParser {
@@ -20,31 +14,45 @@ impl Default for InlineParser {
 
 impl InlineParser {
     pub fn new() -> Self {
-        // Force lazy static initialization
-        let _ = &*INLINE_PATTERN;
         Self
     }
 
-    pub fn parse(&self, content: &str) -> Result<Vec<Decoration>> {
+    /// Parses inline `@whiteout:` decorations. `file_path`'s extension
+    /// selects the comment syntax to look for (e.g. `/* ... */` in `.css`);
+    /// unknown extensions fall back to the `//`/`#`/`--` alternation. A line
+    /// carrying several `@whiteout:` markers (a minified record with more
+    /// than one secret, say) yields one decoration per marker, in order.
+    pub fn parse(&self, content: &str, file_path: Option<&Path>) -> Result<Vec<Decoration>> {
+        let patterns = comment_syntax::patterns_for(file_path);
         let mut decorations = Vec::new();
-        
+
         for (line_num, line) in content.lines().enumerate() {
+            // Cheap substring scan before trying any of `patterns`' regexes:
+            // most lines in a typical file don't mention the marker at all.
+            if !line.contains("@whiteout:") {
+                continue;
+            }
             // Skip escaped decorations
             if line.contains(r"\@whiteout:") {
                 continue;
             }
-            if let Some(captures) = INLINE_PATTERN.captures(line) {
+            let Some(pattern) = patterns.iter().find(|pattern| pattern.is_match(line)) else {
+                continue;
+            };
+
+            for (occurrence, captures) in pattern.captures_iter(line).enumerate() {
                 let local_value = captures.get(1).unwrap().as_str().to_string();
                 let committed_value = captures.get(2).unwrap().as_str().to_string();
-                
+
                 decorations.push(Decoration::Inline {
                     line: line_num + 1,
+                    occurrence,
                     local_value: local_value.trim().to_string(),
                     committed_value: committed_value.trim().to_string(),
                 });
             }
         }
-        
+
         Ok(decorations)
     }
 }
@@ -57,13 +65,14 @@ mod tests {
     fn test_inline_parser() {
         let parser = InlineParser::new();
         let content = r#"let api_key = "sk-12345"; // @whiteout: load_from_env()"#;
-        
-        let decorations = parser.parse(content).unwrap();
+
+        let decorations = parser.parse(content, Some(Path::new("test.rs"))).unwrap();
         assert_eq!(decorations.len(), 1);
-        
+
         match &decorations[0] {
-            Decoration::Inline { line, local_value, committed_value } => {
+            Decoration::Inline { line, occurrence, local_value, committed_value } => {
                 assert_eq!(*line, 1);
+                assert_eq!(*occurrence, 0);
                 assert_eq!(local_value, r#"let api_key = "sk-12345";"#);
                 assert_eq!(committed_value, "load_from_env()");
             }
@@ -71,6 +80,34 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_multiple_decorations_same_line() {
+        let parser = InlineParser::new();
+        let content = r#"{"a": "sk-111"} // @whiteout: "ENV_A" {"b": "sk-222"} // @whiteout: "ENV_B""#;
+
+        let decorations = parser.parse(content, Some(Path::new("test.rs"))).unwrap();
+        assert_eq!(decorations.len(), 2);
+
+        match &decorations[0] {
+            Decoration::Inline { line, occurrence, local_value, committed_value } => {
+                assert_eq!(*line, 1);
+                assert_eq!(*occurrence, 0);
+                assert_eq!(local_value, r#"{"a": "sk-111"}"#);
+                assert_eq!(committed_value, r#""ENV_A""#);
+            }
+            _ => panic!("Expected inline decoration"),
+        }
+        match &decorations[1] {
+            Decoration::Inline { line, occurrence, local_value, committed_value } => {
+                assert_eq!(*line, 1);
+                assert_eq!(*occurrence, 1);
+                assert_eq!(local_value, r#"{"b": "sk-222"}"#);
+                assert_eq!(committed_value, r#""ENV_B""#);
+            }
+            _ => panic!("Expected inline decoration"),
+        }
+    }
+
     #[test]
     fn test_multiple_inline_decorations() {
         let parser = InlineParser::new();
@@ -79,8 +116,39 @@ let api_key = "sk-12345"; // @whiteout: load_from_env()
 let debug = true; // @whiteout: false
 let url = "http://localhost"; // @whiteout: "https://api.example.com"
 "#;
-        
-        let decorations = parser.parse(content).unwrap();
+
+        let decorations = parser.parse(content, Some(Path::new("test.rs"))).unwrap();
         assert_eq!(decorations.len(), 3);
     }
+
+    #[test]
+    fn test_css_comment_syntax() {
+        let parser = InlineParser::new();
+        let content = "color: red; /* @whiteout: blue */";
+
+        let decorations = parser.parse(content, Some(Path::new("style.css"))).unwrap();
+        assert_eq!(decorations.len(), 1);
+        match &decorations[0] {
+            Decoration::Inline { committed_value, .. } => assert_eq!(committed_value, "blue"),
+            _ => panic!("Expected inline decoration"),
+        }
+    }
+
+    #[test]
+    fn test_no_file_path_uses_default_syntax() {
+        let parser = InlineParser::new();
+        let content = r#"let api_key = "sk-12345"; // @whiteout: load_from_env()"#;
+
+        let decorations = parser.parse(content, None).unwrap();
+        assert_eq!(decorations.len(), 1);
+    }
+
+    #[test]
+    fn test_lines_without_marker_are_skipped_without_matching() {
+        let parser = InlineParser::new();
+        let content = "let api_key = \"sk-12345\";\nlet url = \"http://localhost\";";
+
+        let decorations = parser.parse(content, Some(Path::new("test.rs"))).unwrap();
+        assert!(decorations.is_empty());
+    }
 }
\ No newline at end of file
@@ -1,12 +1,17 @@
 pub mod apply;
+pub mod audit;
 pub mod block;
+pub mod comment_syntax;
+pub mod handler;
 pub mod inline;
 pub mod partial;
 pub mod simple;
 pub mod types;
 
 use anyhow::Result;
-pub use types::{Decoration, PartialReplacement};
+use std::path::Path;
+
+pub use types::{Decoration, PartialAlternative, PartialReplacement};
 
 pub struct Parser {
     inline_parser: inline::InlineParser,
@@ -25,31 +30,42 @@ impl Parser {
         }
     }
 
-    pub fn parse(&self, content: &str) -> Result<Vec<Decoration>> {
+    /// Parses every decoration form found in `content`. `file_path`'s
+    /// extension selects the comment syntax inline decorations look for
+    /// (see [`comment_syntax`]); pass `None` to use the default `//`/`#`/`--`
+    /// alternation.
+    pub fn parse(&self, content: &str, file_path: Option<&Path>) -> Result<Vec<Decoration>> {
         let mut decorations = Vec::new();
-        
+
         // Parse simple @whiteout decorations first
-        decorations.extend(self.simple_parser.parse(content)?);
-        
+        decorations.extend(self.simple_parser.parse(content, file_path)?);
+
         // Parse inline decorations
-        decorations.extend(self.inline_parser.parse(content)?);
-        
+        decorations.extend(self.inline_parser.parse(content, file_path)?);
+
         // Parse block decorations
-        decorations.extend(self.block_parser.parse(content)?);
-        
+        decorations.extend(self.block_parser.parse(content, file_path)?);
+
         // Parse partial replacements
         decorations.extend(self.partial_parser.parse(content)?);
-        
+
         Ok(decorations)
     }
 
+    /// Applies `decorations` to `content`. `file_path`'s extension selects
+    /// the comment token used to (re-)emit inline `@whiteout:` markers (see
+    /// [`comment_syntax::comment_token_for`]); pass `None` to use `//`.
+    /// `active_profile` selects which alternative a multi-environment
+    /// `Partial` replacement smudges in as; see [`apply::apply_decorations`].
     pub fn apply_decorations(
         &self,
         content: &str,
         decorations: &[Decoration],
         use_local: bool,
+        file_path: Option<&Path>,
+        active_profile: Option<&str>,
     ) -> String {
-        apply::apply_decorations(content, decorations, use_local)
+        apply::apply_decorations(content, decorations, use_local, file_path, active_profile)
     }
 }
 
@@ -67,7 +83,7 @@ mod tests {
     fn test_parse_inline() -> Result<()> {
         let parser = Parser::new();
         let content = r#"let api_key = "sk-12345"; // @whiteout: "ENV_VAR""#;
-        let decorations = parser.parse(content)?;
+        let decorations = parser.parse(content, Some(Path::new("test.rs")))?;
         
         assert_eq!(decorations.len(), 1);
         match &decorations[0] {
@@ -90,7 +106,7 @@ const DEBUG = true;
 // @whiteout-end
 const DEBUG = false;"#;
         
-        let decorations = parser.parse(content)?;
+        let decorations = parser.parse(content, Some(Path::new("test.rs")))?;
         assert_eq!(decorations.len(), 1);
         
         match &decorations[0] {
@@ -110,8 +126,8 @@ const DEBUG = false;"#;
         
         // Test inline decoration - preserves marker for smudge
         let content = r#"let api_key = "sk-12345"; // @whiteout: "REDACTED""#;
-        let decorations = parser.parse(content)?;
-        let cleaned = parser.apply_decorations(content, &decorations, false);
+        let decorations = parser.parse(content, Some(Path::new("test.rs")))?;
+        let cleaned = parser.apply_decorations(content, &decorations, false, Some(Path::new("test.rs")), None);
         assert_eq!(cleaned, "\"REDACTED\" // @whiteout: \"REDACTED\"");
         assert!(cleaned.contains("@whiteout"));  // Marker is preserved
         assert!(!cleaned.contains("sk-12345"));  // Secret is removed
@@ -123,8 +139,8 @@ const DEBUG = true;
 // @whiteout-end
 const DEBUG = false;
 code after"#;
-        let decorations = parser.parse(content)?;
-        let cleaned = parser.apply_decorations(content, &decorations, false);
+        let decorations = parser.parse(content, Some(Path::new("test.rs")))?;
+        let cleaned = parser.apply_decorations(content, &decorations, false, Some(Path::new("test.rs")), None);
         assert!(cleaned.contains("code before"));
         assert!(cleaned.contains("// @whiteout-start"));  // Marker preserved
         assert!(cleaned.contains("// @whiteout-end"));    // Marker preserved  
@@ -143,10 +159,11 @@ code after"#;
         let content = r#""REDACTED" // @whiteout: "REDACTED""#;
         let decorations = vec![Decoration::Inline {
             line: 1,
+            occurrence: 0,
             local_value: r#"let api_key = "sk-12345";"#.to_string(),
             committed_value: "\"REDACTED\"".to_string(),
         }];
-        let smudged = parser.apply_decorations(content, &decorations, true);
+        let smudged = parser.apply_decorations(content, &decorations, true, Some(Path::new("test.rs")), None);
         assert!(smudged.contains("sk-12345"));
         assert!(smudged.contains("@whiteout"));
         
@@ -164,7 +181,7 @@ const SECRET = "value";
 // Missing @whiteout-end
 const OTHER = "data";
 "#;
-        let decorations = parser.parse(content)?;
+        let decorations = parser.parse(content, Some(Path::new("test.rs")))?;
         // Should not find any decorations since block is incomplete
         if !decorations.is_empty() {
             eprintln!("Found {} decorations:", decorations.len());
@@ -175,7 +192,7 @@ const OTHER = "data";
         assert_eq!(decorations.len(), 0);
         
         // When no decorations, apply_decorations should return content unchanged
-        let result = parser.apply_decorations(content, &decorations, false);
+        let result = parser.apply_decorations(content, &decorations, false, Some(Path::new("test.rs")), None);
         assert_eq!(result, content);
         
         Ok(())
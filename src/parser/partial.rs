@@ -1,18 +1,149 @@
+use std::str::FromStr;
+
 use anyhow::Result;
 use once_cell::sync::Lazy;
 use regex::Regex;
 
-use super::{Decoration, PartialReplacement};
+use super::{Decoration, PartialAlternative, PartialReplacement};
+use crate::error::ParseError;
 
-// Static regex compilation for performance
+// Static regex compilation for performance. Captures everything between the
+// brackets as one group; `PartialParser::parse` splits it on `||` itself so
+// it can support any number of alternatives, not just a local/committed pair.
 static PATTERN: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"\[\[([^|]+)\|\|([^\]]+)\]\]").expect("Failed to compile pattern")
+    Regex::new(r"\[\[([^\[\]]+)\]\]").expect("Failed to compile pattern")
 });
 
 static DECORATOR_PATTERN: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r"//\s*@whiteout-partial").expect("Failed to compile decorator pattern")
 });
 
+// Splits an optional `name=` prefix off one `||`-separated alternative, e.g.
+// `prod=api.example.com` -> (`prod`, `api.example.com`). Always matches
+// (the whole thing falls into group 2 when there's no `name=` prefix), so
+// unnamed alternatives -- including every pre-existing `[[local||committed]]`
+// pair -- parse exactly as before.
+static ALT_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(?:([A-Za-z_][A-Za-z0-9_]*)=)?(.*)$").expect("Failed to compile alternative pattern")
+});
+
+// Matches an optional `:type` (or `:ts(format)`) suffix on the committed
+// side of a `[[local||committed]]` pair, e.g. `443:int` or `prod.com:ts(%Y)`.
+// The greedy `.*` for `value` backtracks as little as possible, so this
+// always splits on the *last* colon-introduced suffix in the group.
+static CONVERSION_SUFFIX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(?P<value>.*):(?P<kind>int|float|bool|bytes|string|ts(?:\((?P<fmt>[^)]*)\))?)$")
+        .expect("Failed to compile conversion suffix pattern")
+});
+
+/// The expected type declared for a `[[local||committed:type]]` replacement.
+/// Both halves are validated against this before the replacement is emitted,
+/// so a committed value that doesn't satisfy it fails at parse time instead
+/// of silently breaking whatever the local value was checked against.
+#[derive(Debug, Clone, PartialEq)]
+enum Conversion {
+    String,
+    Bytes,
+    Integer,
+    Float,
+    Boolean,
+    Timestamp(Option<String>),
+}
+
+impl Conversion {
+    /// Splits a trailing `:type` suffix off `committed_raw`, returning the
+    /// bare value and the conversion it declared. Defaults to `String` (no
+    /// splitting) when the suffix doesn't match a known conversion name, so
+    /// undecorated pairs keep behaving exactly as before.
+    fn parse_suffix(committed_raw: &str) -> (String, Conversion) {
+        let Some(captures) = CONVERSION_SUFFIX.captures(committed_raw) else {
+            return (committed_raw.to_string(), Conversion::String);
+        };
+
+        let value = captures.name("value").unwrap().as_str().to_string();
+        let conversion = match captures.name("kind").unwrap().as_str() {
+            "int" => Conversion::Integer,
+            "float" => Conversion::Float,
+            "bool" => Conversion::Boolean,
+            "bytes" => Conversion::Bytes,
+            "string" => Conversion::String,
+            kind if kind.starts_with("ts") => {
+                Conversion::Timestamp(captures.name("fmt").map(|m| m.as_str().to_string()))
+            }
+            _ => Conversion::String,
+        };
+
+        (value, conversion)
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Conversion::String => "string",
+            Conversion::Bytes => "bytes",
+            Conversion::Integer => "int",
+            Conversion::Float => "float",
+            Conversion::Boolean => "bool",
+            Conversion::Timestamp(_) => "ts",
+        }
+    }
+
+    /// Validates that `value` actually parses as this conversion, returning
+    /// a line-pointing `ParseError` describing which side (`local` or
+    /// `committed`) failed and why.
+    fn validate(&self, side: &str, value: &str, line: usize) -> Result<(), ParseError> {
+        match self {
+            Conversion::String | Conversion::Bytes => Ok(()),
+            Conversion::Integer => i64::from_str(value).map(|_| ()).map_err(|_| {
+                ParseError::InvalidSyntax {
+                    line,
+                    message: format!(
+                        "{} value '{}' is not a valid integer (declared :{})",
+                        side,
+                        value,
+                        self.name()
+                    ),
+                }
+            }),
+            Conversion::Float => f64::from_str(value).map(|_| ()).map_err(|_| {
+                ParseError::InvalidSyntax {
+                    line,
+                    message: format!(
+                        "{} value '{}' is not a valid float (declared :{})",
+                        side,
+                        value,
+                        self.name()
+                    ),
+                }
+            }),
+            Conversion::Boolean => match value {
+                "true" | "false" => Ok(()),
+                _ => Err(ParseError::InvalidSyntax {
+                    line,
+                    message: format!(
+                        "{} value '{}' is not a valid boolean, expected 'true' or 'false' (declared :{})",
+                        side,
+                        value,
+                        self.name()
+                    ),
+                }),
+            },
+            Conversion::Timestamp(format) => {
+                let format = format.as_deref().unwrap_or("%Y-%m-%dT%H:%M:%S");
+                chrono::NaiveDateTime::parse_from_str(value, format)
+                    .map(|_| ())
+                    .or_else(|_| chrono::NaiveDate::parse_from_str(value, format).map(|_| ()))
+                    .map_err(|_| ParseError::InvalidSyntax {
+                        line,
+                        message: format!(
+                            "{} value '{}' does not match timestamp format '{}' (declared :ts)",
+                            side, value, format
+                        ),
+                    })
+            }
+        }
+    }
+}
+
 pub struct PartialParser;
 
 impl Default for PartialParser {
@@ -26,33 +157,83 @@ impl PartialParser {
         // Force lazy static initialization
         let _ = &*PATTERN;
         let _ = &*DECORATOR_PATTERN;
+        let _ = &*ALT_PATTERN;
         Self
     }
 
     pub fn parse(&self, content: &str) -> Result<Vec<Decoration>> {
         let mut decorations = Vec::new();
-        
+
         for (line_num, line) in content.lines().enumerate() {
             // Only process lines that have the @whiteout-partial decorator
             if !DECORATOR_PATTERN.is_match(line) {
                 continue;
             }
-            
+
             let mut replacements = Vec::new();
-            
+
             for capture in PATTERN.captures_iter(line) {
                 let match_pos = capture.get(0).unwrap();
-                let local_value = capture.get(1).unwrap().as_str().to_string();
-                let committed_value = capture.get(2).unwrap().as_str().to_string();
-                
+                let inner = capture.get(1).unwrap().as_str();
+
+                // Need at least two alternatives -- `[[single]]` with no
+                // `||` isn't a replacement, it's just a line that happens
+                // to contain a bracketed word.
+                let raw_parts: Vec<&str> = inner.split("||").collect();
+                if raw_parts.len() < 2 {
+                    continue;
+                }
+
+                let parts: Vec<(Option<String>, &str)> = raw_parts
+                    .iter()
+                    .map(|part| match ALT_PATTERN.captures(part) {
+                        Some(captures) => (
+                            captures.get(1).map(|m| m.as_str().to_string()),
+                            captures.get(2).unwrap().as_str(),
+                        ),
+                        None => (None, *part),
+                    })
+                    .collect();
+
+                let is_legacy_pair = parts.len() == 2 && parts.iter().all(|(name, _)| name.is_none());
+
+                let alternatives = if is_legacy_pair {
+                    // The original two-alternative form: only the
+                    // committed (second) side's `:type` suffix is parsed,
+                    // and that conversion validates *both* sides, exactly
+                    // as it always has.
+                    let local_value = parts[0].1.to_string();
+                    let (committed_value, conversion) = Conversion::parse_suffix(parts[1].1);
+
+                    conversion.validate("local", &local_value, line_num + 1)?;
+                    conversion.validate("committed", &committed_value, line_num + 1)?;
+
+                    vec![
+                        PartialAlternative { name: None, value: local_value },
+                        PartialAlternative { name: None, value: committed_value },
+                    ]
+                } else {
+                    // Multi-environment profile form: every alternative
+                    // carries its own independent `:type` suffix (if any),
+                    // validated under its own name or positional label.
+                    let total = parts.len();
+                    let mut alternatives = Vec::with_capacity(total);
+                    for (index, (name, raw_value)) in parts.into_iter().enumerate() {
+                        let (value, conversion) = Conversion::parse_suffix(raw_value);
+                        let label = name.clone().unwrap_or_else(|| default_side_label(index, total));
+                        conversion.validate(&label, &value, line_num + 1)?;
+                        alternatives.push(PartialAlternative { name, value });
+                    }
+                    alternatives
+                };
+
                 replacements.push(PartialReplacement {
                     start: match_pos.start(),
                     end: match_pos.end(),
-                    local_value,
-                    committed_value,
+                    alternatives,
                 });
             }
-            
+
             if !replacements.is_empty() {
                 decorations.push(Decoration::Partial {
                     line: line_num + 1,
@@ -60,11 +241,24 @@ impl PartialParser {
                 });
             }
         }
-        
+
         Ok(decorations)
     }
 }
 
+/// Labels an unnamed alternative by position when there's no explicit
+/// `name=` to use instead: the first is `local`, the last is `committed`,
+/// anything in between is `alternative N` (1-based).
+fn default_side_label(index: usize, total: usize) -> String {
+    if index == 0 {
+        "local".to_string()
+    } else if index == total - 1 {
+        "committed".to_string()
+    } else {
+        format!("alternative {}", index + 1)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -81,8 +275,8 @@ mod tests {
             Decoration::Partial { line, replacements } => {
                 assert_eq!(*line, 1);
                 assert_eq!(replacements.len(), 1);
-                assert_eq!(replacements[0].local_value, "localhost:8080");
-                assert_eq!(replacements[0].committed_value, "api.example.com");
+                assert_eq!(replacements[0].local_value(), "localhost:8080");
+                assert_eq!(replacements[0].committed_value(), "api.example.com");
             }
             _ => panic!("Expected partial decoration"),
         }
@@ -128,4 +322,127 @@ let pattern = "[[a-z]||[0-9]]"; // Regex pattern
         let decorations = parser.parse(content).unwrap();
         assert_eq!(decorations.len(), 0);
     }
+
+    #[test]
+    fn test_typed_replacement_accepts_valid_values() {
+        let parser = PartialParser::new();
+        let content = r#"let port = [[8080||443:int]]; // @whiteout-partial"#;
+
+        let decorations = parser.parse(content).unwrap();
+        assert_eq!(decorations.len(), 1);
+
+        match &decorations[0] {
+            Decoration::Partial { replacements, .. } => {
+                assert_eq!(replacements[0].local_value(), "8080");
+                assert_eq!(replacements[0].committed_value(), "443");
+            }
+            _ => panic!("Expected partial decoration"),
+        }
+    }
+
+    #[test]
+    fn test_typed_replacement_rejects_invalid_committed_value() {
+        let parser = PartialParser::new();
+        let content = r#"let host = [[dev.local||api.example.com:int]]; // @whiteout-partial"#;
+
+        let error = parser.parse(content).unwrap_err();
+        assert!(error.to_string().contains("committed"));
+        assert!(error.to_string().contains("integer"));
+    }
+
+    #[test]
+    fn test_typed_replacement_rejects_invalid_local_value() {
+        let parser = PartialParser::new();
+        let content = r#"let enabled = [[maybe||true:bool]]; // @whiteout-partial"#;
+
+        let error = parser.parse(content).unwrap_err();
+        assert!(error.to_string().contains("local"));
+        assert!(error.to_string().contains("boolean"));
+    }
+
+    #[test]
+    fn test_typed_replacement_with_timestamp_format() {
+        let parser = PartialParser::new();
+        let content = r#"let date = [[2024-01-01||2024-06-15:ts(%Y-%m-%d)]]; // @whiteout-partial"#;
+
+        let decorations = parser.parse(content).unwrap();
+        assert_eq!(decorations.len(), 1);
+    }
+
+    #[test]
+    fn test_typed_replacement_defaults_to_string_without_suffix() {
+        let parser = PartialParser::new();
+        // No `:type` suffix anywhere, so nothing gets validated -- existing
+        // untyped pairs keep working exactly as before.
+        let content = r#"let config = { host: "[[dev.local||prod.com]]", port: [[8080||443]] }; // @whiteout-partial"#;
+
+        let decorations = parser.parse(content).unwrap();
+        assert_eq!(decorations.len(), 1);
+    }
+
+    #[test]
+    fn test_named_profile_replacement_parses_all_alternatives() {
+        let parser = PartialParser::new();
+        let content = r#"let host = [[dev=localhost||staging=staging.example.com||prod=api.example.com]]; // @whiteout-partial"#;
+
+        let decorations = parser.parse(content).unwrap();
+        assert_eq!(decorations.len(), 1);
+
+        match &decorations[0] {
+            Decoration::Partial { replacements, .. } => {
+                assert_eq!(replacements.len(), 1);
+                assert!(!replacements[0].is_legacy_pair());
+                let alternatives = &replacements[0].alternatives;
+                assert_eq!(alternatives.len(), 3);
+                assert_eq!(alternatives[0].name.as_deref(), Some("dev"));
+                assert_eq!(alternatives[0].value, "localhost");
+                assert_eq!(alternatives[2].name.as_deref(), Some("prod"));
+                assert_eq!(alternatives[2].value, "api.example.com");
+            }
+            _ => panic!("Expected partial decoration"),
+        }
+    }
+
+    #[test]
+    fn test_unnamed_three_way_replacement_is_not_legacy_pair() {
+        let parser = PartialParser::new();
+        let content = r#"let port = [[8080||8081||8082]]; // @whiteout-partial"#;
+
+        let decorations = parser.parse(content).unwrap();
+        match &decorations[0] {
+            Decoration::Partial { replacements, .. } => {
+                assert!(!replacements[0].is_legacy_pair());
+                assert_eq!(replacements[0].alternatives.len(), 3);
+            }
+            _ => panic!("Expected partial decoration"),
+        }
+    }
+
+    #[test]
+    fn test_named_profile_rejects_invalid_typed_alternative() {
+        let parser = PartialParser::new();
+        let content = r#"let port = [[dev=8080||prod=notanumber:int]]; // @whiteout-partial"#;
+
+        let error = parser.parse(content).unwrap_err();
+        assert!(error.to_string().contains("prod"));
+        assert!(error.to_string().contains("integer"));
+    }
+
+    #[test]
+    fn test_select_defaults_to_first_alternative_when_profile_unset() {
+        let parser = PartialParser::new();
+        let content = r#"let host = [[dev=localhost||prod=api.example.com]]; // @whiteout-partial"#;
+
+        let decorations = parser.parse(content).unwrap();
+        match &decorations[0] {
+            Decoration::Partial { replacements, .. } => {
+                assert_eq!(replacements[0].select(None).unwrap().value, "localhost");
+                assert_eq!(replacements[0].select(Some("prod")).unwrap().value, "api.example.com");
+                // An unknown profile name doesn't silently fall back -- the
+                // caller is responsible for treating this as an error.
+                assert!(replacements[0].select(Some("nope")).is_none());
+            }
+            _ => panic!("Expected partial decoration"),
+        }
+    }
 }
\ No newline at end of file
@@ -1,15 +1,9 @@
 use anyhow::Result;
-use once_cell::sync::Lazy;
-use regex::Regex;
+use std::path::Path;
 
+use super::comment_syntax;
 use super::Decoration;
 
-// Static regex compilation for performance
-// Match lines that have @whiteout as a standalone decoration (not part of other text)
-static PATTERN: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"(?m)^\s*(?://|#|--|/\*|\*)\s*@whiteout\s*(?:\*/)?$").expect("Failed to compile pattern")
-});
-
 /// Parser for simple @whiteout lines that hide entire lines or blocks
 pub struct SimpleParser;
 
@@ -21,20 +15,25 @@ impl Default for SimpleParser {
 
 impl SimpleParser {
     pub fn new() -> Self {
-        // Force lazy static initialization
-        let _ = &*PATTERN;
         Self
     }
 
-    pub fn parse(&self, content: &str) -> Result<Vec<Decoration>> {
+    /// Parses bare `@whiteout` lines. `file_path`'s extension selects which
+    /// comment forms are recognized as markers, the same registry
+    /// [`super::block::BlockParser`] uses; unknown extensions fall back to
+    /// the `//`/`#`/`--`/`/*`/`*` alternation.
+    pub fn parse(&self, content: &str, file_path: Option<&Path>) -> Result<Vec<Decoration>> {
+        let patterns = comment_syntax::simple_patterns_for(file_path);
+        let is_marker = |line: &str| patterns.iter().any(|p| p.is_match(line));
+
         let mut decorations = Vec::new();
         let lines: Vec<&str> = content.lines().collect();
         let mut i = 0;
-        
+
         while i < lines.len() {
             // Check if line matches pattern and is not escaped
             // Also skip @whiteout-start, @whiteout-end, @whiteout:, and @whiteout-partial patterns
-            if PATTERN.is_match(lines[i]) 
+            if is_marker(lines[i])
                 && !lines[i].contains(r"\@whiteout")
                 && !lines[i].contains("@whiteout-start")
                 && !lines[i].contains("@whiteout-end")
@@ -83,9 +82,9 @@ this stays visible
 
 normal again"#;
         
-        let decorations = parser.parse(content).unwrap();
+        let decorations = parser.parse(content, None).unwrap();
         assert_eq!(decorations.len(), 1);
-        
+
         match &decorations[0] {
             Decoration::Block { local_content, committed_content, .. } => {
                 assert_eq!(local_content, "this will be hidden");
@@ -1,8 +1,15 @@
+use serde::Serialize;
+
 /// Represents different types of code decorations
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum Decoration {
     Inline {
         line: usize,
+        /// Position among the `@whiteout:` markers found on `line`, left to
+        /// right, starting at 0. Most lines carry exactly one, but a
+        /// minified/one-line record (e.g. JSON, a table row) can carry
+        /// several independent redactions.
+        occurrence: usize,
         local_value: String,
         committed_value: String,
     },
@@ -18,11 +25,76 @@ pub enum Decoration {
     },
 }
 
-/// Represents a partial replacement within a string
-#[derive(Debug, Clone)]
+/// One alternative value of a `[[...]]` partial replacement, optionally
+/// named (e.g. the `prod` in `[[prod=api.example.com]]`). Unnamed
+/// alternatives are positional -- see [`PartialReplacement::is_legacy_pair`].
+#[derive(Debug, Clone, Serialize)]
+pub struct PartialAlternative {
+    pub name: Option<String>,
+    pub value: String,
+}
+
+impl PartialAlternative {
+    fn render(&self) -> String {
+        match &self.name {
+            Some(name) => format!("{}={}", name, self.value),
+            None => self.value.clone(),
+        }
+    }
+}
+
+/// Represents a partial replacement within a string. Two unnamed
+/// alternatives is the original `[[local||committed]]` form, which keeps
+/// the storage-backed secret-hiding treatment in `transform::clean`/
+/// `transform::smudge` (see [`Self::is_legacy_pair`]); three or more
+/// alternatives, or any named one, is the newer multi-environment profile
+/// form, where `clean` preserves the whole `[[...]]` list untouched and
+/// `smudge` just selects one alternative to emit.
+#[derive(Debug, Clone, Serialize)]
 pub struct PartialReplacement {
     pub start: usize,
     pub end: usize,
-    pub local_value: String,
-    pub committed_value: String,
+    pub alternatives: Vec<PartialAlternative>,
+}
+
+impl PartialReplacement {
+    /// True for the original two-alternative, unnamed `[[local||committed]]`
+    /// shape.
+    pub fn is_legacy_pair(&self) -> bool {
+        self.alternatives.len() == 2 && self.alternatives.iter().all(|alt| alt.name.is_none())
+    }
+
+    /// The legacy pair's local (first) value. Only meaningful when
+    /// [`Self::is_legacy_pair`] is true.
+    pub fn local_value(&self) -> &str {
+        &self.alternatives[0].value
+    }
+
+    /// The legacy pair's committed (second) value. Only meaningful when
+    /// [`Self::is_legacy_pair`] is true.
+    pub fn committed_value(&self) -> &str {
+        &self.alternatives[1].value
+    }
+
+    /// Selects the alternative named `profile`. `profile: None` always
+    /// resolves, to the first alternative -- the default when no
+    /// `WHITEOUT_PROFILE` is set. `Some(name)` that matches nothing returns
+    /// `None` rather than silently falling back: `transform::smudge` treats
+    /// that as an error instead of materializing some other environment's
+    /// value with no indication anything went wrong.
+    pub fn select(&self, profile: Option<&str>) -> Option<&PartialAlternative> {
+        match profile {
+            None => Some(&self.alternatives[0]),
+            Some(wanted) => self.alternatives.iter().find(|alt| alt.name.as_deref() == Some(wanted)),
+        }
+    }
+
+    /// Re-renders the full `[[alt||alt||...]]` structure, named
+    /// alternatives as `name=value`. This is what `clean` emits for the
+    /// multi-environment profile form, so a later smudge into any profile
+    /// can still reconstruct the right value.
+    pub fn render(&self) -> String {
+        let inner = self.alternatives.iter().map(PartialAlternative::render).collect::<Vec<_>>().join("||");
+        format!("[[{}]]", inner)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,92 @@
+//! Filesystem-free lexical path normalization.
+//!
+//! `Path::canonicalize` requires the target (or its parent) to already
+//! exist and produces platform-divergent output (a `\\?\` UNC prefix on
+//! Windows), which makes it unusable for validating a planned output file
+//! before its parent directory has been created. [`normalize`] instead
+//! resolves `.`/`..` purely lexically — the same approach `cargo` uses for
+//! paths that shouldn't touch the filesystem — so containment checks work
+//! identically on Unix and Windows and for files that don't exist yet.
+
+use std::path::{Component, Path, PathBuf};
+
+/// Lexically normalizes `path`: drops `.` components and resolves `..` by
+/// popping the preceding normal component. A `..` with nothing left to
+/// pop (at the root, or stacked after another unresolved `..`) is kept
+/// as-is rather than escaping the root — a normalized path never climbs
+/// above where it started. Prefix/root components (drive letters, UNC
+/// shares, a leading `/`) are preserved verbatim.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match result.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    result.pop();
+                }
+                Some(Component::Prefix(_)) | Some(Component::RootDir) => {
+                    // Already at the root; a `..` here would escape it.
+                }
+                Some(Component::ParentDir) | None => {
+                    result.push(component);
+                }
+                Some(Component::CurDir) => unreachable!("CurDir is never pushed into result"),
+            },
+            other => result.push(other),
+        }
+    }
+
+    result
+}
+
+/// Whether `path` (already normalized, e.g. via [`normalize`]) lies within
+/// `base` (also normalized) — a plain component-wise prefix check, kept as
+/// its own function since callers tend to normalize both sides first and
+/// it reads better named than a bare `starts_with`.
+pub fn is_contained_in(path: &Path, base: &Path) -> bool {
+    path.starts_with(base)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_drops_current_dir_components() {
+        assert_eq!(normalize(Path::new("./a/./b")), PathBuf::from("a/b"));
+    }
+
+    #[test]
+    fn test_normalize_resolves_parent_dir_against_prior_component() {
+        assert_eq!(normalize(Path::new("a/b/../c")), PathBuf::from("a/c"));
+    }
+
+    #[test]
+    fn test_normalize_never_escapes_absolute_root() {
+        assert_eq!(normalize(Path::new("/a/../../../b")), PathBuf::from("/b"));
+    }
+
+    #[test]
+    fn test_normalize_keeps_leading_parent_dir_on_relative_path() {
+        assert_eq!(normalize(Path::new("../a")), PathBuf::from("../a"));
+        assert_eq!(normalize(Path::new("../../a")), PathBuf::from("../../a"));
+    }
+
+    #[test]
+    fn test_normalize_handles_nonexistent_target_purely_lexically() {
+        // No filesystem access is involved, so this works even though
+        // `/nonexistent` doesn't exist.
+        assert_eq!(
+            normalize(Path::new("/nonexistent/base/../base/file.txt")),
+            PathBuf::from("/nonexistent/base/file.txt")
+        );
+    }
+
+    #[test]
+    fn test_is_contained_in() {
+        assert!(is_contained_in(Path::new("/base/src/main.rs"), Path::new("/base")));
+        assert!(!is_contained_in(Path::new("/other/main.rs"), Path::new("/base")));
+    }
+}
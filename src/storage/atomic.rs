@@ -1,4 +1,5 @@
 use anyhow::{Context, Result};
+use rand::Rng;
 use std::fs::{self, File, OpenOptions};
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
@@ -7,6 +8,19 @@ use std::time::Duration;
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
 
+/// Whether a failed rename was due to the temp file and target living on
+/// different filesystems (`EXDEV`), in which case a copy+remove fallback
+/// is needed since `fs::rename` can't cross filesystem boundaries.
+#[cfg(unix)]
+fn is_cross_device_error(err: &std::io::Error) -> bool {
+    err.raw_os_error() == Some(libc::EXDEV)
+}
+
+#[cfg(not(unix))]
+fn is_cross_device_error(_err: &std::io::Error) -> bool {
+    false
+}
+
 /// Atomic file operations to prevent TOCTOU race conditions
 pub struct AtomicFile {
     path: PathBuf,
@@ -21,52 +35,102 @@ impl AtomicFile {
         Ok(Self { path, temp_path })
     }
     
-    /// Generate a temporary file path for atomic operations
+    /// Generate a temporary file path for atomic operations. Lives in the
+    /// same directory as the target (required for the rename to be atomic
+    /// on the same filesystem) and carries a random suffix rather than just
+    /// the pid, so two concurrent writes to the same path from the same
+    /// process (e.g. parallel git smudge/clean invocations) never collide.
     fn temp_path(path: &Path) -> Result<PathBuf> {
         let file_name = path
             .file_name()
             .ok_or_else(|| anyhow::anyhow!("Invalid file path"))?;
-        
+
+        let suffix: u64 = rand::thread_rng().gen();
         let temp_name = format!(
-            ".{}.tmp.{}",
+            ".{}.tmp.{}.{:016x}",
             file_name.to_string_lossy(),
-            std::process::id()
+            std::process::id(),
+            suffix
         );
-        
+
         Ok(path.with_file_name(temp_name))
     }
-    
-    /// Atomically write content to file
+
+    /// The Unix mode to use for a freshly written file: the existing
+    /// target's mode if it has one, so re-cleaning a file never silently
+    /// widens or narrows its permissions, or `0o644` for a new file.
+    #[cfg(unix)]
+    fn mode_for_write(&self) -> u32 {
+        fs::metadata(&self.path)
+            .map(|metadata| metadata.permissions().mode())
+            .unwrap_or(0o644)
+    }
+
+    /// Atomically write content to file. Writes to a temp file in the same
+    /// directory, fsyncs it, then renames it into place, creating the
+    /// parent directory on demand and falling back to copy+remove when the
+    /// temp file and target live on different filesystems.
     pub fn write(&self, content: &[u8]) -> Result<()> {
-        // Write to temporary file first
+        #[cfg(unix)]
+        let mode = self.mode_for_write();
+
+        self.write_temp_with_retry(content)?;
+
+        #[cfg(unix)]
+        {
+            let mut permissions = fs::metadata(&self.temp_path)?.permissions();
+            permissions.set_mode(mode);
+            fs::set_permissions(&self.temp_path, permissions)?;
+        }
+
+        match fs::rename(&self.temp_path, &self.path) {
+            Ok(()) => Ok(()),
+            Err(e) if is_cross_device_error(&e) => {
+                fs::copy(&self.temp_path, &self.path)
+                    .context("Failed to copy temporary file across filesystems")?;
+                fs::remove_file(&self.temp_path)
+                    .context("Failed to remove temporary file after cross-device copy")?;
+                Ok(())
+            }
+            Err(e) => Err(e).context("Failed to atomically rename file"),
+        }
+    }
+
+    /// Writes the temp file, creating the target's parent directory and
+    /// retrying once if it didn't exist yet.
+    fn write_temp_with_retry(&self, content: &[u8]) -> Result<()> {
+        match self.write_temp(content) {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                let parent = self
+                    .path
+                    .parent()
+                    .ok_or_else(|| anyhow::anyhow!("Invalid file path: no parent directory"))?;
+                if parent.exists() {
+                    return Err(e);
+                }
+                fs::create_dir_all(parent).context("Failed to create parent directory")?;
+                self.write_temp(content)
+            }
+        }
+    }
+
+    fn write_temp(&self, content: &[u8]) -> Result<()> {
         let mut temp_file = OpenOptions::new()
             .write(true)
             .create(true)
             .truncate(true)
             .open(&self.temp_path)
             .context("Failed to create temporary file")?;
-        
+
         temp_file
             .write_all(content)
             .context("Failed to write to temporary file")?;
-        
+
         temp_file
             .sync_all()
             .context("Failed to sync temporary file")?;
-        
-        // Set permissions on Unix systems
-        #[cfg(unix)]
-        {
-            let metadata = fs::metadata(&self.temp_path)?;
-            let mut permissions = metadata.permissions();
-            permissions.set_mode(0o644); // Read/write for owner, read for others
-            fs::set_permissions(&self.temp_path, permissions)?;
-        }
-        
-        // Atomically rename temp file to target
-        fs::rename(&self.temp_path, &self.path)
-            .context("Failed to atomically rename file")?;
-        
+
         Ok(())
     }
     
@@ -177,21 +241,98 @@ pub mod lock {
     }
 }
 
-#[cfg(not(unix))]
+#[cfg(windows)]
 pub mod lock {
+    use anyhow::Result;
     use std::fs::File;
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Foundation::{GetLastError, ERROR_LOCK_VIOLATION, HANDLE};
+    use windows_sys::Win32::Storage::FileSystem::{
+        LockFileEx, UnlockFileEx, LOCKFILE_EXCLUSIVE_LOCK, LOCKFILE_FAIL_IMMEDIATELY,
+    };
+    use windows_sys::Win32::System::IO::OVERLAPPED;
+
+    pub struct FileLock {
+        file: File,
+    }
+
+    fn zeroed_overlapped() -> OVERLAPPED {
+        unsafe { std::mem::zeroed() }
+    }
+
+    impl FileLock {
+        pub fn acquire(file: File) -> Result<Self> {
+            let handle = file.as_raw_handle() as HANDLE;
+            let mut overlapped = zeroed_overlapped();
+
+            let acquired = unsafe {
+                LockFileEx(
+                    handle,
+                    LOCKFILE_EXCLUSIVE_LOCK,
+                    0,
+                    u32::MAX,
+                    u32::MAX,
+                    &mut overlapped,
+                )
+            };
+
+            if acquired == 0 {
+                anyhow::bail!("Failed to acquire file lock");
+            }
+
+            Ok(Self { file })
+        }
+
+        pub fn try_acquire(file: File) -> Result<Option<Self>> {
+            let handle = file.as_raw_handle() as HANDLE;
+            let mut overlapped = zeroed_overlapped();
+
+            let acquired = unsafe {
+                LockFileEx(
+                    handle,
+                    LOCKFILE_EXCLUSIVE_LOCK | LOCKFILE_FAIL_IMMEDIATELY,
+                    0,
+                    u32::MAX,
+                    u32::MAX,
+                    &mut overlapped,
+                )
+            };
+
+            if acquired != 0 {
+                Ok(Some(Self { file }))
+            } else if unsafe { GetLastError() } == ERROR_LOCK_VIOLATION {
+                Ok(None)
+            } else {
+                anyhow::bail!("Failed to try acquiring file lock");
+            }
+        }
+    }
+
+    impl Drop for FileLock {
+        fn drop(&mut self) {
+            let handle = self.file.as_raw_handle() as HANDLE;
+            let mut overlapped = zeroed_overlapped();
+            unsafe {
+                UnlockFileEx(handle, 0, u32::MAX, u32::MAX, &mut overlapped);
+            }
+        }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+pub mod lock {
     use anyhow::Result;
-    
+    use std::fs::File;
+
     pub struct FileLock {
         _file: File,
     }
-    
+
     impl FileLock {
         pub fn acquire(file: File) -> Result<Self> {
-            // Windows file locking would go here
             Ok(Self { _file: file })
         }
-        
+
         pub fn try_acquire(file: File) -> Result<Option<Self>> {
             Ok(Some(Self { _file: file }))
         }
@@ -290,12 +431,14 @@ mod tests {
         let file1 = File::open(&file_path)?;
         let lock1 = lock::FileLock::acquire(file1)?;
         
-        // Try to acquire another lock (should fail or block)
+        // Try to acquire another lock while the first is still held; this
+        // must return None on every supported platform (unix flock and
+        // Windows LockFileEx both enforce real exclusivity).
         let file2 = File::open(&file_path)?;
         let lock2 = lock::FileLock::try_acquire(file2)?;
-        
-        assert!(lock2.is_none() || cfg!(not(unix)));
-        
+
+        assert!(lock2.is_none());
+
         drop(lock1);
         Ok(())
     }
@@ -0,0 +1,31 @@
+use crate::error::StorageError;
+use std::fmt;
+use std::path::Path;
+
+/// Opaque handle to a stored blob. Concrete `Storage` implementors decide
+/// how to interpret it (a `"path::key"` string for the file-backed store
+/// today; an object key for a future remote backend).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BlobRef(pub String);
+
+impl fmt::Display for BlobRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Storage behind a trait, so the encrypted secret vault doesn't have to
+/// live on the local filesystem: a team can swap in a git-tracked shared
+/// blob or an object-store backend without touching `clean`/`smudge`.
+pub trait Storage: fmt::Debug + Send + Sync {
+    /// Builds the `BlobRef` this backend uses to address `key` for `file_path`.
+    fn blob_ref(&self, file_path: &Path, key: &str) -> BlobRef;
+
+    fn blob_fetch(&self, blob: &BlobRef) -> Result<Vec<u8>, StorageError>;
+
+    fn blob_put(&self, blob: &BlobRef, data: &[u8]) -> Result<(), StorageError>;
+
+    fn list(&self) -> Result<Vec<BlobRef>, StorageError>;
+
+    fn remove(&self, blob: &BlobRef) -> Result<(), StorageError>;
+}
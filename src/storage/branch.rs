@@ -0,0 +1,310 @@
+//! Per-branch snapshots of `LocalStorage`'s entries, so `whiteout sync
+//! branch` can three-way-merge local secrets between Git branches the way
+//! `bundle` merges entries between machines. Unlike `bundle`, which keys by
+//! `storage_key` (file path + decoration identity), a branch merge keys by
+//! decoration identity alone -- `StorageEntry.key`, the content hash from
+//! `content_key` -- so a file that got renamed on one branch still
+//! reconciles against its counterpart on the other.
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{LocalStorage, StorageEntry};
+
+/// What happened to a decoration identity when merging the current branch
+/// against a target branch's last snapshot, relative to their common
+/// ancestor snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncAction {
+    /// Present on the target branch but not ours; pulled in.
+    Added,
+    /// Changed on the target branch since the ancestor snapshot, with no
+    /// conflicting change on ours; the target's value was pulled in.
+    Updated,
+    /// Unchanged since the ancestor, or changed on only our side -- kept
+    /// as-is.
+    Unchanged,
+    /// Changed differently on both sides since the ancestor snapshot; left
+    /// as a conflict marker for the user to resolve by hand.
+    Conflict,
+}
+
+#[derive(Debug, Clone)]
+pub struct SyncPlanEntry {
+    pub identity: String,
+    pub action: SyncAction,
+}
+
+fn sanitize_branch_name(branch: &str) -> String {
+    branch.replace(['/', '\\'], "__")
+}
+
+/// Path of `branch`'s snapshot file under `root_path/.whiteout/branches/`.
+pub fn snapshot_path(root_path: &Path, branch: &str) -> PathBuf {
+    root_path
+        .join(".whiteout")
+        .join("branches")
+        .join(format!("{}.toml", sanitize_branch_name(branch)))
+}
+
+fn by_decoration_identity(entries: HashMap<String, StorageEntry>) -> HashMap<String, StorageEntry> {
+    entries.into_values().map(|entry| (entry.key.clone(), entry)).collect()
+}
+
+/// `storage`'s current entries, re-keyed by decoration identity instead of
+/// `storage_key`, ready to compare against another branch's snapshot.
+pub fn current_snapshot(storage: &LocalStorage) -> Result<HashMap<String, StorageEntry>> {
+    Ok(by_decoration_identity(storage.export_entries()?))
+}
+
+/// Writes `storage`'s current entries to `branch`'s snapshot file. Call this
+/// after a successful sync so the next sync against `branch` has an
+/// accurate common ancestor.
+pub fn save_snapshot(storage: &LocalStorage, root_path: &Path, branch: &str) -> Result<()> {
+    let by_identity = current_snapshot(storage)?;
+    let path = snapshot_path(root_path, branch);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("Failed to create .whiteout/branches directory")?;
+    }
+    let content =
+        toml::to_string_pretty(&by_identity).context("Failed to serialize branch snapshot")?;
+    fs::write(&path, content)
+        .with_context(|| format!("Failed to write branch snapshot to {}", path.display()))
+}
+
+/// Loads `branch`'s snapshot, or an empty map if it was never saved (e.g.
+/// the first sync against that branch, which has no ancestor yet).
+pub fn load_snapshot(root_path: &Path, branch: &str) -> Result<HashMap<String, StorageEntry>> {
+    let path = snapshot_path(root_path, branch);
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read branch snapshot {}", path.display()))?;
+    toml::from_str(&content).context("Failed to parse branch snapshot")
+}
+
+/// Three-way merges `ours` (the current branch's live entries) against
+/// `theirs` (the target branch's last snapshot), using `ancestor` (the
+/// current branch's own last snapshot, the common base) to tell which side
+/// actually changed something. Mirrors the usual VCS merge rules: only one
+/// side changed since the base -> take it; both changed to the same value
+/// -> take it; both changed differently -> conflict.
+pub fn plan_merge(
+    ancestor: &HashMap<String, StorageEntry>,
+    ours: &HashMap<String, StorageEntry>,
+    theirs: &HashMap<String, StorageEntry>,
+) -> Vec<SyncPlanEntry> {
+    let mut identities: Vec<&String> = ancestor.keys().chain(ours.keys()).chain(theirs.keys()).collect();
+    identities.sort();
+    identities.dedup();
+
+    identities
+        .into_iter()
+        .map(|identity| {
+            let base = ancestor.get(identity).map(|e| &e.value);
+            let local = ours.get(identity).map(|e| &e.value);
+            let remote = theirs.get(identity).map(|e| &e.value);
+
+            let action = if local == remote {
+                SyncAction::Unchanged
+            } else if local == base {
+                if local.is_none() {
+                    SyncAction::Added
+                } else {
+                    SyncAction::Updated
+                }
+            } else if remote == base {
+                SyncAction::Unchanged
+            } else {
+                SyncAction::Conflict
+            };
+
+            SyncPlanEntry { identity: identity.clone(), action }
+        })
+        .collect()
+}
+
+/// Applies `plan` to `storage`: writes `theirs`' value for every `Added`/
+/// `Updated` identity (removing ours if `theirs` has since deleted it), and
+/// writes a conflict-marked value for every `Conflict` identity so the user
+/// can find and resolve it by hand. Returns `(merged, conflicts)`.
+pub fn apply_merge(
+    storage: &LocalStorage,
+    ours: &HashMap<String, StorageEntry>,
+    theirs: &HashMap<String, StorageEntry>,
+    plan: &[SyncPlanEntry],
+) -> Result<(usize, usize)> {
+    let mut merged = 0;
+    let mut conflicts = 0;
+
+    for plan_entry in plan {
+        match plan_entry.action {
+            SyncAction::Unchanged => {}
+            SyncAction::Added | SyncAction::Updated => match theirs.get(&plan_entry.identity) {
+                Some(entry) => {
+                    let file_path = ours
+                        .get(&plan_entry.identity)
+                        .map(|e| e.file_path.clone())
+                        .unwrap_or_else(|| entry.file_path.clone());
+                    storage.store_value(&file_path, &entry.key, &entry.value)?;
+                    merged += 1;
+                }
+                None => {
+                    // The target branch deleted this identity since the ancestor
+                    // snapshot; mirror the deletion locally.
+                    if let Some(existing) = ours.get(&plan_entry.identity) {
+                        storage.remove_value(&existing.file_path, &existing.key)?;
+                        merged += 1;
+                    }
+                }
+            },
+            SyncAction::Conflict => {
+                let local_value = ours.get(&plan_entry.identity).map(|e| e.value.as_str()).unwrap_or("");
+                let remote_value = theirs.get(&plan_entry.identity).map(|e| e.value.as_str()).unwrap_or("");
+                let file_path = ours
+                    .get(&plan_entry.identity)
+                    .or_else(|| theirs.get(&plan_entry.identity))
+                    .map(|e| e.file_path.clone())
+                    .unwrap_or_else(|| PathBuf::from(&plan_entry.identity));
+
+                let marked =
+                    format!("<<<<<<< local\n{local_value}\n=======\n{remote_value}\n>>>>>>> incoming\n");
+                storage.store_value(&file_path, &plan_entry.identity, &marked)?;
+                conflicts += 1;
+            }
+        }
+    }
+
+    Ok((merged, conflicts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn fresh_storage(temp_dir: &TempDir) -> Result<LocalStorage> {
+        LocalStorage::init(temp_dir.path())?;
+        LocalStorage::new(temp_dir.path())
+    }
+
+    fn entry(file_path: &str, identity: &str, value: &str) -> StorageEntry {
+        StorageEntry {
+            file_path: PathBuf::from(file_path),
+            key: identity.to_string(),
+            value: value.to_string(),
+            encrypted: false,
+            compressed: false,
+            timestamp: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_only_remote_changed_is_taken() {
+        let ancestor = HashMap::from([("inline::aaa".to_string(), entry("a.rs", "inline::aaa", "old"))]);
+        let ours = ancestor.clone();
+        let theirs = HashMap::from([("inline::aaa".to_string(), entry("a.rs", "inline::aaa", "new"))]);
+
+        let plan = plan_merge(&ancestor, &ours, &theirs);
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan[0].action, SyncAction::Updated);
+    }
+
+    #[test]
+    fn test_only_local_changed_is_kept() {
+        let ancestor = HashMap::from([("inline::aaa".to_string(), entry("a.rs", "inline::aaa", "old"))]);
+        let ours = HashMap::from([("inline::aaa".to_string(), entry("a.rs", "inline::aaa", "mine"))]);
+        let theirs = ancestor.clone();
+
+        let plan = plan_merge(&ancestor, &ours, &theirs);
+        assert_eq!(plan[0].action, SyncAction::Unchanged);
+    }
+
+    #[test]
+    fn test_both_changed_to_same_value_is_unchanged() {
+        let ancestor = HashMap::from([("inline::aaa".to_string(), entry("a.rs", "inline::aaa", "old"))]);
+        let ours = HashMap::from([("inline::aaa".to_string(), entry("a.rs", "inline::aaa", "same"))]);
+        let theirs = HashMap::from([("inline::aaa".to_string(), entry("a.rs", "inline::aaa", "same"))]);
+
+        let plan = plan_merge(&ancestor, &ours, &theirs);
+        assert_eq!(plan[0].action, SyncAction::Unchanged);
+    }
+
+    #[test]
+    fn test_both_changed_differently_is_a_conflict() {
+        let ancestor = HashMap::from([("inline::aaa".to_string(), entry("a.rs", "inline::aaa", "old"))]);
+        let ours = HashMap::from([("inline::aaa".to_string(), entry("a.rs", "inline::aaa", "mine"))]);
+        let theirs = HashMap::from([("inline::aaa".to_string(), entry("a.rs", "inline::aaa", "theirs"))]);
+
+        let plan = plan_merge(&ancestor, &ours, &theirs);
+        assert_eq!(plan[0].action, SyncAction::Conflict);
+    }
+
+    #[test]
+    fn test_renamed_file_still_matches_by_decoration_identity() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let storage = fresh_storage(&temp_dir)?;
+        storage.store_value(Path::new("new_name.rs"), "inline::aaa", "mine")?;
+        let ours = current_snapshot(&storage)?;
+
+        let ancestor = HashMap::from([("inline::aaa".to_string(), entry("old_name.rs", "inline::aaa", "old"))]);
+        let theirs = HashMap::from([("inline::aaa".to_string(), entry("old_name.rs", "inline::aaa", "new"))]);
+
+        let plan = plan_merge(&ancestor, &ours, &theirs);
+        assert_eq!(plan[0].action, SyncAction::Updated);
+
+        let (merged, conflicts) = apply_merge(&storage, &ours, &theirs, &plan)?;
+        assert_eq!(merged, 1);
+        assert_eq!(conflicts, 0);
+        assert_eq!(storage.get_value(Path::new("new_name.rs"), "inline::aaa")?, "new");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_conflict_writes_markers_for_both_sides() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let storage = fresh_storage(&temp_dir)?;
+        storage.store_value(Path::new("a.rs"), "inline::aaa", "mine")?;
+        let ours = current_snapshot(&storage)?;
+
+        let ancestor = HashMap::from([("inline::aaa".to_string(), entry("a.rs", "inline::aaa", "old"))]);
+        let theirs = HashMap::from([("inline::aaa".to_string(), entry("a.rs", "inline::aaa", "theirs"))]);
+
+        let plan = plan_merge(&ancestor, &ours, &theirs);
+        let (merged, conflicts) = apply_merge(&storage, &ours, &theirs, &plan)?;
+        assert_eq!(merged, 0);
+        assert_eq!(conflicts, 1);
+
+        let marked = storage.get_value(Path::new("a.rs"), "inline::aaa")?;
+        assert!(marked.contains("mine"));
+        assert!(marked.contains("theirs"));
+        assert!(marked.contains("<<<<<<<"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot_round_trips_through_disk() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let storage = fresh_storage(&temp_dir)?;
+        storage.store_value(Path::new("a.rs"), "inline::aaa", "secret")?;
+
+        save_snapshot(&storage, temp_dir.path(), "main")?;
+        let loaded = load_snapshot(temp_dir.path(), "main")?;
+
+        assert_eq!(loaded.get("inline::aaa").unwrap().value, "secret");
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_snapshot_loads_as_empty() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let loaded = load_snapshot(temp_dir.path(), "never-synced")?;
+        assert!(loaded.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,275 @@
+//! Portable, passphrase-encrypted bundle format for `whiteout sync export`/
+//! `import`, so local-only secrets can move between machines or teammates
+//! without ever being committed to Git. The whole exported entry set is
+//! sealed as one AEAD blob under a bundle-specific KDF header (its own
+//! random salt, independent of the project's vault salt), so a bundle file
+//! is self-contained and safe to copy anywhere on its own.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::crypto::{Crypto, KdfParams};
+use super::{LocalStorage, StorageEntry};
+
+const BUNDLE_AAD: &[u8] = b"whiteout-sync-bundle";
+const CURRENT_BUNDLE_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Bundle {
+    version: u32,
+    kdf: KdfParams,
+    payload: String,
+}
+
+/// How `sync import` resolves a `storage_key` that exists both locally and
+/// in the incoming bundle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Keep whatever is already stored locally.
+    PreferLocal,
+    /// Overwrite the local entry with the bundle's.
+    PreferIncoming,
+    /// Keep whichever side has the more recent `StorageEntry.timestamp`.
+    Newest,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeAction {
+    /// The key doesn't exist locally yet.
+    Added,
+    /// The key exists locally but the incoming entry wins the conflict.
+    Updated,
+    /// The key exists locally and the local entry wins the conflict.
+    Skipped,
+}
+
+#[derive(Debug, Clone)]
+pub struct MergePlanEntry {
+    pub storage_key: String,
+    pub action: MergeAction,
+}
+
+/// Exports every entry in `storage` (decrypted) and seals it under a fresh
+/// KDF header derived from `passphrase`, returning bytes ready to write to
+/// a bundle file.
+pub fn export(storage: &LocalStorage, passphrase: &str) -> Result<Vec<u8>> {
+    let entries = storage.export_entries()?;
+    let serialized =
+        toml::to_string_pretty(&entries).context("Failed to serialize bundle contents")?;
+
+    let kdf = KdfParams::generate();
+    let crypto = Crypto::from_passphrase(passphrase, &kdf)?;
+    let payload = crypto.encrypt(&serialized, BUNDLE_AAD)?;
+
+    let bundle = Bundle {
+        version: CURRENT_BUNDLE_VERSION,
+        kdf,
+        payload,
+    };
+
+    toml::to_string_pretty(&bundle)
+        .context("Failed to serialize bundle")
+        .map(String::into_bytes)
+}
+
+/// Opens a bundle produced by `export`. Refuses to return anything if the
+/// passphrase is wrong or the bundle has been tampered with -- the AEAD tag
+/// check fails identically in both cases, so a bad bundle never reaches the
+/// local store.
+fn open(bundle_bytes: &[u8], passphrase: &str) -> Result<HashMap<String, StorageEntry>> {
+    let content = std::str::from_utf8(bundle_bytes).context("Bundle is not valid UTF-8")?;
+    let bundle: Bundle = toml::from_str(content).context("Failed to parse bundle file")?;
+
+    if bundle.version > CURRENT_BUNDLE_VERSION {
+        anyhow::bail!(
+            "Bundle format version {} is newer than this build supports ({})",
+            bundle.version,
+            CURRENT_BUNDLE_VERSION
+        );
+    }
+
+    let crypto = Crypto::from_passphrase(passphrase, &bundle.kdf)?;
+    let serialized = crypto
+        .decrypt(&bundle.payload, BUNDLE_AAD)
+        .context("Failed to decrypt bundle: wrong passphrase, or the bundle has been tampered with")?;
+
+    toml::from_str(&serialized).context("Bundle payload is corrupted")
+}
+
+/// Opens `bundle_bytes` and computes what importing it into `storage` would
+/// do under `policy`, without writing anything. `sync import --dry-run`
+/// prints this; a real import calls `apply_import` with the same plan.
+pub fn plan_import(
+    storage: &LocalStorage,
+    bundle_bytes: &[u8],
+    passphrase: &str,
+    policy: ConflictPolicy,
+) -> Result<(HashMap<String, StorageEntry>, Vec<MergePlanEntry>)> {
+    let incoming = open(bundle_bytes, passphrase)?;
+
+    let mut plan: Vec<MergePlanEntry> = Vec::new();
+    for (storage_key, entry) in &incoming {
+        let action = match storage.peek_entry(storage_key)? {
+            None => MergeAction::Added,
+            Some(existing) => match policy {
+                ConflictPolicy::PreferLocal => MergeAction::Skipped,
+                ConflictPolicy::PreferIncoming => MergeAction::Updated,
+                ConflictPolicy::Newest => {
+                    if entry.timestamp > existing.timestamp {
+                        MergeAction::Updated
+                    } else {
+                        MergeAction::Skipped
+                    }
+                }
+            },
+        };
+        plan.push(MergePlanEntry {
+            storage_key: storage_key.clone(),
+            action,
+        });
+    }
+    plan.sort_by(|a, b| a.storage_key.cmp(&b.storage_key));
+
+    Ok((incoming, plan))
+}
+
+/// Writes every `Added`/`Updated` entry in `plan` into `storage`, preserving
+/// each entry's original timestamp. Returns the number of entries written.
+pub fn apply_import(
+    storage: &LocalStorage,
+    incoming: &HashMap<String, StorageEntry>,
+    plan: &[MergePlanEntry],
+) -> Result<usize> {
+    let mut written = 0;
+    for plan_entry in plan {
+        if plan_entry.action == MergeAction::Skipped {
+            continue;
+        }
+
+        let entry = incoming
+            .get(&plan_entry.storage_key)
+            .expect("plan entries are derived from incoming");
+
+        storage.import_entry(
+            &plan_entry.storage_key,
+            &entry.file_path,
+            &entry.key,
+            &entry.value,
+            entry.timestamp,
+        )?;
+        written += 1;
+    }
+
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    fn fresh_storage(temp_dir: &TempDir) -> Result<LocalStorage> {
+        LocalStorage::init(temp_dir.path())?;
+        LocalStorage::new(temp_dir.path())
+    }
+
+    #[test]
+    fn test_export_import_roundtrip() -> Result<()> {
+        let source_dir = TempDir::new()?;
+        let source = fresh_storage(&source_dir)?;
+        source.store_value(Path::new("a.rs"), "key", "secret_value")?;
+
+        let bundle_bytes = export(&source, "bundle-pass")?;
+
+        let dest_dir = TempDir::new()?;
+        let dest = fresh_storage(&dest_dir)?;
+
+        let (incoming, plan) =
+            plan_import(&dest, &bundle_bytes, "bundle-pass", ConflictPolicy::Newest)?;
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan[0].action, MergeAction::Added);
+
+        apply_import(&dest, &incoming, &plan)?;
+        assert_eq!(dest.get_value(Path::new("a.rs"), "key")?, "secret_value");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wrong_passphrase_is_rejected() -> Result<()> {
+        let source_dir = TempDir::new()?;
+        let source = fresh_storage(&source_dir)?;
+        source.store_value(Path::new("a.rs"), "key", "secret_value")?;
+
+        let bundle_bytes = export(&source, "correct-pass")?;
+
+        let dest_dir = TempDir::new()?;
+        let dest = fresh_storage(&dest_dir)?;
+
+        assert!(plan_import(&dest, &bundle_bytes, "wrong-pass", ConflictPolicy::Newest).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tampered_bundle_is_rejected() -> Result<()> {
+        let source_dir = TempDir::new()?;
+        let source = fresh_storage(&source_dir)?;
+        source.store_value(Path::new("a.rs"), "key", "secret_value")?;
+
+        let mut bundle_bytes = export(&source, "bundle-pass")?;
+        let flip_at = bundle_bytes.len() / 2;
+        bundle_bytes[flip_at] ^= 0xFF;
+
+        let dest_dir = TempDir::new()?;
+        let dest = fresh_storage(&dest_dir)?;
+
+        assert!(plan_import(&dest, &bundle_bytes, "bundle-pass", ConflictPolicy::Newest).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_prefer_local_skips_conflicting_key() -> Result<()> {
+        let source_dir = TempDir::new()?;
+        let source = fresh_storage(&source_dir)?;
+        source.store_value(Path::new("a.rs"), "key", "incoming_value")?;
+        let bundle_bytes = export(&source, "bundle-pass")?;
+
+        let dest_dir = TempDir::new()?;
+        let dest = fresh_storage(&dest_dir)?;
+        dest.store_value(Path::new("a.rs"), "key", "local_value")?;
+
+        let (incoming, plan) =
+            plan_import(&dest, &bundle_bytes, "bundle-pass", ConflictPolicy::PreferLocal)?;
+        assert_eq!(plan[0].action, MergeAction::Skipped);
+
+        apply_import(&dest, &incoming, &plan)?;
+        assert_eq!(dest.get_value(Path::new("a.rs"), "key")?, "local_value");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_prefer_incoming_overwrites_conflicting_key() -> Result<()> {
+        let source_dir = TempDir::new()?;
+        let source = fresh_storage(&source_dir)?;
+        source.store_value(Path::new("a.rs"), "key", "incoming_value")?;
+        let bundle_bytes = export(&source, "bundle-pass")?;
+
+        let dest_dir = TempDir::new()?;
+        let dest = fresh_storage(&dest_dir)?;
+        dest.store_value(Path::new("a.rs"), "key", "local_value")?;
+
+        let (incoming, plan) =
+            plan_import(&dest, &bundle_bytes, "bundle-pass", ConflictPolicy::PreferIncoming)?;
+        assert_eq!(plan[0].action, MergeAction::Updated);
+
+        apply_import(&dest, &incoming, &plan)?;
+        assert_eq!(dest.get_value(Path::new("a.rs"), "key")?, "incoming_value");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,176 @@
+//! Content-addressed cache mapping a cleaned file's exact bytes back to the
+//! original working-tree content that produced them, so `smudge` can
+//! restore a file in one read instead of re-deriving every decoration's
+//! value from `LocalStorage`. Keyed by an SRI-style integrity hash
+//! (`sha512-<base64>`) of the *cleaned* content -- what `smudge` actually
+//! has on hand -- so a lookup needs no parsing, only a hash.
+//!
+//! This is a fast path, not a replacement for the decoration-driven
+//! lookup in `transform::smudge`: a cache miss (new clone, cache wiped,
+//! cleaned content edited since) just falls back to that existing path.
+
+use anyhow::{bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha512};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::atomic::AtomicFile;
+
+/// A cache entry on disk: the original content, plus a hash of that same
+/// content computed at write time. Verified against a freshly computed
+/// hash on every read, so on-disk corruption (or a would-be hash
+/// collision) is caught before the wrong content is ever restored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    integrity: String,
+    original: String,
+}
+
+/// Hashes `content` the SRI way: `sha512-<base64 digest>`.
+pub fn integrity_hash(content: &str) -> String {
+    let mut hasher = Sha512::new();
+    hasher.update(content.as_bytes());
+    format!("sha512-{}", BASE64.encode(hasher.finalize()))
+}
+
+fn cache_dir(root: &Path) -> PathBuf {
+    root.join(".whiteout").join("cache")
+}
+
+/// The integrity hash doubles as the file name, with `/` (illegal in a
+/// path component) swapped for `_` since base64's alphabet can contain it.
+fn entry_path(root: &Path, key: &str) -> PathBuf {
+    cache_dir(root).join(key.replace('/', "_"))
+}
+
+/// Looks up `cleaned` (the content `smudge` was just handed) and, if
+/// present, returns the original working-tree content it was cached
+/// against -- after verifying the stored entry's own integrity hash still
+/// matches its content. A mismatch here means the cache file was corrupted
+/// or tampered with, which is always a hard error: silently returning
+/// unverified content could restore the wrong secret.
+pub fn lookup(root: &Path, cleaned: &str) -> Result<Option<String>> {
+    let key = integrity_hash(cleaned);
+    let path = entry_path(root, &key);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let raw = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read cache entry {}", path.display()))?;
+    let entry: CacheEntry = toml::from_str(&raw)
+        .with_context(|| format!("Failed to parse cache entry {}", path.display()))?;
+
+    let actual = integrity_hash(&entry.original);
+    if actual != entry.integrity {
+        bail!(
+            "Cache entry {} is corrupt: expected integrity {}, got {}",
+            path.display(),
+            entry.integrity,
+            actual
+        );
+    }
+
+    Ok(Some(entry.original))
+}
+
+/// Records that `cleaned` was produced from `original`, keyed by
+/// `cleaned`'s integrity hash. If an entry already exists for that key, it
+/// must be self-consistent and agree with `original` -- a mismatch means
+/// either on-disk corruption or a genuine hash collision between two
+/// different originals, and either way is a hard error rather than
+/// something this silently papers over.
+pub fn store(root: &Path, cleaned: &str, original: &str) -> Result<()> {
+    let key = integrity_hash(cleaned);
+    let path = entry_path(root, &key);
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("Failed to create .whiteout/cache directory")?;
+    }
+
+    if path.exists() {
+        let existing = lookup(root, cleaned)?.expect("just checked path.exists()");
+        if existing != original {
+            bail!(
+                "Cache key collision for {}: existing entry doesn't match new content",
+                path.display()
+            );
+        }
+        return Ok(());
+    }
+
+    let entry = CacheEntry {
+        integrity: integrity_hash(original),
+        original: original.to_string(),
+    };
+    let content = toml::to_string_pretty(&entry).context("Failed to serialize cache entry")?;
+
+    let atomic = AtomicFile::new(&path)?;
+    atomic.write(content.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_store_then_lookup_round_trips() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        store(temp_dir.path(), "cleaned", "original secret")?;
+
+        let found = lookup(temp_dir.path(), "cleaned")?;
+        assert_eq!(found, Some("original secret".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lookup_miss_returns_none() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        assert_eq!(lookup(temp_dir.path(), "never stored")?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_storing_the_same_pair_twice_is_a_no_op() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        store(temp_dir.path(), "cleaned", "original")?;
+        store(temp_dir.path(), "cleaned", "original")?;
+
+        assert_eq!(lookup(temp_dir.path(), "cleaned")?, Some("original".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_colliding_key_with_different_content_is_a_hard_error() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        store(temp_dir.path(), "cleaned", "original-a")?;
+
+        let result = store(temp_dir.path(), "cleaned", "original-b");
+        assert!(result.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_corrupted_entry_is_a_hard_error_not_silently_ignored() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        store(temp_dir.path(), "cleaned", "original")?;
+
+        let key = integrity_hash("cleaned");
+        let path = entry_path(temp_dir.path(), &key);
+        let tampered = r#"integrity = "sha512-not-the-real-hash"
+original = "tampered"
+"#;
+        fs::write(&path, tampered)?;
+
+        let result = lookup(temp_dir.path(), "cleaned");
+        assert!(result.is_err());
+
+        Ok(())
+    }
+}
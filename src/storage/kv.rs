@@ -0,0 +1,425 @@
+//! Pluggable persistence backend for `LocalStorage`. `store_value`,
+//! `get_value`, `remove_value`, and `list_values` all go through this trait
+//! instead of touching `local.toml` directly, so a project with thousands
+//! of decorated values can swap the full-file TOML rewrite for an embedded
+//! key-value store without changing any of those call sites.
+
+use std::fmt;
+use std::path::PathBuf;
+
+use super::atomic::{lock::FileLock, AtomicFile};
+use super::{StorageData, StorageEntry};
+use crate::error::StorageError;
+
+pub trait StorageBackend: fmt::Debug + Send + Sync {
+    fn get(&self, key: &str) -> Result<Option<StorageEntry>, StorageError>;
+    fn put(&self, key: &str, entry: StorageEntry) -> Result<(), StorageError>;
+    fn delete(&self, key: &str) -> Result<(), StorageError>;
+    /// Returns every `(storage_key, entry)` pair, optionally restricted to
+    /// keys starting with `prefix` (used by `list_values`'s file filter so
+    /// that case doesn't have to deserialize the whole store).
+    fn iter(&self, prefix: Option<&str>) -> Result<Vec<(String, StorageEntry)>, StorageError>;
+
+    /// Restores the primary store from its last-known-good backup, undoing
+    /// a write that left it truncated or corrupted. Backends that don't
+    /// keep one (e.g. `RedbBackend`, which relies on redb's own
+    /// write-ahead log for crash safety) report that there's nothing to
+    /// restore from.
+    fn restore_from_backup(&self) -> Result<(), StorageError> {
+        Err(StorageError::AccessError {
+            path: PathBuf::new(),
+            message: "This backend does not keep a backup".to_string(),
+        })
+    }
+}
+
+/// Opens the backend named by `config.data.storage.backend`. `"local"` and
+/// `"toml"` are the same thing (the default, whole-file TOML store);
+/// `"redb"` is the embedded key-value alternative for larger projects.
+pub fn open(backend: &str, storage_path: PathBuf) -> anyhow::Result<Box<dyn StorageBackend>> {
+    match backend {
+        "local" | "toml" => Ok(Box::new(TomlBackend::new(storage_path))),
+        "redb" => Ok(Box::new(RedbBackend::open(storage_path.with_extension("redb"))?)),
+        other => anyhow::bail!("Unknown storage backend: {}", other),
+    }
+}
+
+/// The original backend: the whole store is one `local.toml`, parsed and
+/// rewritten on every mutation. Kept as the default for portability (no
+/// extra binary format, diff-able, survives a corrupted embedded DB).
+#[derive(Debug)]
+pub struct TomlBackend {
+    path: PathBuf,
+}
+
+impl TomlBackend {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Path of the single-generation backup kept alongside `self.path`
+    /// (`local.toml` -> `local.toml.bak`), matching the `*.bak` pattern
+    /// `LocalStorage::init` already writes into `.whiteout/.gitignore`.
+    fn backup_path(&self) -> PathBuf {
+        let mut name = self.path.file_name().unwrap_or_default().to_os_string();
+        name.push(".bak");
+        self.path.with_file_name(name)
+    }
+
+    /// Path of the lock file `put`/`delete` hold for the duration of their
+    /// read-modify-write, so e.g. `clean-all`'s rayon-parallel `clean`
+    /// calls can't race two threads' `load` against the same on-disk
+    /// snapshot and have the second `write` silently clobber the first's
+    /// entry.
+    fn lock_path(&self) -> PathBuf {
+        let mut name = self.path.file_name().unwrap_or_default().to_os_string();
+        name.push(".lock");
+        self.path.with_file_name(name)
+    }
+
+    /// Runs `f` with an exclusive lock on `lock_path()` held for its
+    /// duration, serializing `put`/`delete` across threads and processes.
+    fn with_lock<T>(&self, f: impl FnOnce() -> Result<T, StorageError>) -> Result<T, StorageError> {
+        let lock_path = self.lock_path();
+
+        if let Some(parent) = lock_path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| StorageError::AccessError {
+                path: lock_path.clone(),
+                message: e.to_string(),
+            })?;
+        }
+
+        let lock_file = std::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .open(&lock_path)
+            .map_err(|e| StorageError::AccessError {
+                path: lock_path.clone(),
+                message: e.to_string(),
+            })?;
+
+        let _lock = FileLock::acquire(lock_file).map_err(|_| StorageError::Locked)?;
+
+        f()
+    }
+
+    fn parse(content: &str) -> Result<StorageData, StorageError> {
+        toml::from_str(content).map_err(|e| StorageError::Corrupted(e.to_string()))
+    }
+
+    fn load(&self) -> Result<StorageData, StorageError> {
+        if !self.path.exists() {
+            return Ok(StorageData {
+                version: "0.1.0".to_string(),
+                entries: std::collections::HashMap::new(),
+            });
+        }
+
+        let content = std::fs::read_to_string(&self.path).map_err(|e| StorageError::AccessError {
+            path: self.path.clone(),
+            message: e.to_string(),
+        })?;
+
+        match Self::parse(&content) {
+            Ok(data) => Ok(data),
+            Err(e) => {
+                let backup_path = self.backup_path();
+                if !backup_path.exists() {
+                    return Err(e);
+                }
+                tracing::warn!(
+                    "whiteout storage: {} is corrupted ({}), falling back to {}",
+                    self.path.display(),
+                    e,
+                    backup_path.display()
+                );
+                let backup_content = std::fs::read_to_string(&backup_path).map_err(|e| StorageError::AccessError {
+                    path: backup_path.clone(),
+                    message: e.to_string(),
+                })?;
+                Self::parse(&backup_content)
+            }
+        }
+    }
+
+    /// Writes `data` atomically (temp file + fsync + rename), first copying
+    /// the current store to `backup_path()` so a write that's interrupted
+    /// partway through never leaves both the primary and the last-known-good
+    /// copy unusable.
+    fn write(&self, data: &StorageData) -> Result<(), StorageError> {
+        let content = toml::to_string_pretty(data).map_err(|e| StorageError::Corrupted(e.to_string()))?;
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| StorageError::AccessError {
+                path: self.path.clone(),
+                message: e.to_string(),
+            })?;
+        }
+
+        if self.path.exists() {
+            std::fs::copy(&self.path, self.backup_path()).map_err(|e| StorageError::AccessError {
+                path: self.backup_path(),
+                message: e.to_string(),
+            })?;
+        }
+
+        let atomic = AtomicFile::new(&self.path).map_err(|e| StorageError::AccessError {
+            path: self.path.clone(),
+            message: e.to_string(),
+        })?;
+        atomic.write(content.as_bytes()).map_err(|e| StorageError::AccessError {
+            path: self.path.clone(),
+            message: e.to_string(),
+        })
+    }
+}
+
+impl StorageBackend for TomlBackend {
+    fn get(&self, key: &str) -> Result<Option<StorageEntry>, StorageError> {
+        Ok(self.load()?.entries.get(key).cloned())
+    }
+
+    fn put(&self, key: &str, entry: StorageEntry) -> Result<(), StorageError> {
+        self.with_lock(|| {
+            let mut data = self.load()?;
+            data.entries.insert(key.to_string(), entry);
+            self.write(&data)
+        })
+    }
+
+    fn delete(&self, key: &str) -> Result<(), StorageError> {
+        self.with_lock(|| {
+            let mut data = self.load()?;
+            data.entries.remove(key);
+            self.write(&data)
+        })
+    }
+
+    fn iter(&self, prefix: Option<&str>) -> Result<Vec<(String, StorageEntry)>, StorageError> {
+        let data = self.load()?;
+        Ok(data
+            .entries
+            .into_iter()
+            .filter(|(key, _)| prefix.map_or(true, |p| key.starts_with(p)))
+            .collect())
+    }
+
+    fn restore_from_backup(&self) -> Result<(), StorageError> {
+        let backup_path = self.backup_path();
+        if !backup_path.exists() {
+            return Err(StorageError::AccessError {
+                path: backup_path,
+                message: "No backup file exists".to_string(),
+            });
+        }
+
+        let content = std::fs::read(&backup_path).map_err(|e| StorageError::AccessError {
+            path: backup_path.clone(),
+            message: e.to_string(),
+        })?;
+
+        let atomic = AtomicFile::new(&self.path).map_err(|e| StorageError::AccessError {
+            path: self.path.clone(),
+            message: e.to_string(),
+        })?;
+        atomic.write(&content).map_err(|e| StorageError::AccessError {
+            path: self.path.clone(),
+            message: e.to_string(),
+        })
+    }
+}
+
+const REDB_TABLE: redb::TableDefinition<&str, &[u8]> = redb::TableDefinition::new("entries");
+
+/// An embedded key-value backend so per-key writes are a single indexed
+/// upsert (and `list_values` with a file filter is a prefix scan) instead
+/// of parsing and rewriting the entire store on every mutation.
+pub struct RedbBackend {
+    db: redb::Database,
+}
+
+impl RedbBackend {
+    pub fn open(path: PathBuf) -> anyhow::Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let db = redb::Database::create(path)?;
+        Ok(Self { db })
+    }
+
+    fn access_error(e: impl std::fmt::Display) -> StorageError {
+        StorageError::AccessError {
+            path: PathBuf::from("local.redb"),
+            message: e.to_string(),
+        }
+    }
+}
+
+impl fmt::Debug for RedbBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RedbBackend").finish()
+    }
+}
+
+impl StorageBackend for RedbBackend {
+    fn get(&self, key: &str) -> Result<Option<StorageEntry>, StorageError> {
+        let txn = self.db.begin_read().map_err(Self::access_error)?;
+        let table = txn.open_table(REDB_TABLE).map_err(Self::access_error)?;
+
+        match table.get(key).map_err(Self::access_error)? {
+            Some(value) => {
+                let entry = serde_json::from_slice(value.value())
+                    .map_err(|e| StorageError::Corrupted(e.to_string()))?;
+                Ok(Some(entry))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn put(&self, key: &str, entry: StorageEntry) -> Result<(), StorageError> {
+        let bytes = serde_json::to_vec(&entry).map_err(|e| StorageError::Corrupted(e.to_string()))?;
+
+        let txn = self.db.begin_write().map_err(Self::access_error)?;
+        {
+            let mut table = txn.open_table(REDB_TABLE).map_err(Self::access_error)?;
+            table.insert(key, bytes.as_slice()).map_err(Self::access_error)?;
+        }
+        txn.commit().map_err(Self::access_error)
+    }
+
+    fn delete(&self, key: &str) -> Result<(), StorageError> {
+        let txn = self.db.begin_write().map_err(Self::access_error)?;
+        {
+            let mut table = txn.open_table(REDB_TABLE).map_err(Self::access_error)?;
+            table.remove(key).map_err(Self::access_error)?;
+        }
+        txn.commit().map_err(Self::access_error)
+    }
+
+    fn iter(&self, prefix: Option<&str>) -> Result<Vec<(String, StorageEntry)>, StorageError> {
+        let txn = self.db.begin_read().map_err(Self::access_error)?;
+        let table = txn.open_table(REDB_TABLE).map_err(Self::access_error)?;
+
+        let mut results = Vec::new();
+        for row in table.iter().map_err(Self::access_error)? {
+            let (key, value) = row.map_err(Self::access_error)?;
+            let key = key.value().to_string();
+            if prefix.map_or(true, |p| key.starts_with(p)) {
+                let entry = serde_json::from_slice(value.value())
+                    .map_err(|e| StorageError::Corrupted(e.to_string()))?;
+                results.push((key, entry));
+            }
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use tempfile::TempDir;
+
+    fn entry(value: &str) -> StorageEntry {
+        StorageEntry {
+            file_path: PathBuf::from("test.rs"),
+            key: "k".to_string(),
+            value: value.to_string(),
+            encrypted: false,
+            compressed: false,
+            timestamp: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_write_backs_up_previous_version() -> Result<(), StorageError> {
+        let temp_dir = TempDir::new().unwrap();
+        let backend = TomlBackend::new(temp_dir.path().join("local.toml"));
+
+        backend.put("a", entry("first"))?;
+        assert!(!backend.backup_path().exists(), "no backup before a second write");
+
+        backend.put("a", entry("second"))?;
+        assert!(backend.backup_path().exists());
+
+        let backed_up = std::fs::read_to_string(backend.backup_path()).unwrap();
+        assert!(backed_up.contains("first"));
+        assert!(!backed_up.contains("second"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_falls_back_to_backup_when_primary_is_corrupted() -> Result<(), StorageError> {
+        let temp_dir = TempDir::new().unwrap();
+        let backend = TomlBackend::new(temp_dir.path().join("local.toml"));
+
+        backend.put("a", entry("good"))?;
+        backend.put("a", entry("better"))?; // first write is now the backup
+
+        std::fs::write(&backend.path, "not valid toml {{{").unwrap();
+
+        let data = backend.load()?;
+        assert_eq!(data.entries.get("a").unwrap().value, "good");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_from_backup() -> Result<(), StorageError> {
+        let temp_dir = TempDir::new().unwrap();
+        let backend = TomlBackend::new(temp_dir.path().join("local.toml"));
+
+        backend.put("a", entry("good"))?;
+        backend.put("a", entry("better"))?;
+
+        backend.restore_from_backup()?;
+
+        let data = backend.load()?;
+        assert_eq!(data.entries.get("a").unwrap().value, "good");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_from_backup_errors_without_one() {
+        let temp_dir = TempDir::new().unwrap();
+        let backend = TomlBackend::new(temp_dir.path().join("local.toml"));
+
+        assert!(backend.restore_from_backup().is_err());
+    }
+
+    /// Reproduces the race a rayon-parallel `clean-all` hits: many threads
+    /// each `put`-ing their own key against the same `local.toml`
+    /// concurrently. Before `put` serialized `load`+`write` with a lock,
+    /// two threads would both load the same snapshot and the second
+    /// `write` would win, silently dropping whichever entry wasn't in that
+    /// snapshot.
+    #[test]
+    fn test_concurrent_put_does_not_lose_entries() {
+        use std::sync::Arc;
+        use std::thread;
+
+        let temp_dir = TempDir::new().unwrap();
+        let backend = Arc::new(TomlBackend::new(temp_dir.path().join("local.toml")));
+
+        let handles: Vec<_> = (0..16)
+            .map(|i| {
+                let backend = Arc::clone(&backend);
+                thread::spawn(move || {
+                    backend.put(&format!("key{}", i), entry(&format!("value{}", i))).unwrap();
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let data = backend.load().unwrap();
+        assert_eq!(data.entries.len(), 16);
+        for i in 0..16 {
+            assert_eq!(data.entries.get(&format!("key{}", i)).unwrap().value, format!("value{}", i));
+        }
+    }
+}
@@ -1,37 +1,111 @@
 use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use super::{StorageData, StorageEntry};
+use super::crypto::Crypto;
+use super::kv::{self, StorageBackend};
+use super::{BlobRef, Storage, StorageData, StorageEntry};
+use crate::config::Config;
+use crate::error::StorageError;
 
-#[derive(Debug, Clone)]
 pub struct LocalStorage {
     root_path: PathBuf,
     storage_path: PathBuf,
+    crypto: Option<Crypto>,
+    backend: Box<dyn StorageBackend>,
+    compress_threshold: usize,
+    compress_level: i32,
+}
+
+impl std::fmt::Debug for LocalStorage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LocalStorage")
+            .field("root_path", &self.root_path)
+            .field("storage_path", &self.storage_path)
+            .field("encrypted", &self.crypto.is_some())
+            .field("backend", &self.backend)
+            .field("compress_threshold", &self.compress_threshold)
+            .field("compress_level", &self.compress_level)
+            .finish()
+    }
 }
 
 impl LocalStorage {
     pub fn new(project_root: impl AsRef<Path>) -> Result<Self> {
+        Self::open(project_root, None)
+    }
+
+    /// Like [`Self::new`], but encrypts with `crypto` (e.g. an already
+    /// cached cipher held by the agent) instead of deriving one from
+    /// [`Crypto::for_project`]. Still respects `config.data.encryption.enabled`:
+    /// if encryption is off, `crypto` is discarded unused, exactly as `new`
+    /// stores nothing but `None` in that case.
+    pub fn with_crypto(project_root: impl AsRef<Path>, crypto: Crypto) -> Result<Self> {
+        Self::open(project_root, Some(crypto))
+    }
+
+    /// Shared constructor body for [`Self::new`] and [`Self::with_crypto`]:
+    /// `crypto` is `None` to derive a cipher via [`Crypto::for_project`],
+    /// or `Some` to use one the caller already has.
+    fn open(project_root: impl AsRef<Path>, crypto: Option<Crypto>) -> Result<Self> {
         let root_path = project_root.as_ref().to_path_buf();
         let storage_path = root_path.join(".whiteout").join("local.toml");
-        
+
+        let config = Config::load_or_default(&root_path)?;
+        let crypto = if config.data.encryption.enabled {
+            Some(match crypto {
+                Some(crypto) => crypto,
+                None => Crypto::for_project(&root_path)?,
+            })
+        } else {
+            None
+        };
+        let backend = kv::open(&config.data.storage.backend, storage_path.clone())?;
+
         Ok(Self {
             root_path,
             storage_path,
+            crypto,
+            backend,
+            compress_threshold: config.data.storage.compress_threshold,
+            compress_level: config.data.storage.compress_level,
         })
     }
 
     pub fn init(project_root: impl AsRef<Path>) -> Result<()> {
         let whiteout_dir = project_root.as_ref().join(".whiteout");
         fs::create_dir_all(&whiteout_dir).context("Failed to create .whiteout directory")?;
-        
+
+        // `cache/` holds `clean`'s content-addressed cache of full,
+        // uncleaned file contents (see `storage::cache`), and `*.lock` is
+        // `TomlBackend`'s write-serialization lock file (see `storage::kv`)
+        // -- neither should ever be committed. Appended rather than
+        // written only when the file is missing, so a project initialized
+        // by an older version of `whiteout` still picks up lines added
+        // since, instead of being stuck with whatever `init` wrote the
+        // first time.
         let gitignore_path = whiteout_dir.join(".gitignore");
-        if !gitignore_path.exists() {
-            fs::write(&gitignore_path, "local.toml\n*.bak\n")
-                .context("Failed to create .gitignore")?;
+        let required_lines = ["local.toml", "*.bak", "*.lock", "local.redb", "cache/"];
+        let existing = fs::read_to_string(&gitignore_path).unwrap_or_default();
+        let missing: Vec<&str> = required_lines
+            .iter()
+            .filter(|line| !existing.lines().any(|existing_line| existing_line.trim() == **line))
+            .copied()
+            .collect();
+        if !missing.is_empty() {
+            let mut updated = existing;
+            if !updated.is_empty() && !updated.ends_with('\n') {
+                updated.push('\n');
+            }
+            for line in missing {
+                updated.push_str(line);
+                updated.push('\n');
+            }
+            fs::write(&gitignore_path, updated).context("Failed to write .gitignore")?;
         }
-        
+
         let storage_path = whiteout_dir.join("local.toml");
         if !storage_path.exists() {
             let initial_data = StorageData {
@@ -42,7 +116,7 @@ impl LocalStorage {
                 .context("Failed to serialize initial storage")?;
             fs::write(&storage_path, content).context("Failed to write initial storage")?;
         }
-        
+
         Ok(())
     }
 
@@ -53,89 +127,307 @@ impl LocalStorage {
         value: &str,
     ) -> Result<()> {
         let storage_key = self.make_storage_key(file_path, key);
-        
+        let (stored_value, compressed, encrypted) = self.seal_value(&storage_key, value)?;
+
         let entry = StorageEntry {
             file_path: file_path.to_path_buf(),
             key: key.to_string(),
-            value: value.to_string(),
-            encrypted: false,
+            value: stored_value,
+            encrypted,
+            compressed,
             timestamp: chrono::Utc::now(),
         };
-        
-        let mut data = self.load_data()?;
-        data.entries.insert(storage_key, entry);
-        
-        let content = toml::to_string_pretty(&data)
-            .context("Failed to serialize storage")?;
-        
-        fs::create_dir_all(self.storage_path.parent().unwrap())
-            .context("Failed to create storage directory")?;
-        
-        fs::write(&self.storage_path, content)
-            .context("Failed to write storage file")?;
-        
-        Ok(())
+
+        self.backend
+            .put(&storage_key, entry)
+            .map_err(|e| anyhow::anyhow!(e))
+    }
+
+    /// Compresses `value` if it's over `compress_threshold` and encrypts it
+    /// if encryption is enabled, returning `(stored_value, compressed,
+    /// encrypted)`. Shared by `store_value` and `import_entry` so both apply
+    /// this instance's storage policy identically; they differ only in what
+    /// `timestamp` ends up on the resulting entry.
+    fn seal_value(&self, storage_key: &str, value: &str) -> Result<(String, bool, bool)> {
+        let (payload, compressed) = if value.len() > self.compress_threshold {
+            let compressed_bytes = zstd::stream::encode_all(value.as_bytes(), self.compress_level)
+                .context("Failed to compress value")?;
+            (BASE64.encode(compressed_bytes), true)
+        } else {
+            (value.to_string(), false)
+        };
+
+        let (stored_value, encrypted) = match &self.crypto {
+            Some(crypto) => (crypto.encrypt(&payload, storage_key.as_bytes())?, true),
+            None => (payload, false),
+        };
+
+        Ok((stored_value, compressed, encrypted))
+    }
+
+    /// Writes `value` under `storage_key` directly, preserving `timestamp`
+    /// instead of stamping "now" like `store_value` does. Used by `sync
+    /// import` so a merged entry keeps the time it was originally written
+    /// at, which `--newest` conflict resolution depends on.
+    pub fn import_entry(
+        &self,
+        storage_key: &str,
+        file_path: &Path,
+        key: &str,
+        value: &str,
+        timestamp: chrono::DateTime<chrono::Utc>,
+    ) -> Result<()> {
+        let (stored_value, compressed, encrypted) = self.seal_value(storage_key, value)?;
+
+        let entry = StorageEntry {
+            file_path: file_path.to_path_buf(),
+            key: key.to_string(),
+            value: stored_value,
+            encrypted,
+            compressed,
+            timestamp,
+        };
+
+        self.backend
+            .put(storage_key, entry)
+            .map_err(|e| anyhow::anyhow!(e))
+    }
+
+    /// Returns every stored entry with its value decrypted and
+    /// decompressed, keyed by `storage_key`. Used to build a portable sync
+    /// bundle, which re-encrypts everything under its own passphrase rather
+    /// than this instance's local-storage key.
+    pub fn export_entries(&self) -> Result<HashMap<String, StorageEntry>> {
+        let mut out = HashMap::new();
+        for (storage_key, entry) in self.backend.iter(None).map_err(|e| anyhow::anyhow!(e))? {
+            let value = self.decrypt_entry(&storage_key, &entry)?;
+            let relative_file_path = entry
+                .file_path
+                .strip_prefix(&self.root_path)
+                .unwrap_or(&entry.file_path)
+                .to_path_buf();
+            out.insert(
+                storage_key,
+                StorageEntry {
+                    file_path: relative_file_path,
+                    key: entry.key,
+                    value,
+                    encrypted: false,
+                    compressed: false,
+                    timestamp: entry.timestamp,
+                },
+            );
+        }
+        Ok(out)
+    }
+
+    /// Returns the raw stored entry for `storage_key`, still in its on-disk
+    /// encrypted/compressed form, if present. `sync import` uses this to
+    /// resolve conflicts by timestamp without needing to decrypt either
+    /// side.
+    pub fn peek_entry(&self, storage_key: &str) -> Result<Option<StorageEntry>> {
+        self.backend.get(storage_key).map_err(|e| anyhow::anyhow!(e))
     }
 
     pub fn get_value(&self, file_path: &Path, key: &str) -> Result<String> {
         let storage_key = self.make_storage_key(file_path, key);
-        let data = self.load_data()?;
-        
-        data.entries
+
+        let entry = self
+            .backend
             .get(&storage_key)
-            .map(|e| e.value.clone())
-            .ok_or_else(|| anyhow::anyhow!("Value not found for key: {}", storage_key))
+            .map_err(|e| anyhow::anyhow!(e))?
+            .ok_or_else(|| anyhow::anyhow!("Value not found for key: {}", storage_key))?;
+
+        self.decrypt_entry(&storage_key, &entry)
+    }
+
+    /// Decrypts `entry`'s value if it's marked encrypted, surfacing a clear
+    /// error instead of returning ciphertext when no local-storage key is
+    /// available or the AEAD tag check fails. Leaves a compressed payload
+    /// compressed; pair with decompression (see `decrypt_entry`) when the
+    /// fully usable plaintext is needed rather than just an unencrypted view.
+    fn decrypt_value_only(&self, storage_key: &str, entry: &StorageEntry) -> Result<String> {
+        if !entry.encrypted {
+            return Ok(entry.value.clone());
+        }
+
+        let crypto = self.crypto.as_ref().ok_or_else(|| {
+            anyhow::anyhow!(
+                "Entry '{}' is encrypted but no local-storage key is available (is WHITEOUT_KEY or .whiteout/key missing?)",
+                storage_key
+            )
+        })?;
+
+        crypto
+            .decrypt(&entry.value, storage_key.as_bytes())
+            .with_context(|| format!("Failed to decrypt entry '{}'", storage_key))
+    }
+
+    /// Decrypts `entry`'s value if it's marked encrypted, then decompresses
+    /// it if it's marked compressed, surfacing a clear error instead of
+    /// returning corrupt plaintext when no local-storage key is available,
+    /// the AEAD tag check fails, or the compressed payload is malformed.
+    fn decrypt_entry(&self, storage_key: &str, entry: &StorageEntry) -> Result<String> {
+        let payload = self.decrypt_value_only(storage_key, entry)?;
+
+        if !entry.compressed {
+            return Ok(payload);
+        }
+
+        let compressed_bytes = BASE64
+            .decode(payload.trim())
+            .with_context(|| format!("Failed to decode compressed entry '{}'", storage_key))?;
+        let decompressed = zstd::stream::decode_all(compressed_bytes.as_slice())
+            .with_context(|| format!("Failed to decompress entry '{}'", storage_key))?;
+
+        String::from_utf8(decompressed)
+            .with_context(|| format!("Decompressed entry '{}' is not valid UTF-8", storage_key))
+    }
+
+    /// Re-encrypts every still-plaintext entry in place. Run this after
+    /// `whiteout config set encryption.enabled true` so secrets written
+    /// before encryption was turned on don't linger on disk in the clear.
+    pub fn migrate_to_encrypted(&self) -> Result<usize> {
+        let crypto = self
+            .crypto
+            .as_ref()
+            .context("Encryption is not enabled; run `whiteout config set encryption.enabled true` first")?;
+
+        let mut migrated = 0;
+        for (storage_key, mut entry) in self.backend.iter(None).map_err(|e| anyhow::anyhow!(e))? {
+            if !entry.encrypted {
+                entry.value = crypto.encrypt(&entry.value, storage_key.as_bytes())?;
+                entry.encrypted = true;
+                self.backend
+                    .put(&storage_key, entry)
+                    .map_err(|e| anyhow::anyhow!(e))?;
+                migrated += 1;
+            }
+        }
+
+        Ok(migrated)
+    }
+
+    /// Restores the backend's primary store from its last-known-good
+    /// backup (for the default TOML backend, `local.toml.bak`), undoing a
+    /// write that left it truncated or corrupted. Errors if the backend
+    /// doesn't keep a backup or none exists yet.
+    pub fn restore_from_backup(&self) -> Result<()> {
+        self.backend
+            .restore_from_backup()
+            .map_err(|e| anyhow::anyhow!(e))
+    }
+
+    /// One-shot migration: copies every entry from the currently configured
+    /// backend into a freshly opened `target_backend` (e.g. `"redb"`), so
+    /// switching `storage.backend` doesn't strand existing values behind
+    /// the old one.
+    pub fn migrate_to_backend(&self, target_backend: &str) -> Result<usize> {
+        let target = kv::open(target_backend, self.storage_path.clone())?;
+
+        let entries = self.backend.iter(None).map_err(|e| anyhow::anyhow!(e))?;
+        for (storage_key, entry) in &entries {
+            target
+                .put(storage_key, entry.clone())
+                .map_err(|e| anyhow::anyhow!(e))?;
+        }
+
+        Ok(entries.len())
     }
 
     pub fn remove_value(&self, file_path: &Path, key: &str) -> Result<()> {
         let storage_key = self.make_storage_key(file_path, key);
-        
-        let mut data = self.load_data()?;
-        data.entries.remove(&storage_key);
-        
-        let content = toml::to_string_pretty(&data)
-            .context("Failed to serialize storage")?;
-        
-        fs::write(&self.storage_path, content)
-            .context("Failed to write storage file")?;
-        
-        Ok(())
+        self.backend.delete(&storage_key).map_err(|e| anyhow::anyhow!(e))
     }
 
+    /// Lists stored entries (optionally filtered to one file), decrypting
+    /// each value that's marked encrypted so callers never see raw
+    /// ciphertext. Compression, if any, is left as-is (`compressed` stays
+    /// `true` and `value` stays the compressed payload) since decompressing
+    /// is orthogonal to this method's job of not leaking secrets.
     pub fn list_values(&self, file_path: Option<&Path>) -> Result<Vec<StorageEntry>> {
-        let data = self.load_data()?;
-        Ok(data
-            .entries
-            .values()
-            .filter(|e| {
-                file_path.map_or(true, |fp| e.file_path == fp)
-            })
-            .cloned()
-            .collect())
-    }
-    
-    fn load_data(&self) -> Result<StorageData> {
-        if self.storage_path.exists() {
-            let content = fs::read_to_string(&self.storage_path)
-                .context("Failed to read storage file")?;
-            toml::from_str(&content).context("Failed to parse storage file")
-        } else {
-            Ok(StorageData {
-                version: "0.1.0".to_string(),
-                entries: HashMap::new(),
+        let prefix = file_path.map(|fp| {
+            let relative_path = fp.strip_prefix(&self.root_path).unwrap_or(fp);
+            format!("{}::", relative_path.display())
+        });
+
+        let entries = self
+            .backend
+            .iter(prefix.as_deref())
+            .map_err(|e| anyhow::anyhow!(e))?;
+
+        entries
+            .into_iter()
+            .map(|(storage_key, entry)| {
+                let value = self.decrypt_value_only(&storage_key, &entry)?;
+                Ok(StorageEntry {
+                    value,
+                    encrypted: false,
+                    ..entry
+                })
             })
-        }
+            .collect()
     }
 
     fn make_storage_key(&self, file_path: &Path, key: &str) -> String {
         let relative_path = file_path
             .strip_prefix(&self.root_path)
             .unwrap_or(file_path);
-        
+
         format!("{}::{}", relative_path.display(), key)
     }
 }
 
+/// Splits a `"{relative_path}::{key}"` storage key back into its two parts.
+fn split_storage_key(storage_key: &str) -> (String, String) {
+    match storage_key.split_once("::") {
+        Some((file, key)) => (file.to_string(), key.to_string()),
+        None => (storage_key.to_string(), String::new()),
+    }
+}
+
+impl Storage for LocalStorage {
+    fn blob_ref(&self, file_path: &Path, key: &str) -> BlobRef {
+        BlobRef(self.make_storage_key(file_path, key))
+    }
+
+    fn blob_fetch(&self, blob: &BlobRef) -> std::result::Result<Vec<u8>, StorageError> {
+        let entry = self.backend.get(&blob.0)?.ok_or_else(|| {
+            let (file, key) = split_storage_key(&blob.0);
+            StorageError::KeyNotFound { key, file }
+        })?;
+
+        self.decrypt_entry(&blob.0, &entry)
+            .map(String::into_bytes)
+            .map_err(|e| StorageError::Decryption(e.to_string()))
+    }
+
+    fn blob_put(&self, blob: &BlobRef, data: &[u8]) -> std::result::Result<(), StorageError> {
+        let (file, key) = split_storage_key(&blob.0);
+        let value = String::from_utf8(data.to_vec())
+            .map_err(|e| StorageError::Corrupted(format!("non-UTF-8 blob value: {}", e)))?;
+
+        self.store_value(Path::new(&file), &key, &value)
+            .map_err(|e| StorageError::AccessError {
+                path: self.storage_path.clone(),
+                message: e.to_string(),
+            })
+    }
+
+    fn list(&self) -> std::result::Result<Vec<BlobRef>, StorageError> {
+        Ok(self.backend.iter(None)?.into_iter().map(|(key, _)| BlobRef(key)).collect())
+    }
+
+    fn remove(&self, blob: &BlobRef) -> std::result::Result<(), StorageError> {
+        let (file, key) = split_storage_key(&blob.0);
+        self.remove_value(Path::new(&file), &key)
+            .map_err(|e| StorageError::AccessError {
+                path: self.storage_path.clone(),
+                message: e.to_string(),
+            })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -145,11 +437,42 @@ mod tests {
     fn test_storage_init() -> Result<()> {
         let temp_dir = TempDir::new()?;
         LocalStorage::init(temp_dir.path())?;
-        
+
         assert!(temp_dir.path().join(".whiteout").exists());
         assert!(temp_dir.path().join(".whiteout/.gitignore").exists());
         assert!(temp_dir.path().join(".whiteout/local.toml").exists());
-        
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_init_gitignore_excludes_cache_dir() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        LocalStorage::init(temp_dir.path())?;
+
+        let gitignore = fs::read_to_string(temp_dir.path().join(".whiteout/.gitignore"))?;
+        assert!(gitignore.lines().any(|line| line.trim() == "cache/"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_init_backfills_gitignore_lines_missing_from_an_older_init() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let whiteout_dir = temp_dir.path().join(".whiteout");
+        fs::create_dir_all(&whiteout_dir)?;
+        // Simulates `.whiteout/.gitignore` as an older version of `init`
+        // would have left it, before `cache/`/`*.lock` were added.
+        fs::write(whiteout_dir.join(".gitignore"), "local.toml\n*.bak\nlocal.redb\n")?;
+
+        LocalStorage::init(temp_dir.path())?;
+
+        let gitignore = fs::read_to_string(whiteout_dir.join(".gitignore"))?;
+        assert!(gitignore.lines().any(|line| line.trim() == "cache/"));
+        assert!(gitignore.lines().any(|line| line.trim() == "*.lock"));
+        // The pre-existing lines are kept, not replaced.
+        assert!(gitignore.lines().any(|line| line.trim() == "local.toml"));
+
         Ok(())
     }
 
@@ -158,13 +481,13 @@ mod tests {
         let temp_dir = TempDir::new()?;
         LocalStorage::init(temp_dir.path())?;
         let storage = LocalStorage::new(temp_dir.path())?;
-        
+
         let file_path = Path::new("test.rs");
         storage.store_value(file_path, "test_key", "test_value")?;
-        
+
         let value = storage.get_value(file_path, "test_key")?;
         assert_eq!(value, "test_value");
-        
+
         Ok(())
     }
 
@@ -173,13 +496,172 @@ mod tests {
         let temp_dir = TempDir::new()?;
         LocalStorage::init(temp_dir.path())?;
         let storage = LocalStorage::new(temp_dir.path())?;
-        
+
         let file_path = Path::new("test.rs");
         storage.store_value(file_path, "test_key", "test_value")?;
         storage.remove_value(file_path, "test_key")?;
-        
+
         assert!(storage.get_value(file_path, "test_key").is_err());
-        
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_values_filters_by_prefix() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        LocalStorage::init(temp_dir.path())?;
+        let storage = LocalStorage::new(temp_dir.path())?;
+
+        storage.store_value(Path::new("a.rs"), "k1", "v1")?;
+        storage.store_value(Path::new("b.rs"), "k2", "v2")?;
+
+        let only_a = storage.list_values(Some(Path::new("a.rs")))?;
+        assert_eq!(only_a.len(), 1);
+        assert_eq!(only_a[0].value, "v1");
+
+        Ok(())
+    }
+
+    fn enable_encryption(project_root: &Path) -> Result<()> {
+        let mut config = Config::load_or_default(project_root)?;
+        config.data.encryption.enabled = true;
+        config.save()
+    }
+
+    #[test]
+    fn test_encrypted_storage_roundtrip() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        LocalStorage::init(temp_dir.path())?;
+        crate::config::Config::init(temp_dir.path())?;
+        enable_encryption(temp_dir.path())?;
+
+        let storage = LocalStorage::new(temp_dir.path())?;
+        let file_path = Path::new("test.rs");
+        storage.store_value(file_path, "test_key", "test_value")?;
+
+        let on_disk = fs::read_to_string(temp_dir.path().join(".whiteout/local.toml"))?;
+        assert!(!on_disk.contains("test_value"));
+
+        assert_eq!(storage.get_value(file_path, "test_key")?, "test_value");
+
         Ok(())
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_migrate_to_encrypted() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        LocalStorage::init(temp_dir.path())?;
+        crate::config::Config::init(temp_dir.path())?;
+
+        let plaintext_storage = LocalStorage::new(temp_dir.path())?;
+        let file_path = Path::new("test.rs");
+        plaintext_storage.store_value(file_path, "test_key", "test_value")?;
+
+        enable_encryption(temp_dir.path())?;
+        let encrypted_storage = LocalStorage::new(temp_dir.path())?;
+        let migrated = encrypted_storage.migrate_to_encrypted()?;
+        assert_eq!(migrated, 1);
+
+        let on_disk = fs::read_to_string(temp_dir.path().join(".whiteout/local.toml"))?;
+        assert!(!on_disk.contains("test_value"));
+        assert_eq!(encrypted_storage.get_value(file_path, "test_key")?, "test_value");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_small_value_is_stored_uncompressed() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        LocalStorage::init(temp_dir.path())?;
+        let storage = LocalStorage::new(temp_dir.path())?;
+
+        let file_path = Path::new("test.rs");
+        storage.store_value(file_path, "test_key", "small_value")?;
+
+        let entries = storage.list_values(None)?;
+        assert!(!entries[0].compressed);
+        assert_eq!(storage.get_value(file_path, "test_key")?, "small_value");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_large_value_is_compressed_and_roundtrips() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        LocalStorage::init(temp_dir.path())?;
+        crate::config::Config::init(temp_dir.path())?;
+
+        let mut config = Config::load_or_default(temp_dir.path())?;
+        config.data.storage.compress_threshold = 16;
+        config.save()?;
+
+        let storage = LocalStorage::new(temp_dir.path())?;
+        let file_path = Path::new("test.rs");
+        let large_value = "x".repeat(1024);
+        storage.store_value(file_path, "test_key", &large_value)?;
+
+        let entries = storage.list_values(None)?;
+        assert!(entries[0].compressed);
+        assert!(entries[0].value.len() < large_value.len());
+        assert_eq!(storage.get_value(file_path, "test_key")?, large_value);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_compress_level_is_configurable() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        LocalStorage::init(temp_dir.path())?;
+        crate::config::Config::init(temp_dir.path())?;
+
+        let mut config = Config::load_or_default(temp_dir.path())?;
+        config.data.storage.compress_threshold = 16;
+        config.data.storage.compress_level = 19; // highest zstd level
+        config.save()?;
+
+        let storage = LocalStorage::new(temp_dir.path())?;
+        let file_path = Path::new("test.rs");
+        let large_value = "x".repeat(1024);
+        storage.store_value(file_path, "test_key", &large_value)?;
+
+        let entries = storage.list_values(None)?;
+        assert!(entries[0].compressed);
+        assert_eq!(storage.get_value(file_path, "test_key")?, large_value);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_values_decrypts_entries() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        LocalStorage::init(temp_dir.path())?;
+        crate::config::Config::init(temp_dir.path())?;
+        enable_encryption(temp_dir.path())?;
+
+        let storage = LocalStorage::new(temp_dir.path())?;
+        let file_path = Path::new("test.rs");
+        storage.store_value(file_path, "test_key", "test_value")?;
+
+        let entries = storage.list_values(Some(file_path))?;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].value, "test_value");
+        assert!(!entries[0].encrypted);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_to_backend_copies_all_entries() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        LocalStorage::init(temp_dir.path())?;
+        let storage = LocalStorage::new(temp_dir.path())?;
+
+        storage.store_value(Path::new("a.rs"), "k1", "v1")?;
+        storage.store_value(Path::new("b.rs"), "k2", "v2")?;
+
+        let migrated = storage.migrate_to_backend("redb")?;
+        assert_eq!(migrated, 2);
+
+        Ok(())
+    }
+}
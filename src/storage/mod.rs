@@ -1,11 +1,67 @@
+pub mod atomic;
+pub mod backend;
+pub mod branch;
+pub mod bundle;
+pub mod cache;
 pub mod crypto;
+pub mod kv;
 pub mod local;
+pub mod recipients;
 
+pub use backend::{BlobRef, Storage};
 pub use local::LocalStorage;
 
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+/// Opens the storage backend selected by `config.data.storage.backend`.
+/// Only `"local"` exists today; the trait indirection is what lets a future
+/// shared or object-store backend slot in without touching `clean`/`smudge`.
+pub fn open_backend(
+    config: &crate::config::Config,
+    project_root: impl AsRef<Path>,
+) -> anyhow::Result<Box<dyn Storage>> {
+    match config.data.storage.backend.as_str() {
+        "local" | "toml" | "redb" => Ok(Box::new(LocalStorage::new(project_root)?)),
+        other => anyhow::bail!("Unknown storage backend: {}", other),
+    }
+}
+
+/// Like [`open_backend`], but encrypts with `crypto` instead of whatever
+/// [`crypto::Crypto::for_project`] would derive on its own -- for a caller
+/// (the agent) that already cached a cipher and wants `clean`/`smudge` to
+/// actually use it, rather than re-deriving one via `LocalStorage::new`.
+/// Has no effect when `config.data.encryption.enabled` is false: storage
+/// stays unencrypted, the same as `open_backend`.
+pub fn open_backend_with_crypto(
+    config: &crate::config::Config,
+    project_root: impl AsRef<Path>,
+    crypto: crypto::Crypto,
+) -> anyhow::Result<Box<dyn Storage>> {
+    match config.data.storage.backend.as_str() {
+        "local" | "toml" | "redb" => Ok(Box::new(LocalStorage::with_crypto(project_root, crypto)?)),
+        other => anyhow::bail!("Unknown storage backend: {}", other),
+    }
+}
+
+/// Builds the per-decoration part of a storage key from `kind` (`"inline"`,
+/// `"block"`, `"partial"`) and the decoration's *committed* text, rather
+/// than its line number. The committed text is the one thing that's
+/// identical whichever side parses it: on clean it comes straight off the
+/// `@whiteout:` annotation, and on smudge it's what the committed file
+/// actually contains for that slot, so hashing it (instead of `inline_{line}`
+/// etc.) gives a key that survives lines being inserted or removed above the
+/// decoration. Two decorations that share the same committed text naturally
+/// collapse onto the same entry.
+pub fn content_key(kind: &str, committed: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(committed.as_bytes());
+    let digest = hasher.finalize();
+    let hex: String = digest.iter().take(8).map(|b| format!("{:02x}", b)).collect();
+    format!("{}::{}", kind, hex)
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StorageEntry {
@@ -13,6 +69,11 @@ pub struct StorageEntry {
     pub key: String,
     pub value: String,
     pub encrypted: bool,
+    /// Whether `value` is zstd-compressed (and base64-encoded) rather than
+    /// the raw plaintext. Set by `LocalStorage::store_value` for entries
+    /// over `storage.compress_threshold`; older entries default to `false`.
+    #[serde(default)]
+    pub compressed: bool,
     pub timestamp: chrono::DateTime<chrono::Utc>,
 }
 
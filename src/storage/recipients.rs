@@ -0,0 +1,281 @@
+//! Envelope encryption for team-shared vaults: a random per-repo
+//! data-encryption key (DEK) seals every stored secret, and the DEK itself
+//! is wrapped once per recipient's X25519 public key. Onboarding a teammate
+//! only means rewrapping the DEK for their key, not re-encrypting any data.
+
+use aes_gcm::{
+    aead::{Aead, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+use crate::error::SecurityError;
+
+/// A local X25519 identity (private + public key), persisted so the same
+/// developer can keep unwrapping the DEK across sessions.
+pub struct Identity {
+    secret: StaticSecret,
+    public: PublicKey,
+}
+
+impl Identity {
+    pub fn generate() -> Self {
+        let secret = StaticSecret::random_from_rng(OsRng);
+        let public = PublicKey::from(&secret);
+        Self { secret, public }
+    }
+
+    pub fn public_key_base64(&self) -> String {
+        BASE64.encode(self.public.as_bytes())
+    }
+
+    fn identity_path(project_root: &Path) -> PathBuf {
+        project_root.join(".whiteout").join("identity")
+    }
+
+    /// Loads the local identity, generating and persisting a new one (with
+    /// 0600 permissions) the first time a project is opened.
+    pub fn load_or_create(project_root: &Path) -> Result<Self> {
+        let path = Self::identity_path(project_root);
+
+        if path.exists() {
+            let encoded = fs::read_to_string(&path).context("Failed to read identity file")?;
+            let bytes = BASE64
+                .decode(encoded.trim())
+                .context("Failed to decode identity file")?;
+            let key_bytes: [u8; 32] = bytes
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("Identity file is not a valid X25519 key"))?;
+            let secret = StaticSecret::from(key_bytes);
+            let public = PublicKey::from(&secret);
+            return Ok(Self { secret, public });
+        }
+
+        let identity = Self::generate();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create .whiteout directory")?;
+        }
+        fs::write(&path, BASE64.encode(identity.secret.to_bytes()))
+            .context("Failed to write identity file")?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = fs::metadata(&path)?.permissions();
+            perms.set_mode(0o600);
+            fs::set_permissions(&path, perms)?;
+        }
+
+        Ok(identity)
+    }
+}
+
+/// One recipient's entry in the keyring: their public key, and the DEK
+/// sealed under a key derived from an ephemeral X25519 exchange with them
+/// (the same shape as age's X25519 stanza).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecipientEntry {
+    pub public_key: String,
+    pub ephemeral_public_key: String,
+    pub wrapped_dek: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Keyring {
+    pub recipients: Vec<RecipientEntry>,
+}
+
+impl Keyring {
+    fn path(project_root: &Path) -> PathBuf {
+        project_root.join(".whiteout").join("keyring.toml")
+    }
+
+    pub fn load(project_root: &Path) -> Result<Self> {
+        let path = Self::path(project_root);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path).context("Failed to read keyring file")?;
+        toml::from_str(&content).context("Failed to parse keyring file")
+    }
+
+    pub fn save(&self, project_root: &Path) -> Result<()> {
+        let path = Self::path(project_root);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create .whiteout directory")?;
+        }
+        let content = toml::to_string_pretty(self).context("Failed to serialize keyring")?;
+        fs::write(&path, content).context("Failed to write keyring file")
+    }
+
+    /// Wraps `dek` for `recipient_public_key` using a fresh ephemeral
+    /// keypair, so the wrapping process never needs the recipient's secret.
+    pub fn add_recipient(&mut self, dek: &[u8; 32], recipient_public_key_b64: &str) -> Result<()> {
+        let recipient_bytes = BASE64
+            .decode(recipient_public_key_b64)
+            .context("Invalid recipient public key")?;
+        let recipient_bytes: [u8; 32] = recipient_bytes
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Recipient public key must be 32 bytes"))?;
+        let recipient_public = PublicKey::from(recipient_bytes);
+
+        let ephemeral_secret = StaticSecret::random_from_rng(OsRng);
+        let ephemeral_public = PublicKey::from(&ephemeral_secret);
+        let shared_secret = ephemeral_secret.diffie_hellman(&recipient_public);
+
+        let wrapped_dek = seal(shared_secret.as_bytes(), dek)?;
+
+        self.recipients.retain(|r| r.public_key != recipient_public_key_b64);
+        self.recipients.push(RecipientEntry {
+            public_key: recipient_public_key_b64.to_string(),
+            ephemeral_public_key: BASE64.encode(ephemeral_public.as_bytes()),
+            wrapped_dek,
+        });
+
+        Ok(())
+    }
+
+    pub fn remove_recipient(&mut self, public_key: &str) {
+        self.recipients.retain(|r| r.public_key != public_key);
+    }
+
+    /// Unwraps the DEK using `identity`'s secret key, matching against the
+    /// recipient entry whose public key was used to wrap it.
+    pub fn unwrap_dek(&self, identity: &Identity) -> Result<[u8; 32], SecurityError> {
+        let our_public = identity.public_key_base64();
+
+        let entry = self
+            .recipients
+            .iter()
+            .find(|r| r.public_key == our_public)
+            .ok_or(SecurityError::KeyDerivationFailed(
+                "content was not encrypted for you".to_string(),
+            ))?;
+
+        let ephemeral_bytes = BASE64
+            .decode(&entry.ephemeral_public_key)
+            .map_err(|_| SecurityError::InvalidSalt)?;
+        let ephemeral_bytes: [u8; 32] = ephemeral_bytes
+            .try_into()
+            .map_err(|_| SecurityError::InvalidSalt)?;
+        let ephemeral_public = PublicKey::from(ephemeral_bytes);
+
+        let shared_secret = identity.secret.diffie_hellman(&ephemeral_public);
+
+        unseal(shared_secret.as_bytes(), &entry.wrapped_dek)
+            .map_err(|e| SecurityError::KeyDerivationFailed(e.to_string()))
+    }
+
+    pub fn generate_dek() -> [u8; 32] {
+        let mut dek = [0u8; 32];
+        OsRng.fill_bytes(&mut dek);
+        dek
+    }
+}
+
+fn seal(key_bytes: &[u8; 32], plaintext: &[u8]) -> Result<String> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key_bytes));
+
+    let mut nonce_bytes = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce_bytes);
+    let nonce = Nonce::from_slice(&nonce_bytes);
+
+    let ciphertext = cipher
+        .encrypt(nonce, plaintext)
+        .map_err(|e| anyhow::anyhow!("Failed to wrap DEK: {}", e))?;
+
+    let mut combined = Vec::with_capacity(nonce_bytes.len() + ciphertext.len());
+    combined.extend_from_slice(&nonce_bytes);
+    combined.extend_from_slice(&ciphertext);
+
+    Ok(BASE64.encode(combined))
+}
+
+fn unseal(key_bytes: &[u8; 32], wrapped: &str) -> Result<[u8; 32]> {
+    let combined = BASE64.decode(wrapped).context("Invalid wrapped DEK encoding")?;
+    if combined.len() < 12 {
+        anyhow::bail!("Wrapped DEK is too short");
+    }
+
+    let (nonce_bytes, ciphertext) = combined.split_at(12);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key_bytes));
+    let nonce = Nonce::from_slice(nonce_bytes);
+
+    let dek = cipher
+        .decrypt(nonce, ciphertext)
+        .map_err(|e| anyhow::anyhow!("Failed to unwrap DEK: {}", e))?;
+
+    dek.try_into()
+        .map_err(|_| anyhow::anyhow!("Unwrapped DEK has unexpected length"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_add_and_unwrap_recipient() -> Result<()> {
+        let identity = Identity::generate();
+        let dek = Keyring::generate_dek();
+
+        let mut keyring = Keyring::default();
+        keyring.add_recipient(&dek, &identity.public_key_base64())?;
+
+        let unwrapped = keyring.unwrap_dek(&identity).map_err(|e| anyhow::anyhow!(e))?;
+        assert_eq!(unwrapped, dek);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unwrap_fails_for_wrong_identity() -> Result<()> {
+        let identity = Identity::generate();
+        let stranger = Identity::generate();
+        let dek = Keyring::generate_dek();
+
+        let mut keyring = Keyring::default();
+        keyring.add_recipient(&dek, &identity.public_key_base64())?;
+
+        assert!(keyring.unwrap_dek(&stranger).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_recipient() -> Result<()> {
+        let identity = Identity::generate();
+        let dek = Keyring::generate_dek();
+
+        let mut keyring = Keyring::default();
+        keyring.add_recipient(&dek, &identity.public_key_base64())?;
+        keyring.remove_recipient(&identity.public_key_base64());
+
+        assert!(keyring.unwrap_dek(&identity).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_keyring_roundtrip_through_disk() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let identity = Identity::generate();
+        let dek = Keyring::generate_dek();
+
+        let mut keyring = Keyring::default();
+        keyring.add_recipient(&dek, &identity.public_key_base64())?;
+        keyring.save(temp_dir.path())?;
+
+        let loaded = Keyring::load(temp_dir.path())?;
+        let unwrapped = loaded.unwrap_dek(&identity).map_err(|e| anyhow::anyhow!(e))?;
+        assert_eq!(unwrapped, dek);
+
+        Ok(())
+    }
+}
@@ -1,78 +1,144 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use std::path::Path;
 
 use crate::{
     config::Config,
+    gitattributes::{self, State},
     parser::{Decoration, Parser},
-    storage::LocalStorage,
+    storage::{cache, content_key, Storage},
+    transform::registry::{self, Registry},
 };
 
 pub fn apply(
     content: &str,
     file_path: &Path,
-    storage: &LocalStorage,
-    _config: &Config,
+    storage: &dyn Storage,
+    config: &Config,
+    registry: &Registry,
 ) -> Result<String> {
     // Clean filter stores local values and returns content with committed values
     // but preserves decoration markers so smudge can work later
-    
+
+    if !config.matches(file_path)? {
+        return Ok(content.to_string());
+    }
+
+    // A hand-edited `.gitattributes` entry for this path overrides the
+    // `[patterns]`-derived decision above.
+    let project_root = config.path.parent().and_then(Path::parent).unwrap_or_else(|| Path::new("."));
+    if let Some(state) = gitattributes::filter_state_for(project_root, file_path)? {
+        if !matches!(state, State::Value(ref filter) if filter == "whiteout") {
+            return Ok(content.to_string());
+        }
+    }
+
     let parser = Parser::new();
-    let decorations = parser.parse(content)?;
-    
+    let mut decorations = parser.parse(content, Some(file_path))?;
+
     if decorations.is_empty() {
         return Ok(content.to_string());
     }
-    
-    // Store all local values
-    for decoration in &decorations {
+
+    // A committed value written as `name(args)` for a registered provider
+    // is resolved to whatever that provider's `clean` produces, rather
+    // than emitted literally -- `env-var(API_KEY)` becomes
+    // `std::env::var("API_KEY").unwrap_or_default()`, say. Anything that
+    // doesn't parse as a call, or names a provider that isn't registered,
+    // is left exactly as written, so decorations predating providers are
+    // unaffected. Store all local values keyed by a hash of the resulting
+    // committed text rather than line number, so the entry isn't orphaned
+    // by a later edit that shifts the decoration up or down the file (see
+    // `storage::content_key`); this runs after the provider resolves so
+    // `smudge`, which only ever sees the resolved text in the committed
+    // file, looks the value up under the same key.
+    for decoration in decorations.iter_mut() {
         match decoration {
-            Decoration::Inline { line, local_value, .. } => {
-                storage.store_value(
-                    file_path,
-                    &format!("inline_{}", line),
-                    local_value,
-                )?;
+            Decoration::Inline { local_value, committed_value, .. } => {
+                resolve_committed_value(registry, local_value, committed_value)?;
+                let blob = storage.blob_ref(file_path, &content_key("inline", committed_value));
+                storage.blob_put(&blob, local_value.as_bytes())?;
             }
-            Decoration::Block { start_line, local_content, .. } => {
-                storage.store_value(
-                    file_path,
-                    &format!("block_{}", start_line),
-                    local_content,
-                )?;
+            Decoration::Block { local_content, committed_content, .. } => {
+                resolve_committed_value(registry, local_content, committed_content)?;
+                let blob = storage.blob_ref(file_path, &content_key("block", committed_content));
+                storage.blob_put(&blob, local_content.as_bytes())?;
             }
-            Decoration::Partial { line, replacements } => {
-                for (idx, replacement) in replacements.iter().enumerate() {
-                    storage.store_value(
-                        file_path,
-                        &format!("partial_{}_{}", line, idx),
-                        &replacement.local_value,
-                    )?;
+            Decoration::Partial { replacements, .. } => {
+                // Only the original two-alternative, unnamed
+                // `[[local||committed]]` form gets the storage-backed
+                // secret-hiding treatment -- a multi-environment profile
+                // (named alternatives, or more than two of them) has
+                // nothing to hide, so `apply_decorations` preserves the
+                // whole `[[...]]` structure untouched instead.
+                for replacement in replacements.iter_mut().filter(|r| r.is_legacy_pair()) {
+                    let mut committed_value = replacement.committed_value().to_string();
+                    resolve_committed_value(registry, replacement.local_value(), &mut committed_value)?;
+
+                    let blob = storage.blob_ref(file_path, &content_key("partial", &committed_value));
+                    storage.blob_put(&blob, replacement.local_value().as_bytes())?;
+
+                    replacement.alternatives[1].value = committed_value;
                 }
             }
         }
     }
-    
+
     // Apply transformations to remove local values and keep only committed values
     // This is what gets stored in Git
-    let cleaned = parser.apply_decorations(content, &decorations, false);
+    let cleaned = parser.apply_decorations(content, &decorations, false, Some(file_path), None);
+
+    // Cache the full original content, keyed by a hash of the cleaned
+    // output, so `smudge` can restore it in one read on a future checkout
+    // instead of re-deriving every decoration from storage.
+    cache::store(project_root, &cleaned, content)?;
+
     Ok(cleaned)
 }
 
+/// If `committed` parses as a registered provider call (`name(args)`),
+/// replaces it with that provider's `clean(local, args)` output. Leaves
+/// `committed` untouched otherwise -- unparseable text, or a provider name
+/// that isn't registered, both mean "take the literal text as-is", which
+/// is what every decoration written before providers existed already does.
+fn resolve_committed_value(registry: &Registry, local: &str, committed: &mut String) -> Result<()> {
+    if let Some((name, args)) = registry::parse_call(committed) {
+        if let Some(provider) = registry.get(name) {
+            *committed = provider
+                .clean(local, args)
+                .with_context(|| format!("transform '{}' failed", name))?;
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::storage::LocalStorage;
+    use crate::transform::registry::Registry;
     use tempfile::TempDir;
 
+    /// `Config::default()` rooted at `root` instead of the bare
+    /// relative `.whiteout/config.toml` -- needed now that clean/smudge
+    /// write a content-addressed cache under the project root, so tests
+    /// don't escape their `TempDir` and touch the real working directory.
+    fn rooted_config(root: &Path) -> Config {
+        let mut config = Config::default();
+        config.path = root.join(".whiteout").join("config.toml");
+        config
+    }
+
     #[test]
     fn test_clean_inline() -> Result<()> {
         let temp_dir = TempDir::new()?;
         let storage = LocalStorage::new(temp_dir.path())?;
-        let config = Config::default();
+        let config = rooted_config(temp_dir.path());
+        let registry = Registry::new(None);
         
         let content = r#"let api_key = "sk-12345"; // @whiteout: "ENV_VAR""#;
         let file_path = Path::new("test.rs");
         
-        let cleaned = apply(content, file_path, &storage, &config)?;
+        let cleaned = apply(content, file_path, &storage, &config, &registry)?;
         assert!(cleaned.contains("ENV_VAR"));
         assert!(!cleaned.contains("sk-12345"));
         
@@ -83,7 +149,8 @@ mod tests {
     fn test_clean_block() -> Result<()> {
         let temp_dir = TempDir::new()?;
         let storage = LocalStorage::new(temp_dir.path())?;
-        let config = Config::default();
+        let config = rooted_config(temp_dir.path());
+        let registry = Registry::new(None);
         
         let content = r#"
 // @whiteout-start
@@ -93,10 +160,113 @@ const DEBUG = false;
 "#;
         let file_path = Path::new("test.rs");
         
-        let cleaned = apply(content, file_path, &storage, &config)?;
+        let cleaned = apply(content, file_path, &storage, &config, &registry)?;
         assert!(cleaned.contains("false"));
         assert!(!cleaned.contains("true"));
-        
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_clean_inline_python_uses_hash_comment() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let storage = LocalStorage::new(temp_dir.path())?;
+        let config = rooted_config(temp_dir.path());
+        let registry = Registry::new(None);
+
+        let content = "api_key = \"sk-12345\" # @whiteout: load_from_env()\n";
+        let file_path = Path::new("settings.py");
+
+        let cleaned = apply(content, file_path, &storage, &config, &registry)?;
+        assert!(cleaned.contains("load_from_env()"));
+        assert!(!cleaned.contains("sk-12345"));
+        // Re-stamped with a Python comment, not a `//` one.
+        assert!(cleaned.contains("# @whiteout:"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_clean_and_smudge_round_trip_with_multiple_decorations_per_line() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let storage = LocalStorage::new(temp_dir.path())?;
+        let config = rooted_config(temp_dir.path());
+        let registry = Registry::new(None);
+        let file_path = Path::new("test.rs");
+
+        let content = r#"{"a": "sk-111"} // @whiteout: "ENV_A" {"b": "sk-222"} // @whiteout: "ENV_B""#;
+
+        let cleaned = apply(content, file_path, &storage, &config, &registry)?;
+        assert!(!cleaned.contains("sk-111"));
+        assert!(!cleaned.contains("sk-222"));
+        assert!(cleaned.contains("ENV_A"));
+        assert!(cleaned.contains("ENV_B"));
+
+        let smudged = crate::transform::smudge::apply(&cleaned, file_path, &storage, &config, &registry)?;
+        assert!(smudged.contains("sk-111"));
+        assert!(smudged.contains("sk-222"));
+        // Each local value landed back at its own occurrence's position.
+        assert!(smudged.find("sk-111").unwrap() < smudged.find("sk-222").unwrap());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_clean_dispatches_env_var_provider_call() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let storage = LocalStorage::new(temp_dir.path())?;
+        let config = rooted_config(temp_dir.path());
+        let registry = Registry::new(None);
+        let file_path = Path::new("test.rs");
+
+        let content = r#"let api_key = "sk-12345"; // @whiteout: env-var(API_KEY)"#;
+
+        let cleaned = apply(content, file_path, &storage, &config, &registry)?;
+        assert!(!cleaned.contains("sk-12345"));
+        assert!(cleaned.contains(r#"std::env::var("API_KEY")"#));
+
+        let smudged = crate::transform::smudge::apply(&cleaned, file_path, &storage, &config, &registry)?;
+        assert!(smudged.contains("sk-12345"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_clean_preserves_full_structure_of_named_profile_partial() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let storage = LocalStorage::new(temp_dir.path())?;
+        let config = rooted_config(temp_dir.path());
+        let registry = Registry::new(None);
+        let file_path = Path::new("test.rs");
+
+        let content = r#"let host = "[[dev=localhost||staging=staging.example.com||prod=api.example.com]]"; // @whiteout-partial"#;
+
+        let cleaned = apply(content, file_path, &storage, &config, &registry)?;
+        // Clean doesn't hide anything for a multi-environment profile --
+        // every alternative survives into the committed text unchanged.
+        assert!(cleaned.contains("dev=localhost"));
+        assert!(cleaned.contains("staging=staging.example.com"));
+        assert!(cleaned.contains("prod=api.example.com"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_clean_leaves_unregistered_provider_call_literal() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let storage = LocalStorage::new(temp_dir.path())?;
+        let config = rooted_config(temp_dir.path());
+        let registry = Registry::new(None);
+        let file_path = Path::new("test.rs");
+
+        // "load_from_env" parses as a call but isn't a registered
+        // provider, so it must be taken as literal text, same as before
+        // providers existed.
+        let content = r#"let api_key = "sk-12345"; // @whiteout: load_from_env()"#;
+
+        let cleaned = apply(content, file_path, &storage, &config, &registry)?;
+        assert!(cleaned.contains("load_from_env()"));
+
         Ok(())
     }
 }
\ No newline at end of file
@@ -1,25 +1,29 @@
 pub mod clean;
+pub mod registry;
 pub mod smudge;
 
 use anyhow::Result;
 use std::path::Path;
 
-use crate::{config::Config, storage::LocalStorage};
+use crate::{config::Config, storage::Storage};
+use registry::Registry;
 
 pub fn clean(
     content: &str,
     file_path: &Path,
-    storage: &LocalStorage,
+    storage: &dyn Storage,
     config: &Config,
+    registry: &Registry,
 ) -> Result<String> {
-    clean::apply(content, file_path, storage, config)
+    clean::apply(content, file_path, storage, config, registry)
 }
 
 pub fn smudge(
     content: &str,
     file_path: &Path,
-    storage: &LocalStorage,
+    storage: &dyn Storage,
     config: &Config,
+    registry: &Registry,
 ) -> Result<String> {
-    smudge::apply(content, file_path, storage, config)
-}
\ No newline at end of file
+    smudge::apply(content, file_path, storage, config, registry)
+}
@@ -0,0 +1,184 @@
+//! Pluggable redaction-strategy providers for `@whiteout:` decorations.
+//!
+//! `clean`/`smudge` used to hardcode how a decoration's local value becomes
+//! its committed placeholder: whatever text the user wrote after
+//! `@whiteout:` was emitted verbatim. A [`Registry`] lets a decoration
+//! instead name a *provider* -- `@whiteout: env-var(API_KEY)` rather than
+//! `@whiteout: std::env::var("API_KEY").unwrap_or_default()` -- which
+//! `clean` resolves by calling the matching [`Transform`]. Plain text that
+//! doesn't parse as a call, or names a provider that isn't registered,
+//! keeps today's literal behavior untouched.
+
+use anyhow::Result;
+use std::collections::HashMap;
+
+/// A named strategy for turning a decoration's captured local value into
+/// its committed placeholder, and (where the transform is reversible)
+/// back. `clean` calls `clean`; `smudge` falls back to `smudge` only when
+/// no stored original value is found for the decoration.
+pub trait Transform: std::fmt::Debug + Send + Sync {
+    /// Produces the committed placeholder for `captured` (the real local
+    /// value), given the call's `args`.
+    fn clean(&self, captured: &str, args: &str) -> Result<String>;
+    /// Produces what should appear locally for `placeholder` (the
+    /// committed text currently in the file), given the call's `args`.
+    /// Most providers can't actually reverse themselves without the
+    /// original value from storage, so this is a best-effort fallback for
+    /// when storage has no entry at all, not the primary restore path.
+    fn smudge(&self, placeholder: &str, args: &str) -> Result<String>;
+}
+
+/// Passes the value through unchanged in both directions -- what every
+/// decoration already did before providers existed.
+#[derive(Debug)]
+struct IdentityTransform;
+
+impl Transform for IdentityTransform {
+    fn clean(&self, captured: &str, _args: &str) -> Result<String> {
+        Ok(captured.to_string())
+    }
+
+    fn smudge(&self, placeholder: &str, _args: &str) -> Result<String> {
+        Ok(placeholder.to_string())
+    }
+}
+
+/// Replaces the committed placeholder with a fixed marker rather than any
+/// derivative of the real value, for decorations that shouldn't leak even
+/// a transformed form of the secret into Git history.
+#[derive(Debug)]
+struct RedactTransform;
+
+impl Transform for RedactTransform {
+    fn clean(&self, _captured: &str, _args: &str) -> Result<String> {
+        Ok("[REDACTED]".to_string())
+    }
+
+    fn smudge(&self, placeholder: &str, _args: &str) -> Result<String> {
+        // Irreversible by design; the real value can only come from storage.
+        Ok(placeholder.to_string())
+    }
+}
+
+/// Formalizes the `@whiteout: load_from_env()`-style idiom users already
+/// write by hand: `@whiteout: env-var(API_KEY)` commits code that reads
+/// `API_KEY` from the environment instead.
+#[derive(Debug)]
+struct EnvVarTransform;
+
+impl Transform for EnvVarTransform {
+    fn clean(&self, _captured: &str, args: &str) -> Result<String> {
+        if args.trim().is_empty() {
+            anyhow::bail!("env-var transform requires an environment variable name, e.g. env-var(API_KEY)");
+        }
+        Ok(format!(r#"std::env::var("{}").unwrap_or_default()"#, args.trim()))
+    }
+
+    fn smudge(&self, placeholder: &str, _args: &str) -> Result<String> {
+        Ok(placeholder.to_string())
+    }
+}
+
+/// Parses `text` as a provider call `name(args)`, returning `(name, args)`.
+/// Plain literal text -- `"ENV_VAR"`, `"load_from_env()"` when
+/// `load_from_env` isn't registered, `"https://api.example.com"` -- simply
+/// doesn't match, which is what keeps this dispatch backward compatible
+/// with every decoration written before providers existed.
+pub fn parse_call(text: &str) -> Option<(&str, &str)> {
+    let text = text.trim();
+    let open = text.find('(')?;
+    if !text.ends_with(')') {
+        return None;
+    }
+    let name = &text[..open];
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return None;
+    }
+    Some((name, &text[open + 1..text.len() - 1]))
+}
+
+/// The set of `Transform` providers `clean`/`smudge` dispatch to by name.
+#[derive(Debug)]
+pub struct Registry {
+    providers: HashMap<String, Box<dyn Transform>>,
+}
+
+impl Registry {
+    /// Populates the registry with the built-in providers, then drops any
+    /// not named in `enabled` when it's `Some` -- `[transform]
+    /// enabled_providers` in `Config`, so a project can pare the set down
+    /// to just what it actually uses.
+    pub fn new(enabled: Option<&[String]>) -> Self {
+        let mut providers: HashMap<String, Box<dyn Transform>> = HashMap::new();
+        providers.insert("identity".to_string(), Box::new(IdentityTransform));
+        providers.insert("redact".to_string(), Box::new(RedactTransform));
+        providers.insert("env-var".to_string(), Box::new(EnvVarTransform));
+
+        if let Some(enabled) = enabled {
+            providers.retain(|name, _| enabled.iter().any(|e| e == name));
+        }
+
+        Self { providers }
+    }
+
+    /// Registers (or replaces) a provider under `name`.
+    pub fn register(&mut self, name: impl Into<String>, provider: Box<dyn Transform>) {
+        self.providers.insert(name.into(), provider);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Transform> {
+        self.providers.get(name).map(|boxed| boxed.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_call_recognizes_name_and_args() {
+        assert_eq!(parse_call("env-var(API_KEY)"), Some(("env-var", "API_KEY")));
+        assert_eq!(parse_call("redact()"), Some(("redact", "")));
+    }
+
+    #[test]
+    fn test_parse_call_rejects_plain_text() {
+        assert_eq!(parse_call("ENV_VAR"), None);
+        assert_eq!(parse_call("https://api.example.com"), None);
+        assert_eq!(parse_call("blue"), None);
+    }
+
+    #[test]
+    fn test_unregistered_provider_name_is_not_dispatched() {
+        // "load_from_env()" parses as a call, but isn't a built-in, so a
+        // decoration written this way (a common idiom predating providers)
+        // must not suddenly dispatch to anything.
+        let (name, _args) = parse_call("load_from_env()").unwrap();
+        let registry = Registry::new(None);
+        assert!(registry.get(name).is_none());
+    }
+
+    #[test]
+    fn test_env_var_transform_generates_committed_code() {
+        let registry = Registry::new(None);
+        let provider = registry.get("env-var").unwrap();
+        let committed = provider.clean("sk-12345", "API_KEY").unwrap();
+        assert_eq!(committed, r#"std::env::var("API_KEY").unwrap_or_default()"#);
+    }
+
+    #[test]
+    fn test_redact_transform_never_echoes_the_captured_value() {
+        let registry = Registry::new(None);
+        let provider = registry.get("redact").unwrap();
+        let committed = provider.clean("sk-12345", "").unwrap();
+        assert_eq!(committed, "[REDACTED]");
+        assert!(!committed.contains("sk-12345"));
+    }
+
+    #[test]
+    fn test_enabled_list_narrows_the_registry() {
+        let registry = Registry::new(Some(&["identity".to_string()]));
+        assert!(registry.get("identity").is_some());
+        assert!(registry.get("redact").is_none());
+    }
+}
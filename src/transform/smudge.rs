@@ -1,89 +1,240 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
 use std::path::Path;
 
 use crate::{
     config::Config,
+    gitattributes::{self, State},
     parser::{Decoration, Parser},
-    storage::LocalStorage,
+    storage::{cache, content_key, Storage},
+    transform::registry::{self, Registry},
 };
 
+fn fetch_stored(storage: &dyn Storage, file_path: &Path, key: &str) -> Option<String> {
+    let blob = storage.blob_ref(file_path, key);
+    storage
+        .blob_fetch(&blob)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+}
+
+/// Looks a value up by its content-addressed key first, falling back to the
+/// pre-`content_key` line-numbered key (`inline_{line}` etc.) so entries
+/// written by older versions of `clean` are still found after the upgrade.
+fn fetch_stored_migrating(
+    storage: &dyn Storage,
+    file_path: &Path,
+    kind: &str,
+    committed: &str,
+    legacy_key: &str,
+) -> Option<String> {
+    fetch_stored(storage, file_path, &content_key(kind, committed))
+        .or_else(|| fetch_stored(storage, file_path, legacy_key))
+}
+
+/// Last resort when nothing was ever stored for this decoration (e.g. a
+/// fresh clone with no `local.toml` entry): if `committed` parses as a
+/// registered provider call, ask that provider to reconstruct a local
+/// value from it. Most providers can't actually reverse themselves, so
+/// this usually just returns the committed text unchanged, same as
+/// today's behavior on a storage miss.
+fn fallback_via_registry(registry: &Registry, committed: &str) -> Option<String> {
+    let (name, args) = registry::parse_call(committed)?;
+    registry.get(name)?.smudge(committed, args).ok()
+}
+
+/// Rejects an explicit `profile` that doesn't name any alternative on some
+/// multi-environment `Partial` replacement in `decorations`, instead of
+/// letting `apply_decorations` silently fall back to the first alternative.
+/// A typo'd or stale `WHITEOUT_PROFILE` (e.g. `stagign` instead of
+/// `staging`) would otherwise materialize the wrong environment's value
+/// into the working tree with no indication anything went wrong. `profile:
+/// None` (no `WHITEOUT_PROFILE` set) always passes -- falling back to the
+/// first alternative is the documented default, not an error.
+fn reject_unmatched_profile(decorations: &[Decoration], profile: Option<&str>) -> Result<()> {
+    let Some(profile) = profile else { return Ok(()) };
+
+    for decoration in decorations {
+        if let Decoration::Partial { replacements, .. } = decoration {
+            for replacement in replacements {
+                if !replacement.is_legacy_pair() && replacement.select(Some(profile)).is_none() {
+                    bail!(
+                        "WHITEOUT_PROFILE={} doesn't match any alternative in {}",
+                        profile,
+                        replacement.render(),
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
 pub fn apply(
     content: &str,
     file_path: &Path,
-    storage: &LocalStorage,
-    _config: &Config,
+    storage: &dyn Storage,
+    config: &Config,
+    registry: &Registry,
 ) -> Result<String> {
+    if !config.matches(file_path)? {
+        return Ok(content.to_string());
+    }
+
+    // A hand-edited `.gitattributes` entry for this path overrides the
+    // `[patterns]`-derived decision above.
+    let project_root = config.path.parent().and_then(Path::parent).unwrap_or_else(|| Path::new("."));
+    if let Some(state) = gitattributes::filter_state_for(project_root, file_path)? {
+        if !matches!(state, State::Value(ref filter) if filter == "whiteout") {
+            return Ok(content.to_string());
+        }
+    }
+
+    // The content-addressed cache restores in one read when `clean` has
+    // already seen this exact cleaned content before, skipping decoration
+    // parsing and a storage lookup per decoration entirely. A miss (new
+    // clone, cache wiped, cleaned content edited since) falls through to
+    // the decoration-driven path below exactly as before this cache existed.
+    if let Some(cached) = cache::lookup(project_root, content)? {
+        return Ok(cached);
+    }
+
     let parser = Parser::new();
-    let mut decorations = parser.parse(content)?;
-    
+    let mut decorations = parser.parse(content, Some(file_path))?;
+
     if decorations.is_empty() {
         return Ok(content.to_string());
     }
-    
+
     for decoration in &mut decorations {
         match decoration {
-            Decoration::Inline { line, local_value, .. } => {
-                if let Ok(stored_value) = storage.get_value(
-                    file_path,
-                    &format!("inline_{}", line),
-                ) {
+            Decoration::Inline { line, occurrence, local_value, committed_value } => {
+                // Occurrence 0 keeps the exact legacy key pre-dating this
+                // decoration's `occurrence` field, so a single-decoration
+                // line (the overwhelming common case) still finds a value
+                // stored by an older version of `clean`. Later occurrences
+                // on the same line are new, so they get a unique suffix.
+                let legacy_key = if *occurrence == 0 {
+                    format!("inline_{}", line)
+                } else {
+                    format!("inline_{}_{}", line, occurrence)
+                };
+                if let Some(stored_value) =
+                    fetch_stored_migrating(storage, file_path, "inline", committed_value, &legacy_key)
+                {
                     *local_value = stored_value;
+                } else if let Some(fallback) = fallback_via_registry(registry, committed_value) {
+                    *local_value = fallback;
                 }
             }
-            Decoration::Block { start_line, local_content, .. } => {
-                if let Ok(stored_value) = storage.get_value(
-                    file_path,
-                    &format!("block_{}", start_line),
-                ) {
+            Decoration::Block { start_line, local_content, committed_content, .. } => {
+                let legacy_key = format!("block_{}", start_line);
+                if let Some(stored_value) =
+                    fetch_stored_migrating(storage, file_path, "block", committed_content, &legacy_key)
+                {
                     *local_content = stored_value;
+                } else if let Some(fallback) = fallback_via_registry(registry, committed_content) {
+                    *local_content = fallback;
                 }
             }
             Decoration::Partial { line, replacements } => {
-                for (idx, replacement) in replacements.iter_mut().enumerate() {
-                    if let Ok(stored_value) = storage.get_value(
-                        file_path,
-                        &format!("partial_{}_{}", line, idx),
-                    ) {
-                        replacement.local_value = stored_value;
+                // A multi-environment profile (named alternatives, or more
+                // than two of them) was never written to storage by clean
+                // -- the committed text already carries every alternative
+                // it needs, so there's nothing to restore here.
+                for (idx, replacement) in replacements.iter_mut().enumerate().filter(|(_, r)| r.is_legacy_pair()) {
+                    let legacy_key = format!("partial_{}_{}", line, idx);
+                    let committed_value = replacement.committed_value().to_string();
+                    if let Some(stored_value) =
+                        fetch_stored_migrating(storage, file_path, "partial", &committed_value, &legacy_key)
+                    {
+                        replacement.alternatives[0].value = stored_value;
+                    } else if let Some(fallback) = fallback_via_registry(registry, &committed_value) {
+                        replacement.alternatives[0].value = fallback;
                     }
                 }
             }
         }
     }
     
-    let smudged = parser.apply_decorations(content, &decorations, true);
-    
+    // Selects which alternative a multi-environment `Partial` replacement
+    // smudges in as, the same way `WHITEOUT_KEY`/`WHITEOUT_PINENTRY`/
+    // `WHITEOUT_AGENT_TIMEOUT` pass other per-checkout configuration
+    // through to the filter driver, which git invokes with no room for
+    // extra flags. Unset falls back to each replacement's first
+    // alternative; a name that matches nothing is rejected below instead.
+    let active_profile = std::env::var("WHITEOUT_PROFILE").ok();
+    reject_unmatched_profile(&decorations, active_profile.as_deref())?;
+    let smudged = parser.apply_decorations(content, &decorations, true, Some(file_path), active_profile.as_deref());
+
     Ok(smudged)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::storage::LocalStorage;
+    use crate::transform::registry::Registry;
     use tempfile::TempDir;
 
+    /// `Config::default()` rooted at `root` instead of the bare
+    /// relative `.whiteout/config.toml` -- needed now that clean/smudge
+    /// write a content-addressed cache under the project root, so tests
+    /// don't escape their `TempDir` and touch the real working directory.
+    fn rooted_config(root: &Path) -> Config {
+        let mut config = Config::default();
+        config.path = root.join(".whiteout").join("config.toml");
+        config
+    }
+
     #[test]
     fn test_smudge_inline() -> Result<()> {
         let temp_dir = TempDir::new()?;
         let storage = LocalStorage::new(temp_dir.path())?;
-        let config = Config::default();
+        let config = rooted_config(temp_dir.path());
+        let registry = Registry::new(None);
         let file_path = Path::new("test.rs");
         
         storage.store_value(file_path, "inline_1", "let api_key = \"sk-12345\";")?;
         
         let content = r#"let api_key = "ENV_VAR"; // @whiteout: "ENV_VAR""#;
         
-        let smudged = apply(content, file_path, &storage, &config)?;
+        let smudged = apply(content, file_path, &storage, &config, &registry)?;
         assert!(smudged.contains("sk-12345"));
         assert!(!smudged.contains("ENV_VAR"));
         
         Ok(())
     }
 
+    #[test]
+    fn test_smudge_inline_survives_line_renumbering() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let storage = LocalStorage::new(temp_dir.path())?;
+        let config = rooted_config(temp_dir.path());
+        let registry = Registry::new(None);
+        let file_path = Path::new("test.rs");
+
+        // Stored the way `clean` does today: keyed by a hash of the
+        // committed text, not by the line it happened to be on.
+        let key = crate::storage::content_key("inline", "process.env.API_KEY");
+        storage.store_value(file_path, &key, "let api_key = \"sk-12345\";")?;
+
+        // A line was inserted above the decoration since it was cleaned, so
+        // the committed file's decoration now lives on line 2 instead of 1.
+        let content = "// a comment added later\nlet api_key = \"process.env.API_KEY\"; // @whiteout: process.env.API_KEY\n";
+
+        let smudged = apply(content, file_path, &storage, &config, &registry)?;
+        assert!(smudged.contains("sk-12345"));
+
+        Ok(())
+    }
+
     #[test]
     fn test_smudge_block() -> Result<()> {
         let temp_dir = TempDir::new()?;
         let storage = LocalStorage::new(temp_dir.path())?;
-        let config = Config::default();
+        let config = rooted_config(temp_dir.path());
+        let registry = Registry::new(None);
         let file_path = Path::new("test.rs");
         
         storage.store_value(file_path, "block_2", "const DEBUG = true;")?;
@@ -95,9 +246,101 @@ const DEBUG = false;
 const DEBUG = false;
 "#;
         
-        let smudged = apply(content, file_path, &storage, &config)?;
+        let smudged = apply(content, file_path, &storage, &config, &registry)?;
         assert!(smudged.contains("true"));
-        
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_smudge_html_block_uses_block_comment_markers() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let storage = LocalStorage::new(temp_dir.path())?;
+        let config = rooted_config(temp_dir.path());
+        let registry = Registry::new(None);
+        let file_path = Path::new("index.html");
+
+        storage.store_value(file_path, "block_2", "<script src=\"http://localhost:8080/debug.js\"></script>")?;
+
+        let content = "\n<!-- @whiteout-start -->\n<script></script>\n<!-- @whiteout-end -->\n<script></script>\n";
+
+        let smudged = apply(content, file_path, &storage, &config, &registry)?;
+        assert!(smudged.contains("localhost:8080"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_smudge_defaults_to_first_alternative_without_active_profile() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let storage = LocalStorage::new(temp_dir.path())?;
+        let config = rooted_config(temp_dir.path());
+        let registry = Registry::new(None);
+        let file_path = Path::new("test.rs");
+
+        let content = r#"let host = "[[dev=localhost||prod=api.example.com]]"; // @whiteout-partial"#;
+
+        let smudged = apply(content, file_path, &storage, &config, &registry)?;
+        assert!(smudged.contains("localhost"));
+        assert!(!smudged.contains("api.example.com"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_unmatched_profile_passes_through_none_and_matching_profile() -> Result<()> {
+        let parser = Parser::new();
+        let content = r#"let host = "[[dev=localhost||prod=api.example.com]]"; // @whiteout-partial"#;
+        let decorations = parser.parse(content, Some(Path::new("test.rs")))?;
+
+        reject_unmatched_profile(&decorations, None)?;
+        reject_unmatched_profile(&decorations, Some("prod"))?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_unmatched_profile_errors_on_unknown_profile_name() -> Result<()> {
+        let parser = Parser::new();
+        let content = r#"let host = "[[dev=localhost||prod=api.example.com]]"; // @whiteout-partial"#;
+        let decorations = parser.parse(content, Some(Path::new("test.rs")))?;
+
+        let error = reject_unmatched_profile(&decorations, Some("stagign")).unwrap_err();
+        assert!(error.to_string().contains("stagign"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_unmatched_profile_ignores_legacy_pair() -> Result<()> {
+        let parser = Parser::new();
+        let content = r#"let host = "[[local||committed]]"; // @whiteout-partial"#;
+        let decorations = parser.parse(content, Some(Path::new("test.rs")))?;
+
+        // A legacy `[[local||committed]]` pair has no named alternatives to
+        // match against -- any profile value is irrelevant to it.
+        reject_unmatched_profile(&decorations, Some("prod"))?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_smudge_restores_from_cache_without_touching_storage() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let storage = LocalStorage::new(temp_dir.path())?;
+        let config = rooted_config(temp_dir.path());
+        let registry = Registry::new(None);
+        let file_path = Path::new("test.rs");
+
+        let original = r#"let api_key = "sk-12345"; // @whiteout: "ENV_VAR""#;
+        let cleaned = crate::transform::clean::apply(original, file_path, &storage, &config, &registry)?;
+
+        // A fresh storage with nothing in it: the decoration-driven path
+        // alone couldn't restore anything here.
+        let empty_storage = LocalStorage::new(TempDir::new()?.path())?;
+        let smudged = apply(&cleaned, file_path, &empty_storage, &config, &registry)?;
+        assert_eq!(smudged, original);
+
         Ok(())
     }
 }
\ No newline at end of file
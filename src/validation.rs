@@ -1,4 +1,5 @@
 use crate::error::{SecurityError, WhiteoutError, WhiteoutResult};
+use anyhow::Result;
 use once_cell::sync::Lazy;
 use regex::Regex;
 use std::path::{Path, PathBuf};
@@ -20,14 +21,63 @@ static SUSPICIOUS_PATTERN: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r"(?i)(exec|eval|system|shell|cmd|powershell|bash|sh\s)").expect("Failed to compile suspicious pattern")
 });
 
+/// Heuristic patterns for things that look like secrets, shared by the
+/// `check`/`scan` CLI commands and [`crate::parser::audit`] so they all
+/// report the same findings for the same content.
+pub const SECRET_PATTERNS: &[(&str, &str)] = &[
+    (r"(?i)(api[_-]?key|apikey)", "API Key"),
+    (r"(?i)(secret|password|passwd|pwd)", "Secret/Password"),
+    (r"(?i)(token|bearer)", "Token"),
+    (r"(?i)sk-[a-zA-Z0-9]{32,}", "OpenAI API Key"),
+    (r"(?i)ghp_[a-zA-Z0-9]{36}", "GitHub Token"),
+    (r"https?://[^/]*:[^@]*@", "URL with credentials"),
+];
+
+/// A single potential-secret match found by [`find_secrets`].
+pub struct SecretFinding {
+    pub line: usize,
+    pub name: &'static str,
+    pub text: String,
+}
+
+/// Scans `content` for anything matching [`SECRET_PATTERNS`], skipping
+/// lines that are already whiteout-decorated.
+pub fn find_secrets(content: &str) -> Result<Vec<SecretFinding>> {
+    let mut findings = Vec::new();
+    for (pattern_str, name) in SECRET_PATTERNS {
+        let regex = Regex::new(pattern_str)?;
+        for (line_num, line) in content.lines().enumerate() {
+            if line.contains("@whiteout") {
+                continue;
+            }
+            if regex.is_match(line) {
+                findings.push(SecretFinding {
+                    line: line_num + 1,
+                    name,
+                    text: line.trim().to_string(),
+                });
+            }
+        }
+    }
+    Ok(findings)
+}
+
 /// Input validation for security
 pub struct InputValidator;
 
 impl InputValidator {
-    /// Validate file path for security issues
+    /// Validate file path for security issues.
+    ///
+    /// Normalizes `path` and `base_dir` purely lexically (see
+    /// [`crate::path::normalize`]) and checks containment on the
+    /// normalized forms, so a planned output file whose parent doesn't
+    /// exist yet still validates correctly and the check behaves
+    /// identically on Unix and Windows. Use
+    /// [`Self::validate_path_resolving_symlinks`] instead when the target
+    /// is expected to exist and real symlinks must be resolved.
     pub fn validate_path<P: AsRef<Path>>(path: P, base_dir: &Path) -> WhiteoutResult<PathBuf> {
         let path = path.as_ref();
-        
+
         // Check for path traversal attempts
         let path_str = path.to_string_lossy();
         if PATH_TRAVERSAL_PATTERN.is_match(&path_str) {
@@ -35,56 +85,90 @@ impl InputValidator {
                 path: path.to_path_buf(),
             }));
         }
-        
-        // Resolve to canonical path
+
+        // Validate file name before normalizing, same as before. Allow
+        // .gitignore but be cautious about other hidden files.
+        if let Some(file_name) = path.file_name() {
+            let file_name_str = file_name.to_string_lossy();
+            if file_name_str.starts_with('.')
+                && file_name_str != ".gitignore"
+                && !file_name_str.starts_with(".whiteout")
+            {
+                return Err(WhiteoutError::Security(SecurityError::SuspiciousPattern {
+                    pattern: format!("Hidden file: {}", file_name_str),
+                }));
+            }
+        }
+
+        let normalized = crate::path::normalize(path);
+        let normalized_base = crate::path::normalize(base_dir);
+
+        if !crate::path::is_contained_in(&normalized, &normalized_base) {
+            return Err(WhiteoutError::Security(SecurityError::PathTraversal {
+                path: normalized.clone(),
+            }));
+        }
+
+        Ok(normalized)
+    }
+
+    /// Like [`Self::validate_path`], but resolves real symlinks via
+    /// `Path::canonicalize` first. Requires `path` (or its parent, for a
+    /// not-yet-created file) and `base_dir` to already exist on disk.
+    pub fn validate_path_resolving_symlinks<P: AsRef<Path>>(
+        path: P,
+        base_dir: &Path,
+    ) -> WhiteoutResult<PathBuf> {
+        let path = path.as_ref();
+
+        let path_str = path.to_string_lossy();
+        if PATH_TRAVERSAL_PATTERN.is_match(&path_str) {
+            return Err(WhiteoutError::Security(SecurityError::PathTraversal {
+                path: path.to_path_buf(),
+            }));
+        }
+
         let canonical = if path.exists() {
-            path.canonicalize()
-                .map_err(|e| WhiteoutError::Io(e))?
+            path.canonicalize().map_err(WhiteoutError::Io)?
         } else {
-            // For non-existent files, validate parent and construct path
-            let parent = path.parent()
-                .ok_or_else(|| WhiteoutError::InvalidInput(
-                    "Invalid path: no parent directory".to_string()
-                ))?;
-            
+            let parent = path.parent().ok_or_else(|| {
+                WhiteoutError::InvalidInput("Invalid path: no parent directory".to_string())
+            })?;
+
             if !parent.exists() {
-                return Err(WhiteoutError::InvalidInput(
-                    format!("Parent directory does not exist: {:?}", parent)
-                ));
+                return Err(WhiteoutError::InvalidInput(format!(
+                    "Parent directory does not exist: {:?}",
+                    parent
+                )));
             }
-            
-            let parent_canonical = parent.canonicalize()
-                .map_err(|e| WhiteoutError::Io(e))?;
-            
-            let file_name = path.file_name()
-                .ok_or_else(|| WhiteoutError::InvalidInput(
-                    "Invalid path: no file name".to_string()
-                ))?;
-            
-            // Validate file name
+
+            let parent_canonical = parent.canonicalize().map_err(WhiteoutError::Io)?;
+
+            let file_name = path
+                .file_name()
+                .ok_or_else(|| WhiteoutError::InvalidInput("Invalid path: no file name".to_string()))?;
+
             let file_name_str = file_name.to_string_lossy();
-            if file_name_str.starts_with('.') && file_name_str != ".gitignore" {
-                // Allow .gitignore but be cautious about other hidden files
-                if !file_name_str.starts_with(".whiteout") {
-                    return Err(WhiteoutError::Security(SecurityError::SuspiciousPattern {
-                        pattern: format!("Hidden file: {}", file_name_str),
-                    }));
-                }
+            if file_name_str.starts_with('.')
+                && file_name_str != ".gitignore"
+                && !file_name_str.starts_with(".whiteout")
+            {
+                return Err(WhiteoutError::Security(SecurityError::SuspiciousPattern {
+                    pattern: format!("Hidden file: {}", file_name_str),
+                }));
             }
-            
+
             parent_canonical.join(file_name)
         };
-        
-        // Ensure path is within base directory
-        let base_canonical = base_dir.canonicalize()
-            .map_err(|e| WhiteoutError::Io(e))?;
-        
+
+        let base_canonical = base_dir.canonicalize().map_err(WhiteoutError::Io)?;
+
         if !canonical.starts_with(&base_canonical) {
             return Err(WhiteoutError::Security(SecurityError::PathTraversal {
                 path: canonical.clone(),
             }));
         }
-        
+
         Ok(canonical)
     }
     
@@ -320,6 +404,28 @@ mod tests {
         );
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_path_validation_succeeds_for_nonexistent_target() {
+        // Neither the file nor its parent directory exists anywhere on
+        // disk; lexical normalization doesn't need them to.
+        let base = Path::new("/home/user/project");
+        let result = InputValidator::validate_path(
+            Path::new("/home/user/project/not/created/yet/output.txt"),
+            base,
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_path_validation_rejects_dotdot_relative_to_base() {
+        let base = Path::new("/home/user/project");
+        let result = InputValidator::validate_path(
+            Path::new("/home/user/project/subdir/../../outside.txt"),
+            base,
+        );
+        assert!(result.is_err());
+    }
     
     #[test]
     fn test_decoration_validation() {
@@ -0,0 +1,94 @@
+//! Round-trip tests built on `support::GitHarness`, which drives real
+//! `git add`/`commit`/`checkout` (rather than calling `Whiteout::clean`/
+//! `smudge` directly) so a regression in the `.gitattributes`/`git config
+//! filter.*` wiring that `init::handle` sets up would actually be caught.
+
+mod support;
+
+use std::path::Path;
+
+#[test]
+fn test_inline_decoration_round_trips_through_real_git() -> anyhow::Result<()> {
+    let Some(harness) = support::GitHarness::new()? else {
+        eprintln!("skipping: git not found on PATH");
+        return Ok(());
+    };
+
+    harness.write_file(
+        "config.js",
+        "const apiKey = \"sk-live-ROUNDTRIP-SECRET\"; // @whiteout: process.env.API_KEY\n",
+    )?;
+    harness.commit_all("add config")?;
+
+    let committed = harness.committed_content("config.js")?;
+    assert!(!committed.contains("sk-live-ROUNDTRIP-SECRET"));
+    assert!(committed.contains("process.env.API_KEY"));
+
+    harness.checkout("config.js")?;
+    let restored = harness.read_working("config.js")?;
+    assert!(restored.contains("sk-live-ROUNDTRIP-SECRET"));
+
+    Ok(())
+}
+
+#[test]
+fn test_nested_decorations_round_trip_from_fixture() -> anyhow::Result<()> {
+    let Some(harness) =
+        support::GitHarness::from_fixture(Path::new("tests/fixtures/nested_decoration"))?
+    else {
+        eprintln!("skipping: git not found on PATH");
+        return Ok(());
+    };
+
+    harness.commit_all("add nested fixture")?;
+
+    let committed = harness.committed_content("app.js")?;
+    assert!(!committed.contains("sk-proj-NESTED-SECRET-KEY"));
+    assert!(committed.contains("process.env.DB_API_KEY"));
+    assert!(committed.contains("enabled: false"));
+    assert!(committed.contains("verbose: false"));
+
+    harness.checkout("app.js")?;
+    let restored = harness.read_working("app.js")?;
+    assert!(restored.contains("sk-proj-NESTED-SECRET-KEY"));
+    assert!(restored.contains("enabled: true"));
+    assert!(restored.contains("verbose: true"));
+
+    Ok(())
+}
+
+#[test]
+fn test_multiple_partial_decorations_round_trip_from_fixture() -> anyhow::Result<()> {
+    let Some(harness) =
+        support::GitHarness::from_fixture(Path::new("tests/fixtures/multi_partial"))?
+    else {
+        eprintln!("skipping: git not found on PATH");
+        return Ok(());
+    };
+
+    harness.commit_all("add multi-partial fixture")?;
+
+    let committed = harness.committed_content("app.js")?;
+    for secret in [
+        "admin:pass123@dev.localhost:8080",
+        "secret-token@internal.dev",
+        "staging.internal",
+    ] {
+        assert!(!committed.contains(secret), "{} leaked into commit", secret);
+    }
+    for safe in [
+        "api.production.com",
+        "webhook.example.com",
+        "metrics.example.com",
+    ] {
+        assert!(committed.contains(safe), "{} missing from commit", safe);
+    }
+
+    harness.checkout("app.js")?;
+    let restored = harness.read_working("app.js")?;
+    assert!(restored.contains("admin:pass123@dev.localhost:8080"));
+    assert!(restored.contains("secret-token@internal.dev"));
+    assert!(restored.contains("staging.internal"));
+
+    Ok(())
+}
@@ -0,0 +1,203 @@
+//! Shared harness for exercising whiteout's clean/smudge filters through a
+//! *real* Git repository, rather than calling `Whiteout::clean`/`smudge`
+//! directly. The other integration tests in this crate only do the latter,
+//! so they never catch a regression in the `.gitattributes`/
+//! `git config filter.*` wiring that `init::handle` sets up - only `git
+//! add`/`commit`/`checkout` running the filters end to end can.
+//!
+//! Tests opt in with `mod support;` and [`GitHarness::new`] or
+//! [`GitHarness::from_fixture`]. Both return `Ok(None)` when `git` isn't on
+//! `PATH`; callers should skip rather than fail in that case:
+//!
+//! ```ignore
+//! let Some(harness) = support::GitHarness::new()? else {
+//!     eprintln!("skipping: git not found on PATH");
+//!     return Ok(());
+//! };
+//! ```
+#![allow(dead_code)]
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+/// A throwaway Git repository, already `whiteout init`-ed, that real `git`
+/// commands can be run against.
+pub struct GitHarness {
+    temp_dir: TempDir,
+    whiteout_bin: PathBuf,
+}
+
+impl GitHarness {
+    /// Creates an empty repository and runs `whiteout init` in it.
+    pub fn new() -> Result<Option<Self>> {
+        Self::build(None)
+    }
+
+    /// Like [`Self::new`], but first copies every file under `fixture_dir`
+    /// into the repo, so cases like nested or multi-partial decorations can
+    /// be seeded from a fixture directory instead of inline strings.
+    pub fn from_fixture(fixture_dir: &Path) -> Result<Option<Self>> {
+        Self::build(Some(fixture_dir))
+    }
+
+    fn build(fixture_dir: Option<&Path>) -> Result<Option<Self>> {
+        if !git_available() {
+            return Ok(None);
+        }
+
+        let whiteout_bin = build_whiteout_bin()?;
+        let temp_dir = TempDir::new()?;
+        let repo_path = temp_dir.path();
+
+        run_git(repo_path, &["init"])?;
+        run_git(repo_path, &["config", "user.name", "Test User"])?;
+        run_git(repo_path, &["config", "user.email", "test@example.com"])?;
+
+        if let Some(fixture_dir) = fixture_dir {
+            copy_dir_contents(fixture_dir, repo_path)
+                .context("Failed to seed repo from fixture directory")?;
+        }
+
+        let harness = Self { temp_dir, whiteout_bin };
+        let output = harness.whiteout(&["init"])?;
+        if !output.status.success() {
+            anyhow::bail!("whiteout init failed: {}", String::from_utf8_lossy(&output.stderr));
+        }
+
+        Ok(Some(harness))
+    }
+
+    pub fn path(&self) -> &Path {
+        self.temp_dir.path()
+    }
+
+    /// Writes (or overwrites) `rel_path` in the working tree, creating
+    /// parent directories as needed.
+    pub fn write_file(&self, rel_path: &str, content: &str) -> Result<()> {
+        let full_path = self.path().join(rel_path);
+        if let Some(parent) = full_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(full_path, content).context("Failed to write test fixture file")
+    }
+
+    pub fn read_working(&self, rel_path: &str) -> Result<String> {
+        fs::read_to_string(self.path().join(rel_path)).context("Failed to read working tree file")
+    }
+
+    /// Stages and commits every file in the working tree, running the
+    /// `clean` filter on anything matched by `.gitattributes`.
+    pub fn commit_all(&self, message: &str) -> Result<()> {
+        run_git(self.path(), &["add", "-A"])?;
+        run_git(self.path(), &["commit", "-m", message])?;
+        Ok(())
+    }
+
+    /// Reads `rel_path` as it was actually committed at `HEAD`, i.e. the
+    /// output of the `clean` filter, bypassing the working tree entirely.
+    pub fn committed_content(&self, rel_path: &str) -> Result<String> {
+        let output = Command::new("git")
+            .args(["show", &format!("HEAD:{}", rel_path)])
+            .current_dir(self.path())
+            .output()
+            .context("Failed to run git show")?;
+        if !output.status.success() {
+            anyhow::bail!("git show failed: {}", String::from_utf8_lossy(&output.stderr));
+        }
+        String::from_utf8(output.stdout).context("Committed content was not valid UTF-8")
+    }
+
+    /// Deletes `rel_path` from the working tree and restores it via `git
+    /// checkout`, running the `smudge` filter.
+    pub fn checkout(&self, rel_path: &str) -> Result<()> {
+        fs::remove_file(self.path().join(rel_path))
+            .with_context(|| format!("Failed to remove {} before checkout", rel_path))?;
+        run_git(self.path(), &["checkout", rel_path])
+    }
+
+    /// Runs the `whiteout` binary under test with `args`, inside the repo.
+    pub fn whiteout(&self, args: &[&str]) -> Result<Output> {
+        Command::new(&self.whiteout_bin)
+            .args(args)
+            .current_dir(self.path())
+            .output()
+            .with_context(|| format!("Failed to run whiteout {}", args.join(" ")))
+    }
+}
+
+pub fn git_available() -> bool {
+    Command::new("git")
+        .arg("--version")
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+fn run_git(repo_path: &Path, args: &[&str]) -> Result<()> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(repo_path)
+        .output()
+        .with_context(|| format!("Failed to run git {}", args.join(" ")))?;
+    if !output.status.success() {
+        anyhow::bail!("git {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr));
+    }
+    Ok(())
+}
+
+fn copy_dir_contents(src: &Path, dst: &Path) -> Result<()> {
+    for entry in walkdir::WalkDir::new(src).min_depth(1) {
+        let entry = entry.context("Failed to walk fixture directory")?;
+        let relative = entry.path().strip_prefix(src)?;
+        let target = dst.join(relative);
+
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target)?;
+        } else {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Finds (building if necessary) the `whiteout` binary under test, the same
+/// way `end_to_end_git_test.rs` does.
+fn build_whiteout_bin() -> Result<PathBuf> {
+    let candidates = [
+        PathBuf::from("target/release/whiteout"),
+        PathBuf::from("target/debug/whiteout"),
+    ];
+    for candidate in &candidates {
+        if candidate.exists() {
+            return candidate.canonicalize().context("Failed to resolve whiteout binary path");
+        }
+    }
+
+    let output = Command::new("cargo")
+        .args(["build", "--release"])
+        .output()
+        .context("Failed to run cargo build")?;
+    if output.status.success() {
+        return PathBuf::from("target/release/whiteout")
+            .canonicalize()
+            .context("Failed to resolve whiteout binary path");
+    }
+
+    let output = Command::new("cargo")
+        .args(["build"])
+        .output()
+        .context("Failed to run cargo build")?;
+    if !output.status.success() {
+        anyhow::bail!("Failed to build whiteout: {}", String::from_utf8_lossy(&output.stderr));
+    }
+
+    PathBuf::from("target/debug/whiteout")
+        .canonicalize()
+        .context("Failed to resolve whiteout binary path")
+}